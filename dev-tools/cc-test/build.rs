@@ -42,11 +42,19 @@ fn main() {
         .compile("foo");
 
     let compiler = build.get_compiler();
+    assert_ne!(
+        compiler.info().vendor,
+        cc::CompilerVendor::Unknown,
+        "failed to detect compiler vendor: {:?}",
+        compiler.info()
+    );
 
     cc::Build::new()
         .file("src/bar1.c")
         .file("src/bar2.c")
         .include("src/include")
+        .require_compiler_version(cc::CompilerVendor::Gnu, cc::CompilerVersion::new(4, 0, 0))
+        .require_compiler_version(cc::CompilerVendor::Msvc, cc::CompilerVersion::new(19, 0, 0))
         .compile("bar");
 
     let target = std::env::var("TARGET").unwrap();
@@ -17,6 +17,7 @@
 use std::{
     env,
     ffi::{OsStr, OsString},
+    fmt,
     ops::Deref,
     path::{Path, PathBuf},
     process::Command,
@@ -48,7 +49,6 @@ impl TargetArch {
         }
     }
 
-    #[cfg(windows)]
     /// Gets the Visual Studio name for the architecture.
     fn as_vs_arch(&self) -> &'static str {
         match self {
@@ -58,6 +58,16 @@ impl TargetArch {
             Self::Arm => "arm",
         }
     }
+
+    /// Gets the name xwin uses for the architecture in a splat directory.
+    fn as_xwin_arch(&self) -> &'static str {
+        match self {
+            Self::X64 => "x86_64",
+            Self::Arm64 | Self::Arm64ec => "aarch64",
+            Self::X86 => "x86",
+            Self::Arm => "aarch",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -95,6 +105,34 @@ impl From<Env> for PathBuf {
 
 pub trait EnvGetter {
     fn get_env(&self, name: &'static str) -> Option<Env>;
+
+    /// The root of an [xwin] "splat" directory containing the CRT and Windows
+    /// SDK, used to find tools when cross-compiling from a non-Windows host.
+    ///
+    /// Defaults to the value of the `MSVC_SPLAT_DIR` environment variable.
+    ///
+    /// [xwin]: https://github.com/Jake-Shadle/xwin
+    fn msvc_splat_dir(&self) -> Option<PathBuf> {
+        self.get_env("MSVC_SPLAT_DIR").map(PathBuf::from)
+    }
+
+    /// The MSVC toolset version to use, e.g. `14.38` or `14.38.33130`. A
+    /// partial version selects the newest matching toolset.
+    ///
+    /// Defaults to the value of the `VCToolsVersion` environment variable.
+    fn vc_tools_version(&self) -> Option<String> {
+        let version = self.get_env("VCToolsVersion")?;
+        version.to_str().map(ToString::to_string)
+    }
+
+    /// The Windows SDK version to use, e.g. `10.0.22621.0` or `10.0.22621`.
+    /// A partial version selects the newest matching SDK.
+    ///
+    /// Defaults to the value of the `WindowsSDKVersion` environment variable.
+    fn windows_sdk_version(&self) -> Option<String> {
+        let version = self.get_env("WindowsSDKVersion")?;
+        Some(version.to_str()?.trim_end_matches('\\').to_string())
+    }
 }
 
 struct StdEnvGetter;
@@ -133,10 +171,20 @@ impl EnvGetter for StdEnvGetter {
 /// as found in the current `PATH`. If that fails, it will attempt to locate
 /// the newest MSVC toolset in the newest installed version of Visual Studio.
 /// To limit the search to a specific version of the MSVC toolset, set the
-/// VCToolsVersion environment variable to the desired version (e.g. "14.44.35207").
+/// VCToolsVersion environment variable to the desired version (e.g. "14.44.35207"
+/// or "14.44"). Similarly, the WindowsSDKVersion environment variable selects
+/// the Windows SDK (e.g. "10.0.22621.0").
+///
+/// If an [xwin] "splat" directory is configured through the `MSVC_SPLAT_DIR`
+/// environment variable (or [`EnvGetter::msvc_splat_dir`]), the CRT and
+/// Windows SDK are taken from there instead, and the tool is looked up in
+/// `PATH`. On non-Windows hosts `cl.exe`, `link.exe` and `lib.exe` are
+/// substituted by `clang-cl`, `lld-link` and `llvm-lib`.
 ///
 /// Note that this function always returns `None` for non-MSVC targets (if a
 /// full target name was specified).
+///
+/// [xwin]: https://github.com/Jake-Shadle/xwin
 pub fn find(arch_or_target: &str, tool: &str) -> Option<Command> {
     find_tool(arch_or_target, tool).map(|c| c.to_command())
 }
@@ -158,6 +206,62 @@ pub fn find_tool(arch_or_target: &str, tool: &str) -> Option<Tool> {
     find_tool_with_env(full_arch, tool, &StdEnvGetter)
 }
 
+/// Like [`find_tool_with_env`], but reports an error if the MSVC toolset or
+/// Windows SDK version selected through [`EnvGetter::vc_tools_version`] or
+/// [`EnvGetter::windows_sdk_version`] is not installed.
+pub fn try_find_tool_with_env(
+    full_arch: &str,
+    tool: &str,
+    env_getter: &dyn EnvGetter,
+) -> Result<Option<Tool>, VersionNotFound> {
+    if let Some(tool) = find_tool_with_env(full_arch, tool, env_getter) {
+        return Ok(Some(tool));
+    }
+    if let Some(target) = TargetArch::new(full_arch) {
+        if env_getter.msvc_splat_dir().is_none() {
+            check_versions(target, env_getter)?;
+        }
+    }
+    Ok(None)
+}
+
+/// Check that the selected MSVC toolset and Windows SDK versions are installed.
+fn check_versions(target: TargetArch, env_getter: &dyn EnvGetter) -> Result<(), VersionNotFound> {
+    if let Some(requested) = env_getter.vc_tools_version() {
+        check_version("MSVC toolset", requested, || {
+            impl_::vc_tools_versions(target, env_getter)
+        })?;
+    }
+    check_sdk_version(env_getter)
+}
+
+fn check_sdk_version(env_getter: &dyn EnvGetter) -> Result<(), VersionNotFound> {
+    match env_getter.windows_sdk_version() {
+        Some(requested) => check_version("Windows SDK", requested, impl_::windows_sdk_versions),
+        None => Ok(()),
+    }
+}
+
+fn check_version(
+    component: &'static str,
+    requested: String,
+    available: impl FnOnce() -> Vec<String>,
+) -> Result<(), VersionNotFound> {
+    // Installed versions can only be enumerated on Windows.
+    if !cfg!(windows) {
+        return Ok(());
+    }
+    let available = available();
+    match versions::select(&requested, &available) {
+        Some(_) => Ok(()),
+        None => Err(VersionNotFound {
+            component,
+            requested,
+            available,
+        }),
+    }
+}
+
 pub fn find_tool_with_env(full_arch: &str, tool: &str, env_getter: &dyn EnvGetter) -> Option<Tool> {
     // We only need the arch.
     let target = TargetArch::new(full_arch)?;
@@ -174,6 +278,12 @@ pub fn find_tool_with_env(full_arch: &str, tool: &str, env_getter: &dyn EnvGette
         return impl_::find_devenv(target, env_getter);
     }
 
+    // A splat directory only contains the CRT and SDK, all tools (including
+    // clang-cl) come from `PATH`.
+    if let Some(splat) = env_getter.msvc_splat_dir() {
+        return splat::find_tool(&splat, tool, target, env_getter);
+    }
+
     // Clang/LLVM isn't located in the same location as other tools like
     // cl.exe and lib.exe.
     if ["clang", "lldb", "llvm", "ld", "lld"]
@@ -195,6 +305,44 @@ pub fn find_tool_with_env(full_arch: &str, tool: &str, env_getter: &dyn EnvGette
         .or_else(|| impl_::find_msvc_14(tool, target, env_getter))
 }
 
+/// The error returned when a selected MSVC toolset or Windows SDK version is
+/// not installed.
+#[derive(Debug, Clone)]
+pub struct VersionNotFound {
+    component: &'static str,
+    requested: String,
+    available: Vec<String>,
+}
+
+impl VersionNotFound {
+    /// The version that was requested.
+    pub fn requested(&self) -> &str {
+        &self.requested
+    }
+
+    /// The installed versions, newest first.
+    pub fn available(&self) -> &[String] {
+        &self.available
+    }
+}
+
+impl fmt::Display for VersionNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} version {} is not installed",
+            self.component, self.requested
+        )?;
+        if self.available.is_empty() {
+            write!(f, ", and no other versions were found")
+        } else {
+            write!(f, ", available versions: {}", self.available.join(", "))
+        }
+    }
+}
+
+impl std::error::Error for VersionNotFound {}
+
 /// An SDK found by [`find_windows_sdk`]
 #[derive(Default)]
 pub struct Sdk {
@@ -221,6 +369,8 @@ impl Sdk {
     }
 
     /// The Windows SDK version that was found.
+    ///
+    /// This is empty if the version is unknown, e.g. for an xwin splat directory.
     pub fn sdk_version(&self) -> &str {
         &self.version
     }
@@ -231,8 +381,29 @@ impl Sdk {
 /// It's not necessary to call this if using [`find`] or [`find_tool`] as they will populate the relvant
 /// environment variables with the SDK directories.
 pub fn find_windows_sdk(full_arch: &str) -> Option<Sdk> {
-    let target = TargetArch::new(full_arch)?;
-    impl_::get_sdks(target, &StdEnvGetter)
+    try_find_windows_sdk_with_env(full_arch, &StdEnvGetter)
+        .ok()
+        .flatten()
+}
+
+/// Like [`find_windows_sdk`], but with a custom [`EnvGetter`], and reporting
+/// an error if the version selected through [`EnvGetter::windows_sdk_version`]
+/// is not installed.
+pub fn try_find_windows_sdk_with_env(
+    full_arch: &str,
+    env_getter: &dyn EnvGetter,
+) -> Result<Option<Sdk>, VersionNotFound> {
+    let target = match TargetArch::new(full_arch) {
+        Some(target) => target,
+        None => return Ok(None),
+    };
+    if let Some(splat) = env_getter.msvc_splat_dir() {
+        return Ok(splat::get_sdk(&splat, target));
+    }
+    match impl_::get_sdks(target, env_getter) {
+        Some(sdk) => Ok(Some(sdk)),
+        None => check_sdk_version(env_getter).map(|()| None),
+    }
 }
 
 /// A version of Visual Studio
@@ -313,9 +484,378 @@ pub fn find_vs_version() -> Result<VsVers, String> {
 /// find the newest version. While this sort of sorting isn't ideal,  it is
 /// what vcvars does so that's good enough for us.
 ///
+/// If the `WindowsSDKVersion` environment variable is set, only matching
+/// versions are considered.
+///
 /// Returns a pair of (root, version) for the ucrt dir if found
 pub fn get_ucrt_dir() -> Option<(PathBuf, String)> {
-    impl_::get_ucrt_dir()
+    impl_::get_ucrt_dir(&StdEnvGetter)
+}
+
+/// Find the versions of the installed Windows 10 (or later) SDKs, newest first.
+///
+/// Always returns an empty list on non-Windows hosts.
+pub fn find_windows_sdk_versions() -> Vec<String> {
+    impl_::windows_sdk_versions()
+}
+
+/// Find the versions of the installed Universal CRTs, newest first.
+///
+/// Always returns an empty list on non-Windows hosts.
+pub fn find_ucrt_versions() -> Vec<String> {
+    impl_::ucrt_versions()
+}
+
+/// Listing and selecting the versioned directories of MSVC toolsets and
+/// Windows SDKs.
+pub(crate) mod versions {
+    use std::{cmp::Ordering, fs, path::Path};
+
+    /// Compare two versions component-wise, numerically where possible.
+    pub(super) fn compare(a: &str, b: &str) -> Ordering {
+        let mut a = a.split('.');
+        let mut b = b.split('.');
+        loop {
+            match (a.next(), b.next()) {
+                (Some(a), Some(b)) => {
+                    let ordering = match (a.parse::<u32>(), b.parse::<u32>()) {
+                        (Ok(a), Ok(b)) => a.cmp(&b),
+                        _ => a.cmp(b),
+                    };
+                    if ordering != Ordering::Equal {
+                        return ordering;
+                    }
+                }
+                (a, b) => return a.is_some().cmp(&b.is_some()),
+            }
+        }
+    }
+
+    /// Whether `version` matches `requested`, i.e. `requested` is equal to
+    /// `version` or a prefix of its components. `14.3` does not match `14.38`.
+    pub(super) fn matches(requested: &str, version: &str) -> bool {
+        let mut version = version.split('.');
+        requested
+            .split('.')
+            .all(|requested| version.next() == Some(requested))
+    }
+
+    /// List the names of the subdirectories of `dir` that satisfy `is_valid`,
+    /// newest version first.
+    pub(crate) fn list(dir: &Path, is_valid: impl Fn(&str, &Path) -> bool) -> Vec<String> {
+        let mut versions: Vec<String> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let name = entry.file_name().into_string().ok()?;
+                is_valid(&name, &entry.path()).then_some(name)
+            })
+            .collect();
+        versions.sort_by(|a, b| compare(b, a));
+        versions
+    }
+
+    /// Select the newest of `available` (sorted newest first) that matches
+    /// `requested`.
+    pub(super) fn select<'a>(requested: &str, available: &'a [String]) -> Option<&'a String> {
+        available.iter().find(|version| matches(requested, version))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use tempfile::TempDir;
+
+        use super::*;
+
+        fn tree(dirs: &[&str]) -> TempDir {
+            let root = tempfile::tempdir().unwrap();
+            for dir in dirs {
+                fs::create_dir_all(root.path().join(dir)).unwrap();
+            }
+            root
+        }
+
+        #[test]
+        fn compare_versions() {
+            assert_eq!(compare("14.38.33130", "14.4.0"), Ordering::Greater);
+            assert_eq!(compare("10.0.9999.0", "10.0.22621.0"), Ordering::Less);
+            assert_eq!(compare("14.38", "14.38.33130"), Ordering::Less);
+            assert_eq!(compare("14.38.33130", "14.38.33130"), Ordering::Equal);
+        }
+
+        #[test]
+        fn match_versions() {
+            assert!(matches("14.38", "14.38.33130"));
+            assert!(matches("14.38.33130", "14.38.33130"));
+            assert!(!matches("14.3", "14.38.33130"));
+            assert!(!matches("14.38.33130", "14.38"));
+            assert!(matches("10.0.22621", "10.0.22621.0"));
+        }
+
+        #[test]
+        fn toolset_directories() {
+            let root = tree(&[
+                "14.38.33130/bin",
+                "14.38.33135/bin",
+                "14.40.33807/bin",
+                "14.9.0/bin",
+                // Without `bin`, e.g. a left-over from an uninstalled toolset.
+                "14.41.34120",
+            ]);
+            let versions = list(root.path(), |_, path| path.join("bin").is_dir());
+            assert_eq!(
+                versions,
+                ["14.40.33807", "14.38.33135", "14.38.33130", "14.9.0"]
+            );
+
+            assert_eq!(
+                select("14.38", &versions).map(String::as_str),
+                Some("14.38.33135")
+            );
+            assert_eq!(
+                select("14.38.33130", &versions).map(String::as_str),
+                Some("14.38.33130")
+            );
+            assert_eq!(select("14.41", &versions), None);
+        }
+
+        #[test]
+        fn sdk_directories() {
+            let root = tree(&[
+                "10.0.19041.0/um/x64",
+                "10.0.22621.0/um/x64",
+                "10.0.22621.0/ucrt/x64",
+                "10.0.26100.0/ucrt/x64",
+                "wdf",
+            ]);
+            let sdks = list(root.path(), |name, path| {
+                name.starts_with("10.") && path.join("um").is_dir()
+            });
+            assert_eq!(sdks, ["10.0.22621.0", "10.0.19041.0"]);
+            let ucrts = list(root.path(), |name, path| {
+                name.starts_with("10.") && path.join("ucrt").is_dir()
+            });
+            assert_eq!(ucrts, ["10.0.26100.0", "10.0.22621.0"]);
+
+            assert_eq!(
+                select("10.0.22621", &sdks).map(String::as_str),
+                Some("10.0.22621.0")
+            );
+            assert_eq!(select("10.0.26100.0", &sdks), None);
+        }
+    }
+}
+
+/// Support for the "splat" directory layout produced by `xwin splat`:
+///
+/// ```text
+/// crt/include
+/// crt/lib/<arch>
+/// sdk/include/{ucrt,um,shared,winrt,cppwinrt}
+/// sdk/lib/{ucrt,um}/<arch>
+/// ```
+///
+/// `<arch>` is either xwin's naming (`x86_64`, `aarch64`, ...) or, with
+/// `--preserve-ms-arch-notation`, Microsoft's (`x64`, `arm64`, ...).
+mod splat {
+    use std::{
+        env,
+        ffi::OsString,
+        path::{Path, PathBuf},
+    };
+
+    use super::{EnvGetter, Sdk, TargetArch};
+    use crate::Tool;
+
+    /// Find the library directory for the target in `dir`.
+    fn arch_dir(dir: &Path, target: TargetArch) -> Option<PathBuf> {
+        [target.as_xwin_arch(), target.as_vs_arch()]
+            .iter()
+            .map(|arch| dir.join(arch))
+            .find(|dir| dir.is_dir())
+    }
+
+    pub(super) fn get_sdk(root: &Path, target: TargetArch) -> Option<Sdk> {
+        let include = root.join("sdk").join("include");
+        let lib = root.join("sdk").join("lib");
+        if !include.is_dir() {
+            return None;
+        }
+
+        let mut sdk = Sdk::default();
+        sdk.include.extend(
+            ["ucrt", "um", "shared", "winrt", "cppwinrt"]
+                .iter()
+                .map(|dir| include.join(dir))
+                .filter(|dir| dir.is_dir()),
+        );
+        sdk.libs.extend(
+            ["ucrt", "um"]
+                .iter()
+                .filter_map(|dir| arch_dir(&lib.join(dir), target)),
+        );
+        Some(sdk)
+    }
+
+    /// The tool to use in place of an MSVC tool when cross-compiling.
+    fn tool_name(tool: &str) -> &str {
+        if cfg!(windows) {
+            return tool;
+        }
+        match tool {
+            "cl.exe" | "clang-cl.exe" => "clang-cl",
+            "link.exe" | "lld-link.exe" => "lld-link",
+            "lib.exe" | "llvm-lib.exe" => "llvm-lib",
+            tool => tool.strip_suffix(".exe").unwrap_or(tool),
+        }
+    }
+
+    pub(super) fn find_tool(
+        root: &Path,
+        tool: &str,
+        target: TargetArch,
+        env_getter: &dyn EnvGetter,
+    ) -> Option<Tool> {
+        let crt = root.join("crt");
+        let crt_include = crt.join("include");
+        if !crt_include.is_dir() {
+            return None;
+        }
+        let sdk = get_sdk(root, target)?;
+
+        let name = tool_name(tool);
+        let path = env_getter
+            .get_env("PATH")
+            .and_then(|path| {
+                env::split_paths(&path)
+                    .map(|p| p.join(name))
+                    .find(|p| p.exists())
+            })
+            .unwrap_or_else(|| PathBuf::from(name));
+
+        let mut include = vec![crt_include];
+        include.extend(sdk.include);
+        let mut libs: Vec<_> = arch_dir(&crt.join("lib"), target).into_iter().collect();
+        libs.extend(sdk.libs);
+
+        Some(Tool {
+            is_clang_cl: name.contains("clang-cl"),
+            tool: path,
+            env: vec![
+                prepend_env("INCLUDE", include, env_getter),
+                prepend_env("LIB", libs, env_getter),
+            ],
+        })
+    }
+
+    fn prepend_env(
+        env: &'static str,
+        paths: Vec<PathBuf>,
+        env_getter: &dyn EnvGetter,
+    ) -> (OsString, OsString) {
+        let prev = env_getter.get_env(env);
+        let prev = prev.iter().flat_map(|prev| env::split_paths(prev));
+        (
+            env.into(),
+            env::join_paths(paths.into_iter().chain(prev)).unwrap(),
+        )
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::fs;
+
+        use tempfile::TempDir;
+
+        use super::*;
+        use crate::find_tools::Env;
+
+        struct SplatEnv(PathBuf);
+
+        impl EnvGetter for SplatEnv {
+            fn get_env(&self, name: &'static str) -> Option<Env> {
+                match name {
+                    "MSVC_SPLAT_DIR" => Some(Env::Owned(self.0.clone().into())),
+                    _ => None,
+                }
+            }
+        }
+
+        fn fake_splat(arch: &str) -> TempDir {
+            let dir = tempfile::tempdir().unwrap();
+            for subdir in [
+                "crt/include".to_string(),
+                format!("crt/lib/{arch}"),
+                "sdk/include/ucrt".to_string(),
+                "sdk/include/um".to_string(),
+                "sdk/include/shared".to_string(),
+                format!("sdk/lib/ucrt/{arch}"),
+                format!("sdk/lib/um/{arch}"),
+            ] {
+                fs::create_dir_all(dir.path().join(subdir)).unwrap();
+            }
+            dir
+        }
+
+        fn env_paths(tool: &Tool, name: &str) -> Vec<PathBuf> {
+            let (_, value) = tool.env.iter().find(|(k, _)| k == name).unwrap();
+            env::split_paths(value).collect()
+        }
+
+        #[test]
+        fn splat_layout() {
+            let dir = fake_splat("x86_64");
+            let root = dir.path();
+            let tool =
+                crate::find_tool_with_env("x86_64", "cl.exe", &SplatEnv(root.to_owned())).unwrap();
+
+            if !cfg!(windows) {
+                assert!(tool.is_clang_cl());
+                assert_eq!(tool.path(), Path::new("clang-cl"));
+            }
+            assert_eq!(
+                env_paths(&tool, "INCLUDE"),
+                [
+                    root.join("crt/include"),
+                    root.join("sdk/include/ucrt"),
+                    root.join("sdk/include/um"),
+                    root.join("sdk/include/shared"),
+                ]
+            );
+            assert_eq!(
+                env_paths(&tool, "LIB"),
+                [
+                    root.join("crt/lib/x86_64"),
+                    root.join("sdk/lib/ucrt/x86_64"),
+                    root.join("sdk/lib/um/x86_64"),
+                ]
+            );
+
+            let sdk = get_sdk(root, TargetArch::X64).unwrap();
+            assert_eq!(sdk.libs().count(), 2);
+            assert_eq!(sdk.include().count(), 3);
+            assert_eq!(sdk.sdk_version(), "");
+        }
+
+        #[test]
+        fn splat_ms_arch_notation() {
+            let dir = fake_splat("arm64");
+            let root = dir.path();
+            let tool = crate::find_tool_with_env("aarch64", "lib.exe", &SplatEnv(root.to_owned()))
+                .unwrap();
+
+            if !cfg!(windows) {
+                assert!(!tool.is_clang_cl());
+                assert_eq!(tool.path(), Path::new("llvm-lib"));
+            }
+            assert_eq!(env_paths(&tool, "LIB")[0], root.join("crt/lib/arm64"));
+
+            // The x64 libraries are missing.
+            let sdk = get_sdk(root, TargetArch::X64).unwrap();
+            assert_eq!(sdk.libs().count(), 0);
+        }
+    }
 }
 
 /// Windows Implementation.
@@ -342,7 +882,7 @@ mod impl_ {
     use std::sync::atomic::{AtomicBool, Ordering};
     use std::sync::Once;
 
-    use super::{EnvGetter, Sdk, TargetArch};
+    use super::{versions, EnvGetter, Sdk, TargetArch};
     use crate::Tool;
 
     struct MsvcTool {
@@ -898,10 +1438,11 @@ mod impl_ {
     }
 
     fn vs15plus_vc_read_version(dir: &Path, env_getter: &dyn EnvGetter) -> Option<String> {
-        if let Some(version) = env_getter.get_env("VCToolsVersion") {
+        if let Some(requested) = env_getter.vc_tools_version() {
             // Restrict the search to a specific msvc version; if it doesn't exist then
             // our caller will fail to find the tool for this instance and move on.
-            return version.to_str().map(ToString::to_string);
+            let available = vs15plus_vc_versions(dir);
+            return versions::select(&requested, &available).cloned();
         }
 
         // Try to open the default version file.
@@ -928,21 +1469,7 @@ mod impl_ {
             }
             if version_file.is_empty() {
                 // If all else fails, manually search for bin directories.
-                let tools_dir: PathBuf = dir.join(r"VC\Tools\MSVC");
-                return tools_dir
-                    .read_dir()
-                    .ok()?
-                    .filter_map(|file| {
-                        let file = file.ok()?;
-                        let name = file.file_name().into_string().ok()?;
-
-                        file.path().join("bin").exists().then(|| {
-                            let version = parse_version(&name);
-                            (name, version)
-                        })
-                    })
-                    .max_by_key(|(_, version)| *version)
-                    .map(|(name, _)| name);
+                return vs15plus_vc_versions(dir).into_iter().next();
             }
             version_path.push(version_file);
             File::open(version_path).ok()?
@@ -955,6 +1482,25 @@ mod impl_ {
         Some(version)
     }
 
+    /// The MSVC toolsets installed in a VS instance, newest first.
+    fn vs15plus_vc_versions(dir: &Path) -> Vec<String> {
+        versions::list(&dir.join(r"VC\Tools\MSVC"), |_, path| {
+            path.join("bin").exists()
+        })
+    }
+
+    pub(super) fn vc_tools_versions(target: TargetArch, env_getter: &dyn EnvGetter) -> Vec<String> {
+        let mut available: Vec<String> = vs15plus_instances(target, env_getter)
+            .into_iter()
+            .flat_map(|instances| instances.into_iter())
+            .filter_map(|instance| instance.installation_path())
+            .flat_map(|path| vs15plus_vc_versions(&path))
+            .collect();
+        available.sort_by(|a, b| versions::compare(b, a));
+        available.dedup();
+        available
+    }
+
     fn use_spectre_mitigated_libs(env_getter: &dyn EnvGetter) -> bool {
         env_getter
             .get_env("VSCMD_ARG_VCVARS_SPECTRE")
@@ -993,7 +1539,7 @@ mod impl_ {
 
     pub(super) fn get_sdks(target: TargetArch, env_getter: &dyn EnvGetter) -> Option<Sdk> {
         let sub = target.as_vs_arch();
-        let (ucrt, ucrt_version) = get_ucrt_dir()?;
+        let (ucrt, ucrt_version) = get_ucrt_dir(env_getter)?;
 
         let host = match host_arch() {
             X86 => "x86",
@@ -1095,25 +1641,30 @@ mod impl_ {
     // what vcvars does so that's good enough for us.
     //
     // Returns a pair of (root, version) for the ucrt dir if found
-    pub(super) fn get_ucrt_dir() -> Option<(PathBuf, String)> {
+    pub(super) fn get_ucrt_dir(env_getter: &dyn EnvGetter) -> Option<(PathBuf, String)> {
+        let (root, available) = ucrt_root_and_versions()?;
+        let version = match env_getter.windows_sdk_version() {
+            Some(requested) => versions::select(&requested, &available)?,
+            None => available.first()?,
+        };
+        Some((root, version.clone()))
+    }
+
+    /// The root of the Universal CRT and its installed versions, newest first.
+    fn ucrt_root_and_versions() -> Option<(PathBuf, Vec<String>)> {
         let key = r"SOFTWARE\Microsoft\Windows Kits\Installed Roots";
         let key = LOCAL_MACHINE.open(key.as_ref()).ok()?;
-        let root = key.query_str("KitsRoot10").ok()?;
-        let readdir = Path::new(&root).join("lib").read_dir().ok()?;
-        let max_libdir = readdir
-            .filter_map(|dir| dir.ok())
-            .map(|dir| dir.path())
-            .filter(|dir| {
-                dir.components()
-                    .next_back()
-                    .and_then(|c| c.as_os_str().to_str())
-                    .map(|c| c.starts_with("10.") && dir.join("ucrt").is_dir())
-                    .unwrap_or(false)
-            })
-            .max()?;
-        let version = max_libdir.components().next_back().unwrap();
-        let version = version.as_os_str().to_str().unwrap().to_string();
-        Some((root.into(), version))
+        let root = PathBuf::from(key.query_str("KitsRoot10").ok()?);
+        let available = versions::list(&root.join("lib"), |name, dir| {
+            name.starts_with("10.") && dir.join("ucrt").is_dir()
+        });
+        Some((root, available))
+    }
+
+    pub(super) fn ucrt_versions() -> Vec<String> {
+        ucrt_root_and_versions()
+            .map(|(_, available)| available)
+            .unwrap_or_default()
     }
 
     // Vcvars finds the correct version of the Windows 10 SDK by looking
@@ -1128,35 +1679,34 @@ mod impl_ {
     // environment variables set by vcvars to use the environment sdk version
     // if one is already configured.
     fn get_sdk10_dir(env_getter: &dyn EnvGetter) -> Option<(PathBuf, String)> {
-        if let (Some(root), Some(version)) = (
-            env_getter.get_env("WindowsSdkDir"),
-            env_getter
-                .get_env("WindowsSDKVersion")
-                .as_ref()
-                .and_then(|version| version.as_ref().to_str()),
-        ) {
-            return Some((
-                PathBuf::from(root),
-                version.trim_end_matches('\\').to_string(),
-            ));
+        let requested = env_getter.windows_sdk_version();
+        if let (Some(root), Some(version)) = (env_getter.get_env("WindowsSdkDir"), &requested) {
+            return Some((PathBuf::from(root), version.clone()));
         }
 
+        let (root, available) = sdk10_versions()?;
+        let version = match requested {
+            Some(requested) => versions::select(&requested, &available)?,
+            None => available.first()?,
+        };
+        Some((root, version.clone()))
+    }
+
+    /// The root of the Windows 10 SDK and its installed versions, newest first.
+    fn sdk10_versions() -> Option<(PathBuf, Vec<String>)> {
         let key = r"SOFTWARE\Microsoft\Microsoft SDKs\Windows\v10.0";
         let key = LOCAL_MACHINE.open(key.as_ref()).ok()?;
-        let root = key.query_str("InstallationFolder").ok()?;
-        let readdir = Path::new(&root).join("lib").read_dir().ok()?;
-        let mut dirs = readdir
-            .filter_map(|dir| dir.ok())
-            .map(|dir| dir.path())
-            .collect::<Vec<_>>();
-        dirs.sort();
-        let dir = dirs
-            .into_iter()
-            .rev()
-            .find(|dir| dir.join("um").join("x64").join("kernel32.lib").is_file())?;
-        let version = dir.components().next_back().unwrap();
-        let version = version.as_os_str().to_str().unwrap().to_string();
-        Some((root.into(), version))
+        let root = PathBuf::from(key.query_str("InstallationFolder").ok()?);
+        let available = versions::list(&root.join("lib"), |_, dir| {
+            dir.join("um").join("x64").join("kernel32.lib").is_file()
+        });
+        Some((root, available))
+    }
+
+    pub(super) fn windows_sdk_versions() -> Vec<String> {
+        sdk10_versions()
+            .map(|(_, available)| available)
+            .unwrap_or_default()
     }
 
     // Interestingly there are several subdirectories, `win7` `win8` and
@@ -1592,10 +2142,25 @@ mod impl_ {
     }
 
     #[inline(always)]
-    pub(super) fn get_ucrt_dir() -> Option<(PathBuf, String)> {
+    pub(super) fn get_ucrt_dir(_: &dyn EnvGetter) -> Option<(PathBuf, String)> {
         None
     }
 
+    #[inline(always)]
+    pub(super) fn vc_tools_versions(_target: TargetArch, _: &dyn EnvGetter) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[inline(always)]
+    pub(super) fn windows_sdk_versions() -> Vec<String> {
+        Vec::new()
+    }
+
+    #[inline(always)]
+    pub(super) fn ucrt_versions() -> Vec<String> {
+        Vec::new()
+    }
+
     #[inline(always)]
     pub(super) fn get_sdks(_target: TargetArch, _env_getter: &dyn EnvGetter) -> Option<Sdk> {
         None
@@ -0,0 +1,322 @@
+//! Enumeration of the installed Visual Studio instances, for diagnostics.
+
+use std::path::{Path, PathBuf};
+
+use crate::{find_tools::versions, json::Value};
+
+/// A Visual Studio 2017 (or later) installation, see [`find_vs_installations`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VsInstallation {
+    instance_id: String,
+    name: String,
+    version: String,
+    path: PathBuf,
+    product_path: Option<PathBuf>,
+}
+
+impl VsInstallation {
+    /// The unique ID of the instance, e.g. `58104422`.
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// The name of the installation, e.g. `VisualStudio/17.8.3+34330.188`.
+    pub fn installation_name(&self) -> &str {
+        &self.name
+    }
+
+    /// The version of the installation, e.g. `17.8.34330.188`.
+    pub fn installation_version(&self) -> &str {
+        &self.version
+    }
+
+    /// The root directory of the installation.
+    pub fn installation_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The path to the main executable of the product, e.g. `devenv.exe` or
+    /// `LaunchDevCmd.bat` for the Build Tools.
+    pub fn product_path(&self) -> Option<&Path> {
+        self.product_path.as_deref()
+    }
+
+    /// The versions of the MSVC toolsets in this installation, newest first.
+    pub fn msvc_toolsets(&self) -> Vec<String> {
+        let tools = self.path.join("VC").join("Tools").join("MSVC");
+        versions::list(&tools, |_, path| path.join("bin").exists())
+    }
+
+    /// Parse the output of `vswhere -format json`.
+    pub fn parse_vswhere_json(json: &str) -> Result<Vec<Self>, String> {
+        match Value::parse(json)? {
+            Value::Array(instances) => instances
+                .iter()
+                .map(|instance| {
+                    let mut installation = Self::from_json(instance)?;
+                    installation.product_path = instance
+                        .get("productPath")
+                        .and_then(Value::as_str)
+                        .map(PathBuf::from);
+                    Ok(installation)
+                })
+                .collect(),
+            _ => Err("expected an array of instances".to_string()),
+        }
+    }
+
+    /// Parse the `state.json` file that the Visual Studio installer keeps for
+    /// each instance in `%ProgramData%\Microsoft\VisualStudio\Packages\_Instances\<id>`.
+    ///
+    /// `state.json` doesn't always contain the instance ID, in which case it
+    /// is empty; it is also the name of the directory containing the file.
+    pub fn parse_state_json(json: &str) -> Result<Self, String> {
+        let state = Value::parse(json)?;
+        let mut installation = Self::from_json(&state)?;
+        installation.product_path = state
+            .get("launchParams")
+            .and_then(|params| params.get("fileName"))
+            .and_then(Value::as_str)
+            .map(|file| installation.path.join(file));
+        Ok(installation)
+    }
+
+    fn from_json(instance: &Value) -> Result<Self, String> {
+        let get = |key| {
+            instance
+                .get(key)
+                .and_then(Value::as_str)
+                .ok_or_else(|| format!("required property `{key}` not found"))
+        };
+        Ok(Self {
+            instance_id: get("instanceId").unwrap_or_default().to_string(),
+            name: get("installationName")?.to_string(),
+            version: get("installationVersion")?.to_string(),
+            path: get("installationPath")?.into(),
+            product_path: None,
+        })
+    }
+}
+
+/// Find all Visual Studio 2017 (or later) installations.
+///
+/// The instances are queried through the Visual Studio setup COM API, and if
+/// that fails, through `vswhere.exe` or the installer's `state.json` files.
+/// Always returns an empty list on non-Windows hosts.
+pub fn find_vs_installations() -> Vec<VsInstallation> {
+    impl_::find_vs_installations()
+}
+
+#[cfg(windows)]
+mod impl_ {
+    use std::{
+        env, fs,
+        path::{Path, PathBuf},
+        process::{Command, Stdio},
+    };
+
+    use super::VsInstallation;
+    use crate::{
+        com,
+        setup_config::SetupConfiguration,
+        vs_instances::{VsInstance, VsInstances},
+    };
+
+    pub(super) fn find_vs_installations() -> Vec<VsInstallation> {
+        from_com()
+            .or_else(from_vswhere)
+            .or_else(from_state_json)
+            .unwrap_or_default()
+    }
+
+    fn from_com() -> Option<Vec<VsInstallation>> {
+        com::initialize().ok()?;
+        let config = SetupConfiguration::new().ok()?;
+        let instances = VsInstances::ComBased(config.enum_all_instances().ok()?);
+        Some(instances.into_iter().filter_map(from_vs_instance).collect())
+    }
+
+    fn from_vs_instance(instance: VsInstance) -> Option<VsInstallation> {
+        let path = instance.installation_path()?;
+        Some(VsInstallation {
+            instance_id: instance.instance_id().unwrap_or_default().into_owned(),
+            name: instance.installation_name()?.into_owned(),
+            version: instance.installation_version()?.into_owned(),
+            // The COM API returns the product path relative to the installation.
+            product_path: instance.product_path().map(|product| path.join(product)),
+            path,
+        })
+    }
+
+    #[allow(clippy::disallowed_methods)]
+    fn from_vswhere() -> Option<Vec<VsInstallation>> {
+        let program_files =
+            env::var_os("ProgramFiles(x86)").or_else(|| env::var_os("ProgramFiles"))?;
+        let vswhere =
+            Path::new(&program_files).join(r"Microsoft Visual Studio\Installer\vswhere.exe");
+        let output = Command::new(vswhere)
+            .args([
+                "-all",
+                "-products",
+                "*",
+                "-format",
+                "json",
+                "-utf8",
+                "-nologo",
+            ])
+            .stderr(Stdio::inherit())
+            .output()
+            .ok()?;
+        let json = String::from_utf8(output.stdout).ok()?;
+        VsInstallation::parse_vswhere_json(&json).ok()
+    }
+
+    #[allow(clippy::disallowed_methods)]
+    fn from_state_json() -> Option<Vec<VsInstallation>> {
+        let program_data = PathBuf::from(env::var_os("ProgramData")?);
+        let instances = program_data.join(r"Microsoft\VisualStudio\Packages\_Instances");
+        let installations = fs::read_dir(instances)
+            .ok()?
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let json = fs::read_to_string(entry.path().join("state.json")).ok()?;
+                let mut installation = VsInstallation::parse_state_json(&json).ok()?;
+                if installation.instance_id.is_empty() {
+                    installation.instance_id = entry.file_name().into_string().ok()?;
+                }
+                Some(installation)
+            })
+            .collect();
+        Some(installations)
+    }
+}
+
+#[cfg(not(windows))]
+mod impl_ {
+    use super::VsInstallation;
+
+    #[inline(always)]
+    pub(super) fn find_vs_installations() -> Vec<VsInstallation> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::VsInstallation;
+
+    #[test]
+    fn parse_vswhere_json() {
+        let json = r#"[
+  {
+    "instanceId": "58104422",
+    "installDate": "2021-02-21T21:50:33Z",
+    "installationName": "VisualStudio/16.9.2+31112.23",
+    "installationPath": "C:\\Program Files (x86)\\Microsoft Visual Studio\\2019\\BuildTools",
+    "installationVersion": "16.9.31112.23",
+    "productId": "Microsoft.VisualStudio.Product.BuildTools",
+    "productPath": "C:\\Program Files (x86)\\Microsoft Visual Studio\\2019\\BuildTools\\Common7\\Tools\\LaunchDevCmd.bat",
+    "state": 4294967295,
+    "isComplete": true,
+    "isPrerelease": false,
+    "displayName": "Visual Studio Build Tools 2019",
+    "catalog": {
+      "buildBranch": "d16.9",
+      "productDisplayVersion": "16.9.2"
+    },
+    "properties": {
+      "nickname": ""
+    }
+  },
+  {
+    "instanceId": "a1b2c3d4",
+    "installationName": "VisualStudio/17.8.3+34330.188",
+    "installationPath": "C:\\Program Files\\Microsoft Visual Studio\\2022\\Community",
+    "installationVersion": "17.8.34330.188"
+  }
+]"#;
+        let installations = VsInstallation::parse_vswhere_json(json).unwrap();
+        assert_eq!(installations.len(), 2);
+
+        let build_tools = &installations[0];
+        assert_eq!(build_tools.instance_id(), "58104422");
+        assert_eq!(
+            build_tools.installation_name(),
+            "VisualStudio/16.9.2+31112.23"
+        );
+        assert_eq!(build_tools.installation_version(), "16.9.31112.23");
+        assert_eq!(
+            build_tools.installation_path(),
+            Path::new(r"C:\Program Files (x86)\Microsoft Visual Studio\2019\BuildTools")
+        );
+        assert_eq!(
+            build_tools.product_path(),
+            Some(Path::new(
+                r"C:\Program Files (x86)\Microsoft Visual Studio\2019\BuildTools\Common7\Tools\LaunchDevCmd.bat"
+            ))
+        );
+
+        assert_eq!(installations[1].installation_version(), "17.8.34330.188");
+        assert_eq!(installations[1].product_path(), None);
+
+        assert_eq!(VsInstallation::parse_vswhere_json("[]").unwrap(), []);
+    }
+
+    #[test]
+    fn parse_vswhere_json_errors() {
+        assert!(VsInstallation::parse_vswhere_json("").is_err());
+        assert!(VsInstallation::parse_vswhere_json("{}").is_err());
+        let err = VsInstallation::parse_vswhere_json(r#"[{"installationName": "x"}]"#).unwrap_err();
+        assert!(err.contains("installationVersion"), "{err}");
+    }
+
+    #[test]
+    fn parse_state_json() {
+        let json = "\u{feff}{
+  \"installationName\": \"VisualStudio/17.8.3+34330.188\",
+  \"installationPath\": \"C:\\\\Program Files\\\\Microsoft Visual Studio\\\\2022\\\\Community\",
+  \"installationVersion\": \"17.8.34330.188\",
+  \"launchParams\": {
+    \"fileName\": \"Common7\\\\IDE\\\\devenv.exe\",
+    \"arguments\": \"\"
+  },
+  \"catalogInfo\": {
+    \"productDisplayVersion\": \"17.8.3\"
+  },
+  \"selectedPackages\": [
+    { \"id\": \"Microsoft.VisualStudio.Component.VC.Tools.x86.x64\" }
+  ]
+}";
+        let installation = VsInstallation::parse_state_json(json).unwrap();
+        assert_eq!(installation.instance_id(), "");
+        assert_eq!(
+            installation.installation_name(),
+            "VisualStudio/17.8.3+34330.188"
+        );
+        assert_eq!(installation.installation_version(), "17.8.34330.188");
+        let path = Path::new(r"C:\Program Files\Microsoft Visual Studio\2022\Community");
+        assert_eq!(installation.installation_path(), path);
+        assert_eq!(
+            installation.product_path(),
+            Some(path.join(r"Common7\IDE\devenv.exe").as_path())
+        );
+    }
+
+    #[test]
+    fn msvc_toolsets() {
+        let root = tempfile::tempdir().unwrap();
+        for dir in ["14.38.33130/bin", "14.40.33807/bin", "14.39.33519"] {
+            std::fs::create_dir_all(root.path().join("VC/Tools/MSVC").join(dir)).unwrap();
+        }
+        let installation = VsInstallation {
+            instance_id: String::new(),
+            name: String::new(),
+            version: String::new(),
+            path: root.path().to_owned(),
+            product_path: None,
+        };
+        assert_eq!(installation.msvc_toolsets(), ["14.40.33807", "14.38.33130"]);
+    }
+}
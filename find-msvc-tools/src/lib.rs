@@ -8,6 +8,11 @@ pub use find_tools::*;
 mod tool;
 pub use tool::*;
 
+mod installations;
+pub use installations::*;
+
+mod json;
+
 #[cfg(windows)]
 #[doc(hidden)]
 pub mod windows_link;
@@ -12,6 +12,17 @@ pub enum VsInstance {
 }
 
 impl VsInstance {
+    pub fn instance_id(&self) -> Option<Cow<'_, str>> {
+        match self {
+            VsInstance::Com(s) => s
+                .instance_id()
+                .ok()
+                .and_then(|s| s.into_string().ok())
+                .map(Cow::from),
+            VsInstance::Vswhere(v) => v.map.get("instanceId").map(Cow::from),
+        }
+    }
+
     pub fn installation_name(&self) -> Option<Cow<'_, str>> {
         match self {
             VsInstance::Com(s) => s
@@ -40,6 +51,13 @@ impl VsInstance {
             VsInstance::Vswhere(v) => v.map.get("installationVersion").map(Cow::from),
         }
     }
+
+    pub fn product_path(&self) -> Option<PathBuf> {
+        match self {
+            VsInstance::Com(s) => s.product_path().ok().map(PathBuf::from),
+            VsInstance::Vswhere(v) => v.map.get("productPath").map(PathBuf::from),
+        }
+    }
 }
 
 pub enum VsInstances {
@@ -0,0 +1,197 @@
+//! Discovery of an installed Android NDK, see [`Build::android_api_level`].
+//!
+//! [`Build::android_api_level`]: crate::Build::android_api_level
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{check_exe, json::Value, Error, ErrorKind};
+
+/// An Android NDK installation.
+#[derive(Debug)]
+pub(crate) struct AndroidNdk {
+    /// The `Pkg.Revision` from `source.properties`, e.g. `26.1.10909125`.
+    pub(crate) version: String,
+    /// The `toolchains/llvm/prebuilt/<host>/bin` directory.
+    pub(crate) bin: PathBuf,
+    /// The lowest API level supported by the NDK.
+    pub(crate) min_api_level: u32,
+    /// The highest API level supported by the NDK, if known.
+    pub(crate) max_api_level: Option<u32>,
+}
+
+impl AndroidNdk {
+    pub(crate) fn from_root(root: &Path) -> Result<Self, Error> {
+        let properties = root.join("source.properties");
+        let properties = fs::read_to_string(&properties).map_err(|e| {
+            Error::new(
+                ErrorKind::IOError,
+                format!(
+                    "failed to read {} (is {} an Android NDK?): {e}",
+                    properties.display(),
+                    root.display()
+                ),
+            )
+        })?;
+        let version = parse_source_properties(&properties).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidArgument,
+                format!(
+                    "no `Pkg.Revision` in {}",
+                    root.join("source.properties").display()
+                ),
+            )
+        })?;
+        let major = version
+            .split('.')
+            .next()
+            .and_then(|major| major.parse::<u32>().ok())
+            .unwrap_or(0);
+
+        let prebuilt = root.join("toolchains").join("llvm").join("prebuilt");
+        let host_dir = prebuilt.join(host_tag());
+        let host_dir = if host_dir.is_dir() {
+            host_dir
+        } else {
+            // Fall back to the only available host, if any.
+            let mut hosts = fs::read_dir(&prebuilt)
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|path| path.is_dir());
+            match (hosts.next(), hosts.next()) {
+                (Some(host_dir), None) => host_dir,
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::ToolNotFound,
+                        format!(
+                            "Android NDK {version} at {} has no toolchain for host `{}`",
+                            root.display(),
+                            host_tag()
+                        ),
+                    ))
+                }
+            }
+        };
+
+        let (min_api_level, max_api_level) =
+            match fs::read_to_string(root.join("meta").join("platforms.json")) {
+                Ok(platforms) => {
+                    let platforms = Value::parse(&platforms).unwrap_or(Value::Null);
+                    let min = api_level(&platforms, "min");
+                    (min.unwrap_or(21), api_level(&platforms, "max"))
+                }
+                // NDKs before r21 don't have `meta/platforms.json`. NDK r16
+                // through r23 support API level 16, later ones 21.
+                Err(_) => (if major < 24 { 16 } else { 21 }, None),
+            };
+
+        Ok(Self {
+            version,
+            bin: host_dir.join("bin"),
+            min_api_level,
+            max_api_level,
+        })
+    }
+
+    /// The clang target triple for the given Rust target and API level,
+    /// e.g. `armv7a-linux-androideabi24`.
+    pub(crate) fn clang_target(raw_target: &str, arch: &str, api_level: u32) -> String {
+        let triple = match arch {
+            "arm" => "armv7a-linux-androideabi",
+            "aarch64" => "aarch64-linux-android",
+            "x86" => "i686-linux-android",
+            "x86_64" => "x86_64-linux-android",
+            "riscv64" => "riscv64-linux-android",
+            _ => raw_target,
+        };
+        format!("{triple}{api_level}")
+    }
+
+    /// The lowest API level that can be used for the given architecture.
+    pub(crate) fn min_api_level_for_arch(&self, arch: &str) -> u32 {
+        let arch_min = match arch {
+            // 64-bit architectures were added in Android 5.0.
+            "aarch64" | "x86_64" => 21,
+            "riscv64" => 35,
+            _ => 0,
+        };
+        self.min_api_level.max(arch_min)
+    }
+
+    pub(crate) fn tool(&self, name: &str) -> PathBuf {
+        let path = self.bin.join(name);
+        check_exe(path.clone()).unwrap_or(path)
+    }
+}
+
+/// The name of the directory in `toolchains/llvm/prebuilt` for the current host.
+fn host_tag() -> &'static str {
+    if cfg!(windows) {
+        "windows-x86_64"
+    } else if cfg!(target_os = "macos") {
+        // The macOS toolchain is a universal binary, but is still named `x86_64`.
+        "darwin-x86_64"
+    } else if cfg!(target_arch = "aarch64") {
+        "linux-aarch64"
+    } else {
+        "linux-x86_64"
+    }
+}
+
+fn parse_source_properties(properties: &str) -> Option<String> {
+    properties.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim() == "Pkg.Revision").then(|| value.trim().to_string())
+    })
+}
+
+/// The API level under `key` in `meta/platforms.json`.
+fn api_level(platforms: &Value, key: &str) -> Option<u32> {
+    match platforms.get(key)? {
+        Value::Number(level) => level.parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_properties() {
+        let properties = "Pkg.Desc = Android NDK\nPkg.Revision = 26.1.10909125\nPkg.BaseRevision = 26.1.10909125\n";
+        assert_eq!(
+            parse_source_properties(properties).as_deref(),
+            Some("26.1.10909125")
+        );
+        assert_eq!(parse_source_properties("Pkg.Desc = foo"), None);
+    }
+
+    #[test]
+    fn platforms_json() {
+        let json = "{\n  \"min\": 21,\n  \"max\": 34,\n  \"aliases\": {\n    \"20\": 19\n  }\n}\n";
+        let platforms = Value::parse(json).unwrap();
+        assert_eq!(api_level(&platforms, "min"), Some(21));
+        assert_eq!(api_level(&platforms, "max"), Some(34));
+        assert_eq!(api_level(&platforms, "other"), None);
+    }
+
+    #[test]
+    fn clang_target() {
+        assert_eq!(
+            AndroidNdk::clang_target("armv7-linux-androideabi", "arm", 24),
+            "armv7a-linux-androideabi24"
+        );
+        assert_eq!(
+            AndroidNdk::clang_target("thumbv7neon-linux-androideabi", "arm", 21),
+            "armv7a-linux-androideabi21"
+        );
+        assert_eq!(
+            AndroidNdk::clang_target("aarch64-linux-android", "aarch64", 21),
+            "aarch64-linux-android21"
+        );
+    }
+}
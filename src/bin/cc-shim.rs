@@ -5,7 +5,7 @@
 #![allow(clippy::disallowed_methods)]
 
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{self, prelude::*};
 use std::path::PathBuf;
 use std::process::ExitCode;
@@ -96,6 +96,31 @@ fn main() -> ExitCode {
         }
     }
 
+    // Emulate GCC's scanning for C++ modules, for the tests of `cpp_modules`.
+    if let Some(deps_file) = args.clone().find_map(|a| a.strip_prefix("-fdeps-file=")) {
+        let src = args
+            .clone()
+            .skip_while(|a| *a != "c++")
+            .nth(1)
+            .expect("no source to scan");
+        let source = fs::read_to_string(src).unwrap_or_default();
+        let mut provides = String::new();
+        let mut requires = Vec::new();
+        for line in source.lines() {
+            let line = line.trim().trim_end_matches(';');
+            if let Some(module) = line.strip_prefix("export module ") {
+                provides = format!(r#""provides": [{{"logical-name": "{module}"}}], "#);
+            } else if let Some(module) = line.strip_prefix("import ") {
+                requires.push(format!(r#"{{"logical-name": "{module}"}}"#));
+            }
+        }
+        let json = format!(
+            r#"{{"version": 1, "revision": 0, "rules": [{{{provides}"requires": [{}]}}]}}"#,
+            requires.join(", ")
+        );
+        fs::write(deps_file, json).unwrap();
+    }
+
     // Allow tests to make the shim fail when a specific arg is present.
     if let Ok(fail_arg) = env::var("CC_SHIM_FAIL_IF_ARG") {
         if args.any(|a| a == &fail_arg) {
@@ -6,7 +6,7 @@ use std::{
     ffi::OsString,
     fmt::Display,
     fs,
-    hash::Hasher,
+    hash::{Hash, Hasher},
     io::{self, Read, Write},
     path::Path,
     process::{Child, ChildStderr, Command, Output, Stdio},
@@ -16,7 +16,7 @@ use std::{
     },
 };
 
-use crate::{utilities::cargo_env_var_os, Error, ErrorKind, Object};
+use crate::{file_options::SourceFile, utilities::cargo_env_var_os, Error, ErrorKind, Object};
 
 #[derive(Clone, Debug)]
 pub(crate) struct CargoOutput {
@@ -280,9 +280,13 @@ fn wait_on_child(
 
 /// Find the destination object path for each file in the input source files,
 /// and store them in the output Object.
-pub(crate) fn objects_from_files(files: &[Arc<Path>], dst: &Path) -> Result<Vec<Object>, Error> {
+pub(crate) fn objects_from_files(files: &[SourceFile], dst: &Path) -> Result<Vec<Object>, Error> {
     let mut objects = Vec::with_capacity(files.len());
-    for file in files {
+    for SourceFile {
+        path: file,
+        options,
+    } in files
+    {
         let basename = file
             .file_name()
             .ok_or_else(|| {
@@ -319,6 +323,10 @@ pub(crate) fn objects_from_files(files: &[Arc<Path>], dst: &Path) -> Result<Vec<
         if let Some(extension) = file.extension() {
             hasher.write(extension.to_string_lossy().as_bytes());
         }
+        // Files compiled with different options must not share an object.
+        if let Some(options) = options {
+            options.hash(&mut hasher);
+        }
 
         let obj = dst
             .join(format!("{:016x}-{}", hasher.finish(), basename))
@@ -334,7 +342,7 @@ pub(crate) fn objects_from_files(files: &[Arc<Path>], dst: &Path) -> Result<Vec<
             }
         };
 
-        objects.push(Object::new(file.to_path_buf(), obj));
+        objects.push(Object::new(file.to_path_buf(), obj, options.clone()));
     }
 
     Ok(objects)
@@ -345,6 +353,16 @@ pub(crate) fn run(cmd: &mut Command, cargo_output: &CargoOutput) -> Result<(), E
     wait_on_child(cmd, &mut child, cargo_output)
 }
 
+/// Like [`run`], but with the output of the command going to `stdout`.
+pub(crate) fn run_with_stdout(
+    cmd: &mut Command,
+    cargo_output: &CargoOutput,
+    stdout: Stdio,
+) -> Result<(), Error> {
+    let mut child = spawn_with_stdout(cmd, cargo_output, stdout)?;
+    wait_on_child(cmd, &mut child, cargo_output)
+}
+
 /// Like [`run`], but stderr is only forwarded as `cargo:warning=` when the
 /// command succeeds. On failure, stderr is silently discarded.
 ///
@@ -421,6 +439,15 @@ pub(crate) fn run_output(cmd: &mut Command, cargo_output: &CargoOutput) -> Resul
 }
 
 pub(crate) fn spawn(cmd: &mut Command, cargo_output: &CargoOutput) -> Result<Child, Error> {
+    spawn_with_stdout(cmd, cargo_output, cargo_output.stdio_for_output())
+}
+
+/// Like [`spawn`], but with the output of the command going to `stdout`.
+pub(crate) fn spawn_with_stdout(
+    cmd: &mut Command,
+    cargo_output: &CargoOutput,
+    stdout: Stdio,
+) -> Result<Child, Error> {
     struct ResetStderr<'cmd>(&'cmd mut Command);
 
     impl Drop for ResetStderr<'_> {
@@ -437,7 +464,7 @@ pub(crate) fn spawn(cmd: &mut Command, cargo_output: &CargoOutput) -> Result<Chi
     let child = cmd
         .0
         .stderr(cargo_output.stdio_for_warnings())
-        .stdout(cargo_output.stdio_for_output())
+        .stdout(stdout)
         .spawn();
     match child {
         Ok(child) => Ok(child),
@@ -465,12 +492,17 @@ pub(crate) struct CmdAddOutputFileArgs {
     pub(crate) msvc: bool,
     pub(crate) clang: bool,
     pub(crate) gnu: bool,
+    pub(crate) ti: bool,
     pub(crate) is_asm: bool,
     pub(crate) is_arm: bool,
 }
 
 pub(crate) fn command_add_output_file(cmd: &mut Command, dst: &Path, args: CmdAddOutputFileArgs) {
-    if args.is_assembler_msvc
+    if args.ti {
+        let mut s = OsString::from("--output_file=");
+        s.push(dst);
+        cmd.arg(s);
+    } else if args.is_assembler_msvc
         || !(!args.msvc || args.clang || args.gnu || args.cuda || (args.is_asm && args.is_arm))
     {
         let mut s = OsString::from("-Fo");
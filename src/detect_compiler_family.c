@@ -13,3 +13,134 @@
 #ifdef __VXWORKS__
 #pragma message "VxWorks"
 #endif
+
+#ifdef __NVCOMPILER
+#pragma message "nvhpc"
+#endif
+
+#ifdef __TI_COMPILER_VERSION__
+#pragma message "TI"
+#endif
+
+// The following lines are parsed from the preprocessed output to determine the
+// vendor, version and target of the compiler, see `CompilerInfo`.
+
+#ifdef __clang__
+cc_rs_clang_version __clang_major__ __clang_minor__ __clang_patchlevel__
+#endif
+
+#ifdef __apple_build_version__
+cc_rs_apple_clang
+#endif
+
+#ifdef __INTEL_LLVM_COMPILER
+cc_rs_intel_llvm_compiler __INTEL_LLVM_COMPILER
+#endif
+
+#ifdef __ARMCC_VERSION
+cc_rs_armcc_version __ARMCC_VERSION
+#endif
+
+#ifdef __NVCOMPILER
+cc_rs_nvcompiler_version __NVCOMPILER_MAJOR__ __NVCOMPILER_MINOR__ __NVCOMPILER_PATCHLEVEL__
+#endif
+
+#ifdef __TI_COMPILER_VERSION__
+cc_rs_ti_compiler_version __TI_COMPILER_VERSION__
+#endif
+
+#ifdef __GNUC__
+cc_rs_gnuc_version __GNUC__ __GNUC_MINOR__ __GNUC_PATCHLEVEL__
+#endif
+
+#ifdef _MSC_FULL_VER
+cc_rs_msc_full_ver _MSC_FULL_VER
+#endif
+
+#if defined(_M_ARM64EC)
+cc_rs_msvc_arch_arm64ec
+#elif defined(_M_ARM64)
+cc_rs_msvc_arch_aarch64
+#elif defined(_M_X64)
+cc_rs_msvc_arch_x86_64
+#elif defined(_M_IX86)
+cc_rs_msvc_arch_i686
+#elif defined(_M_ARM)
+cc_rs_msvc_arch_thumbv7a
+#endif
+
+// The default target of the other compilers, assembled in the style of
+// `-dumpmachine` by `CompilerInfo::from_preprocessed`.
+
+#if defined(__x86_64__)
+cc_rs_arch_x86_64
+#elif defined(__i386__)
+cc_rs_arch_i686
+#elif defined(__aarch64__)
+cc_rs_arch_aarch64
+#elif defined(__arm__)
+cc_rs_arch_arm
+#elif defined(__riscv) && __riscv_xlen == 64
+cc_rs_arch_riscv64
+#elif defined(__riscv)
+cc_rs_arch_riscv32
+#elif defined(__powerpc64__) && defined(__LITTLE_ENDIAN__)
+cc_rs_arch_powerpc64le
+#elif defined(__powerpc64__)
+cc_rs_arch_powerpc64
+#elif defined(__powerpc__)
+cc_rs_arch_powerpc
+#elif defined(__s390x__)
+cc_rs_arch_s390x
+#elif defined(__loongarch64)
+cc_rs_arch_loongarch64
+#elif defined(__mips64) && defined(__MIPSEL__)
+cc_rs_arch_mips64el
+#elif defined(__mips64)
+cc_rs_arch_mips64
+#elif defined(__mips__) && defined(__MIPSEL__)
+cc_rs_arch_mipsel
+#elif defined(__mips__)
+cc_rs_arch_mips
+#elif defined(__wasm32__)
+cc_rs_arch_wasm32
+#elif defined(__wasm64__)
+cc_rs_arch_wasm64
+#endif
+
+#if defined(__arm__) && defined(__ARM_PCS_VFP)
+cc_rs_arm_hard_float
+#endif
+
+// The C library is only known from its headers: glibc defines `__GLIBC__` in
+// <features.h>, whereas musl doesn't define any macro for itself.
+#if defined(__linux__) && !defined(__ANDROID__) && defined(__has_include)
+#if __has_include(<features.h>)
+#include <features.h>
+#define CC_RS_HAS_FEATURES_H
+#endif
+#endif
+
+#if defined(__APPLE__)
+cc_rs_os_apple_darwin
+#elif defined(__ANDROID__)
+cc_rs_os_linux_android
+#elif defined(__linux__) && defined(CC_RS_HAS_FEATURES_H) && !defined(__GLIBC__)
+cc_rs_os_linux_musl
+#elif defined(__linux__)
+cc_rs_os_linux_gnu
+#elif defined(__MINGW32__)
+cc_rs_os_w64_mingw32
+#elif defined(__FreeBSD__)
+cc_rs_os_unknown_freebsd
+#elif defined(__NetBSD__)
+cc_rs_os_unknown_netbsd
+#elif defined(__OpenBSD__)
+cc_rs_os_unknown_openbsd
+#elif defined(__wasi__)
+cc_rs_os_unknown_wasi
+#elif defined(__EMSCRIPTEN__)
+cc_rs_os_unknown_emscripten
+#elif !defined(_WIN32)
+cc_rs_os_none_elf
+#endif
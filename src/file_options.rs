@@ -0,0 +1,121 @@
+//! Options for individual source files, see [`Build::file_with_options`].
+//!
+//! [`Build::file_with_options`]: crate::Build::file_with_options
+
+use std::{
+    ffi::{OsStr, OsString},
+    path::Path,
+    sync::Arc,
+};
+
+use crate::tool::Tool;
+
+/// Extra flags, definitions and include directories for a single source
+/// file, on top of those of the [`Build`](crate::Build) it's part of.
+///
+/// Files assembled with NASM or the MSVC assemblers get these options too,
+/// and removed flags apply to the [assembler flags](crate::Build::asm_flag)
+/// of the build as well.
+///
+/// # Example
+///
+/// ```no_run
+/// let mut simd = cc::FileOptions::new();
+/// simd.flag("-mavx2").define("USE_AVX2", None);
+///
+/// let mut generated = cc::FileOptions::new();
+/// generated.flag("-w").remove_flag("-Wall");
+///
+/// cc::Build::new()
+///     .file("src/foo.c")
+///     .file_with_options("src/kernel.c", &simd)
+///     .file_with_options("src/generated.c", &generated)
+///     .compile("foo");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FileOptions {
+    flags: Vec<Arc<OsStr>>,
+    removed_flags: Vec<Arc<OsStr>>,
+    definitions: Vec<(Arc<str>, Option<Arc<str>>)>,
+    include_directories: Vec<Arc<Path>>,
+}
+
+impl FileOptions {
+    /// Construct a new, empty set of options.
+    pub fn new() -> FileOptions {
+        FileOptions::default()
+    }
+
+    /// Add a flag, passed after the flags of the build.
+    pub fn flag(&mut self, flag: impl AsRef<OsStr>) -> &mut FileOptions {
+        self.flags.push(flag.as_ref().into());
+        self
+    }
+
+    /// Remove a flag that the build would otherwise pass, including default
+    /// flags such as `-O2` or `-Wall`.
+    pub fn remove_flag(&mut self, flag: impl AsRef<OsStr>) -> &mut FileOptions {
+        self.removed_flags.push(flag.as_ref().into());
+        self
+    }
+
+    /// Specify a `-D` variable with an optional value, see
+    /// [`Build::define`](crate::Build::define).
+    pub fn define<'a, V: Into<Option<&'a str>>>(&mut self, var: &str, val: V) -> &mut FileOptions {
+        self.definitions
+            .push((var.into(), val.into().map(Into::into)));
+        self
+    }
+
+    /// Add a directory to the `-I` include path, searched after those of the
+    /// build.
+    pub fn include<P: AsRef<Path>>(&mut self, dir: P) -> &mut FileOptions {
+        self.include_directories.push(dir.as_ref().into());
+        self
+    }
+
+    /// Whether the build's `flag` is to be removed for the file.
+    pub(crate) fn removes(&self, flag: &OsStr) -> bool {
+        self.removed_flags.iter().any(|removed| **removed == *flag)
+    }
+
+    pub(crate) fn flags(&self) -> impl Iterator<Item = &OsStr> {
+        self.flags.iter().map(|flag| &**flag)
+    }
+
+    pub(crate) fn definitions(&self) -> &[(Arc<str>, Option<Arc<str>>)] {
+        &self.definitions
+    }
+
+    pub(crate) fn include_directories(&self) -> &[Arc<Path>] {
+        &self.include_directories
+    }
+
+    /// Add the options to the compiler invocation for the file.
+    pub(crate) fn apply(&self, cmd: &mut Tool) {
+        for flag in &self.removed_flags {
+            cmd.remove_arg((**flag).into());
+        }
+        for directory in &self.include_directories {
+            cmd.args.push(cmd.family.include_flag().into());
+            cmd.args.push(directory.as_os_str().into());
+        }
+        for flag in &self.flags {
+            cmd.push_cc_arg((**flag).into());
+        }
+        for (key, value) in &self.definitions {
+            let define: OsString = match value {
+                Some(value) => format!("-D{key}={value}").into(),
+                None => format!("-D{key}").into(),
+            };
+            cmd.args.push(define);
+        }
+    }
+}
+
+/// A file to compile, along with its options.
+#[derive(Clone, Debug)]
+pub(crate) struct SourceFile {
+    pub(crate) path: Arc<Path>,
+    pub(crate) options: Option<Arc<FileOptions>>,
+}
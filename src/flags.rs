@@ -341,7 +341,7 @@ impl<'this> RustcCodegenFlags<'this> {
                     }
                 }
             }
-            ToolFamily::Gnu => {}
+            ToolFamily::Gnu | ToolFamily::NvHpc | ToolFamily::Ti => {}
             ToolFamily::Msvc { .. } => {
                 // https://learn.microsoft.com/en-us/cpp/build/reference/guard-enable-control-flow-guard
                 if let Some(value) = self.control_flow_guard {
@@ -0,0 +1,190 @@
+//! Flags for freestanding (bare-metal and kernel) code, see
+//! [`Build::freestanding`].
+//!
+//! [`Build::freestanding`]: crate::Build::freestanding
+
+use std::ffi::OsString;
+
+use crate::{target::TargetInfo, tool::ToolFamily};
+
+/// The C library to compile freestanding code against, see
+/// [`Build::c_library`](crate::Build::c_library).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CLibrary {
+    /// No C library, only the headers that come with the compiler, such as
+    /// `<stdint.h>` and `<stddef.h>`, are available.
+    #[default]
+    None,
+    /// [Newlib](https://sourceware.org/newlib/), the default C library of
+    /// most bare-metal GCC toolchains.
+    Newlib,
+    /// The size-optimized variant of Newlib, `newlib-nano`.
+    NewlibNano,
+    /// [picolibc](https://github.com/picolibc/picolibc).
+    Picolibc,
+}
+
+/// The flags for freestanding code compiled with a compiler of `family` for
+/// `target`, against `libc`.
+pub(crate) fn flags(family: ToolFamily, target: &TargetInfo<'_>, libc: CLibrary) -> Vec<OsString> {
+    let mut flags: Vec<OsString> = Vec::new();
+    match family {
+        ToolFamily::Gnu | ToolFamily::Clang { .. } => {
+            flags.push("-ffreestanding".into());
+            flags.push("-fno-builtin".into());
+            flags.push("-fno-stack-protector".into());
+        }
+        ToolFamily::Msvc { .. } => {
+            // Don't check for buffer overruns, which needs the CRT, and
+            // don't reference the default CRT from the object files.
+            flags.push("-GS-".into());
+            flags.push("-Zl".into());
+            return flags;
+        }
+        ToolFamily::NvHpc | ToolFamily::Ti => return flags,
+    }
+
+    // GCC derives the floating point ABI and FPU from the target in
+    // `add_default_flags` already, Clang only gets the triple.
+    if matches!(family, ToolFamily::Clang { .. }) {
+        if target.arch == "arm" && target.os == "none" {
+            if target.abi == "eabihf" {
+                flags.push("-mfloat-abi=hard".into());
+                if let Some(fpu) = arm_fpu(target.full_arch) {
+                    flags.push(format!("-mfpu={fpu}").into());
+                }
+            } else {
+                flags.push("-mfloat-abi=soft".into());
+            }
+        }
+        if target.arch == "riscv32" || target.arch == "riscv64" {
+            // The `32imac`/`64gc`/... part.
+            let extensions = &target.full_arch[5..];
+            flags.push(format!("-march=rv{extensions}").into());
+            flags.push(format!("-mabi={}", riscv_abi(extensions)).into());
+        }
+    }
+
+    match (libc, family) {
+        (CLibrary::None, ToolFamily::Gnu) => flags.push("-nostdlib".into()),
+        // Clang warns about `-nostdlib` when only compiling, but unlike GCC
+        // can leave out the C library's headers while keeping its own.
+        (CLibrary::None, _) => flags.push("-nostdlibinc".into()),
+        // Clang doesn't support spec files, the C library is found in the
+        // sysroot instead.
+        (_, ToolFamily::Clang { .. }) => {}
+        (CLibrary::Newlib, _) => flags.push("--specs=nosys.specs".into()),
+        (CLibrary::NewlibNano, _) => {
+            flags.push("--specs=nano.specs".into());
+            flags.push("--specs=nosys.specs".into());
+        }
+        (CLibrary::Picolibc, _) => flags.push("--specs=picolibc.specs".into()),
+    }
+    flags
+}
+
+/// The lowest common denominator FPU of the Arm bare-metal `eabihf` targets,
+/// the same as in `add_default_flags` for GCC.
+fn arm_fpu(full_arch: &str) -> Option<&'static str> {
+    let fpu = if full_arch.starts_with("thumbv7em") {
+        "fpv4-sp-d16"
+    } else if full_arch.starts_with("thumbv8m.main") {
+        "fpv5-sp-d16"
+    } else if full_arch.starts_with("armv8r") || full_arch.starts_with("thumbv8r") {
+        "fp-armv8"
+    } else if full_arch.starts_with("armv7")
+        || full_arch.starts_with("armebv7")
+        || full_arch.starts_with("thumbv7")
+    {
+        "vfpv3-d16"
+    } else if full_arch.starts_with("armv6") || full_arch.starts_with("thumbv6") {
+        "vfpv2"
+    } else {
+        return None;
+    };
+    Some(fpu)
+}
+
+/// The RISC-V ABI matching the floating point extensions, e.g. `ilp32f` for
+/// `32imafc`.
+fn riscv_abi(extensions: &str) -> String {
+    // Only the single-letter extensions before any `_zba`-style ones count.
+    let single = extensions.split('_').next().unwrap_or_default();
+    let base = if single.starts_with("64") {
+        "lp64"
+    } else if single.contains('e') {
+        "ilp32e"
+    } else {
+        "ilp32"
+    };
+    let float = if single.contains('g') || single.contains('d') {
+        "d"
+    } else if single.contains('f') {
+        "f"
+    } else {
+        ""
+    };
+    format!("{base}{float}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{arm_fpu, flags, riscv_abi, CLibrary};
+    use crate::{target::TargetInfo, tool::ToolFamily};
+
+    fn flags_for(family: ToolFamily, target: &str, libc: CLibrary) -> Vec<String> {
+        let target = TargetInfo::from_rustc_target(target).unwrap();
+        flags(family, &target, libc)
+            .into_iter()
+            .map(|flag| flag.into_string().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn freestanding_flags() {
+        let clang = ToolFamily::Clang { zig_cc: false };
+        assert_eq!(
+            flags_for(clang, "thumbv7em-none-eabihf", CLibrary::None),
+            [
+                "-ffreestanding",
+                "-fno-builtin",
+                "-fno-stack-protector",
+                "-mfloat-abi=hard",
+                "-mfpu=fpv4-sp-d16",
+                "-nostdlibinc",
+            ]
+        );
+        assert_eq!(
+            flags_for(clang, "riscv32imafc-unknown-none-elf", CLibrary::Picolibc)[3..],
+            ["-march=rv32imafc", "-mabi=ilp32f"]
+        );
+        assert_eq!(
+            flags_for(ToolFamily::Gnu, "thumbv6m-none-eabi", CLibrary::NewlibNano)[3..],
+            ["--specs=nano.specs", "--specs=nosys.specs"]
+        );
+        assert_eq!(
+            flags_for(ToolFamily::Gnu, "x86_64-unknown-none", CLibrary::None)[3..],
+            ["-nostdlib"]
+        );
+        assert_eq!(
+            flags_for(
+                ToolFamily::Msvc { clang_cl: false },
+                "x86_64-pc-windows-msvc",
+                CLibrary::None
+            ),
+            ["-GS-", "-Zl"]
+        );
+    }
+
+    #[test]
+    fn float_abis() {
+        assert_eq!(arm_fpu("thumbv8m.main"), Some("fpv5-sp-d16"));
+        assert_eq!(arm_fpu("armv7r"), Some("vfpv3-d16"));
+        assert_eq!(arm_fpu("armv6"), Some("vfpv2"));
+        assert_eq!(riscv_abi("32imac"), "ilp32");
+        assert_eq!(riscv_abi("32e"), "ilp32e");
+        assert_eq!(riscv_abi("64gc"), "lp64d");
+        assert_eq!(riscv_abi("32imc_zba"), "ilp32");
+    }
+}
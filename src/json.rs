@@ -0,0 +1,261 @@
+//! A minimal JSON parser, for the few JSON files cc reads: the Android NDK's
+//! `platforms.json`, custom target specs and the P1689 module dependency
+//! files of C++ compilers.
+
+use std::{iter::Peekable, str::Chars};
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    /// Numbers are kept as written, to be parsed as needed.
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub(crate) fn parse(input: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: input.chars().peekable(),
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        match parser.chars.next() {
+            Some(c) => Err(format!("unexpected `{c}` after JSON value")),
+            None => Ok(value),
+        }
+    }
+
+    /// Get the value of `key`, if this is an object.
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The elements if this is an array, or none otherwise.
+    pub(crate) fn elements(&self) -> &[Value] {
+        match self {
+            Value::Array(elements) => elements,
+            _ => &[],
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected `{expected}`, found `{c}`")),
+            None => Err(format!("expected `{expected}`, found end of input")),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Value) -> Result<Value, String> {
+        for expected in keyword.chars() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('n') => self.keyword("null", Value::Null),
+            Some('t') => self.keyword("true", Value::Bool(true)),
+            Some('f') => self.keyword("false", Value::Bool(false)),
+            Some('"') => self.string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => self.object(),
+            Some(c) if *c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) => Err(format!("unexpected `{c}`")),
+            None => Err("unexpected end of input".to_string()),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let mut number = String::new();
+        number.extend(self.chars.next_if_eq(&'-'));
+        // No leading zeros.
+        if self.chars.next_if_eq(&'0').is_some() {
+            number.push('0');
+        } else {
+            self.digits(&mut number)?;
+        }
+        if self.chars.next_if_eq(&'.').is_some() {
+            number.push('.');
+            self.digits(&mut number)?;
+        }
+        if let Some(e) = self.chars.next_if(|c| matches!(c, 'e' | 'E')) {
+            number.push(e);
+            number.extend(self.chars.next_if(|c| matches!(c, '+' | '-')));
+            self.digits(&mut number)?;
+        }
+        Ok(Value::Number(number))
+    }
+
+    /// Append one or more digits to `number`.
+    fn digits(&mut self, number: &mut String) -> Result<(), String> {
+        let len = number.len();
+        number.extend(std::iter::from_fn(|| {
+            self.chars.next_if(char::is_ascii_digit)
+        }));
+        if number.len() == len {
+            return Err("expected a digit in number".to_string());
+        }
+        Ok(())
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => match self.chars.next() {
+                    Some('"') => s.push('"'),
+                    Some('\\') => s.push('\\'),
+                    Some('/') => s.push('/'),
+                    Some('b') => s.push('\u{8}'),
+                    Some('f') => s.push('\u{c}'),
+                    Some('n') => s.push('\n'),
+                    Some('r') => s.push('\r'),
+                    Some('t') => s.push('\t'),
+                    Some('u') => {
+                        let mut code = self.hex4()?;
+                        // Combine UTF-16 surrogate pairs.
+                        if (0xd800..0xdc00).contains(&code) {
+                            self.expect('\\')?;
+                            self.expect('u')?;
+                            let low = self.hex4()?;
+                            code = 0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00));
+                        }
+                        s.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    Some(c) => return Err(format!("invalid escape `\\{c}`")),
+                    None => return Err("unterminated string".to_string()),
+                },
+                Some(c) => s.push(c),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .chars
+                .next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or("invalid `\\u` escape")?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&']').is_some() {
+            return Ok(Value::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(values)),
+                _ => return Err("expected `,` or `]` in array".to_string()),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut entries = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&'}').is_some() {
+            return Ok(Value::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            entries.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Value::Object(entries)),
+                _ => return Err("expected `,` or `}` in object".to_string()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Value;
+
+    #[test]
+    fn parse_values() {
+        let value = Value::parse(
+            r#"{ "a": [1, -2.5e3, 0.5E+1, true, false, null], "b": { "c": "d\\e\"\u00e9\ud83d\ude00" }, "e": {} }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            value.get("a"),
+            Some(&Value::Array(vec![
+                Value::Number("1".to_string()),
+                Value::Number("-2.5e3".to_string()),
+                Value::Number("0.5E+1".to_string()),
+                Value::Bool(true),
+                Value::Bool(false),
+                Value::Null,
+            ]))
+        );
+        assert_eq!(
+            value
+                .get("b")
+                .and_then(|b| b.get("c"))
+                .and_then(Value::as_str),
+            Some("d\\e\"\u{e9}\u{1f600}")
+        );
+        assert_eq!(value.get("e"), Some(&Value::Object(Vec::new())));
+        assert_eq!(value.get("f"), None);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Value::parse("").is_err());
+        assert!(Value::parse("[1, 2").is_err());
+        assert!(Value::parse(r#"{"a" 1}"#).is_err());
+        assert!(Value::parse(r#""unterminated"#).is_err());
+        assert!(Value::parse("[] []").is_err());
+        assert!(Value::parse("-").is_err());
+        assert!(Value::parse("01").is_err());
+        assert!(Value::parse("1.").is_err());
+        assert!(Value::parse("1e").is_err());
+        assert!(Value::parse("1-2").is_err());
+    }
+}
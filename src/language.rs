@@ -0,0 +1,103 @@
+//! Classifying source files by language, see [`Build::mixed_languages`].
+//!
+//! [`Build::mixed_languages`]: crate::Build::mixed_languages
+
+use std::path::Path;
+
+/// The language of a source file, derived from its extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Language {
+    /// C, `.c` files.
+    C,
+    /// C++, `.cc`, `.cpp`, `.cxx`, `.c++`, `.cp` and `.C` files, as well as
+    /// the module interface units `.cppm`, `.ixx`, `.mpp`, `.cxxm` and `.ccm`.
+    Cpp,
+    /// Objective-C, `.m` files.
+    ObjC,
+    /// Objective-C++, `.mm` and `.M` files.
+    ObjCpp,
+    /// CUDA C++, `.cu` files, compiled with NVCC.
+    Cuda,
+    /// Assembly, `.s`, `.S`, `.sx`, `.asm` and `.nasm` files.
+    Asm,
+    /// Fortran, `.f`, `.for`, `.f77`, `.f90`, `.f95`, `.f03` and `.f08`
+    /// files, compiled with the Fortran compiler (`FC`). Files with an upper
+    /// case extension like `.F90` are preprocessed.
+    Fortran,
+}
+
+impl Language {
+    /// The language of `file`, or `None` if the extension isn't known.
+    pub fn from_path(file: &Path) -> Option<Self> {
+        let language = match file.extension()?.to_str()? {
+            "c" => Language::C,
+            "cc" | "cpp" | "cxx" | "c++" | "cp" | "C" | "CPP" => Language::Cpp,
+            "cppm" | "ixx" | "mpp" | "cxxm" | "ccm" => Language::Cpp,
+            "m" => Language::ObjC,
+            "mm" | "M" => Language::ObjCpp,
+            "cu" => Language::Cuda,
+            "s" | "S" | "sx" | "asm" | "ASM" | "nasm" => Language::Asm,
+            "f" | "for" | "f77" | "f90" | "f95" | "f03" | "f08" | "F" | "FOR" | "F77" | "F90"
+            | "F95" | "F03" | "F08" => Language::Fortran,
+            _ => return None,
+        };
+        Some(language)
+    }
+
+    /// Whether the language is compiled with the C++ compiler and links the
+    /// C++ standard library.
+    pub(crate) fn is_cpp(self) -> bool {
+        matches!(self, Language::Cpp | Language::ObjCpp | Language::Cuda)
+    }
+
+    /// Whether the language is Objective-C or Objective-C++.
+    pub(crate) fn is_objc(self) -> bool {
+        matches!(self, Language::ObjC | Language::ObjCpp)
+    }
+}
+
+/// The Objective-C runtime, see [`Build::objc_runtime`].
+///
+/// [`Build::objc_runtime`]: crate::Build::objc_runtime
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ObjcRuntime {
+    /// Apple's runtime, the only one on Apple targets.
+    Apple,
+    /// The GNU runtime: `libobjc2` from `GNUstep` with Clang, or
+    /// GCC's own `libobjc` with GCC.
+    GnuStep,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::Language;
+
+    #[test]
+    fn from_path() {
+        for (file, language) in [
+            ("foo.c", Some(Language::C)),
+            ("src/foo.cpp", Some(Language::Cpp)),
+            ("foo.cc", Some(Language::Cpp)),
+            ("foo.C", Some(Language::Cpp)),
+            ("foo.cppm", Some(Language::Cpp)),
+            ("foo.m", Some(Language::ObjC)),
+            ("foo.mm", Some(Language::ObjCpp)),
+            ("foo.cu", Some(Language::Cuda)),
+            ("foo.S", Some(Language::Asm)),
+            ("foo.asm", Some(Language::Asm)),
+            ("foo.f90", Some(Language::Fortran)),
+            ("foo.F", Some(Language::Fortran)),
+            ("foo.h", None),
+            ("foo", None),
+        ] {
+            assert_eq!(Language::from_path(Path::new(file)), language, "{file}");
+        }
+        assert!(Language::Cuda.is_cpp());
+        assert!(!Language::ObjC.is_cpp());
+        assert!(Language::ObjCpp.is_objc() && !Language::C.is_objc());
+    }
+}
@@ -79,6 +79,26 @@
 //!   For other custom `CC` wrapper, just set `CC_KNOWN_WRAPPER_CUSTOM`
 //!   to the custom wrapper used in `CC`.
 //! * `AR` - the `ar` (archiver) executable to use to build the static library.
+//! * `CARGO_TARGET_<TRIPLE>_LINKER` and `RUSTC_LINKER` - if neither `CC`/`CXX` nor
+//!   `AR` are set and Cargo is configured to link with a gcc/clang-style driver,
+//!   such as `aarch64-none-linux-gnu-gcc`, the compiler and archiver next to it
+//!   are used, such as `aarch64-none-linux-gnu-g++` and `aarch64-none-linux-gnu-ar`.
+//! * `CC_TOOLCHAIN_FILE` - path to a toolchain file that configures the compiler,
+//!   archiver and flags in one place, see [`Build::toolchain_file`].
+//! * `SYSROOT_<target>` - the sysroot to compile against, see [`Build::sysroot`].
+//! * `CC_USE_ZIG` - if set, use `zig cc` as the compiler, see [`Build::zig`].
+//! * `WASI_SDK_PATH` - the [WASI SDK](https://github.com/WebAssembly/wasi-sdk) to take
+//!   the compiler, sysroot and archiver from when targeting WASI.
+//! * `EMSDK` - the [Emscripten SDK](https://emscripten.org) to take `emcc`, `em++` and
+//!   `emar` from when targeting Emscripten.
+//! * `MSVC_SPLAT_DIR` - an [xwin](https://github.com/Jake-Shadle/xwin) splat directory to
+//!   take the CRT and Windows SDK from when targeting MSVC, e.g. with `clang-cl` from a
+//!   non-Windows host.
+//! * `RUST_TARGET_PATH` - the directories to look for custom target JSON specs in, as
+//!   with `rustc`. The spec's `llvm-target`, `cpu`, `features` and `relocation-model`
+//!   are used for the compiler's target flags. `TARGET` may also be the path to a spec.
+//! * `CC_SANITY_CHECK` - if set, check that the compiler can produce objects for the target
+//!   before compiling anything, see [`Build::sanity_check`].
 //! * `CRATE_CC_NO_DEFAULTS` - the default compiler flags may cause conflicts in
 //!   some cross compiling scenarios. Setting this variable
 //!   will disable the generation of default compiler
@@ -226,6 +246,25 @@
 //!     .compile("bar");
 //! ```
 //!
+//! # Fortran support
+//!
+//! Fortran sources, such as `.f`, `.f90` and `.F90` files, are compiled with
+//! `gfortran` or the compiler set in the `FC` environment variable, which can
+//! also be `flang`. Sources with an upper case extension are preprocessed, with
+//! the include directories and definitions of the `Build`. The flags are taken
+//! from `FFLAGS` and [`Build::language_flag`] rather than [`Build::flag`], and
+//! the Fortran runtime is linked: `gfortran` for gfortran, and for flang
+//! `flang_rt.runtime` if the driver finds it, or otherwise `FortranRuntime`.
+//! To link something else, disable [`Build::cargo_metadata`] and link the
+//! library yourself.
+//!
+//! ```rust,no_run
+//! cc::Build::new()
+//!     .file("src/dgemm.f")
+//!     .file("src/solver.F90")
+//!     .compile("solver");
+//! ```
+//!
 //! # Speed up compilation with sccache
 //!
 //! `cc-rs` does not handle incremental compilation like `make` or `ninja`. It
@@ -258,6 +297,7 @@ use shlex::Shlex;
 mod parallel;
 
 mod target;
+pub use self::target::Target;
 use self::target::*;
 
 /// A helper module to looking for windows-specific tools:
@@ -343,8 +383,8 @@ mod command_helpers;
 use command_helpers::*;
 
 mod tool;
-pub use tool::Tool;
 use tool::{CompilerFamilyLookupCache, ToolFamily};
+pub use tool::{CompilerInfo, CompilerVendor, CompilerVersion, Tool};
 
 mod tempfile;
 
@@ -354,6 +394,32 @@ use utilities::*;
 mod flags;
 use flags::*;
 
+mod json;
+
+mod toolchain_file;
+use toolchain_file::ToolchainFile;
+
+mod android_ndk;
+use android_ndk::AndroidNdk;
+mod wasm_sdk;
+use wasm_sdk::{Emsdk, WasiSdk};
+mod linker_driver;
+mod sanity_check;
+use linker_driver::LinkerDriver;
+mod freestanding;
+pub use freestanding::CLibrary;
+mod language;
+pub use language::{Language, ObjcRuntime};
+mod file_options;
+pub use file_options::FileOptions;
+mod multiversion;
+use file_options::SourceFile;
+pub use multiversion::Multiversion;
+mod modules;
+use modules::{ModuleDeps, ScanOutput};
+mod pch;
+use pch::PrecompiledHeader;
+
 #[derive(Debug, Eq, PartialEq, Hash)]
 struct CompilerFlag {
     compiler: Box<Path>,
@@ -371,7 +437,10 @@ struct BuildCache {
     apple_versions_cache: RwLock<HashMap<Box<str>, Arc<str>>>,
     cached_compiler_family: RwLock<CompilerFamilyLookupCache>,
     known_flag_support_status_cache: RwLock<HashMap<CompilerFlag, bool>>,
+    toolchain_files: RwLock<HashMap<Box<Path>, Arc<ToolchainFile>>>,
+    target_specs: RwLock<HashMap<Box<Path>, Arc<TargetSpec>>>,
     target_info_parser: target::TargetInfoParser,
+    host: OnceLock<Result<Box<str>, Error>>,
 }
 
 /// A builder for compilation of a native library.
@@ -388,14 +457,16 @@ pub struct Build {
     flags_supported: Vec<Arc<OsStr>>,
     ar_flags: Vec<Arc<OsStr>>,
     asm_flags: Vec<Arc<OsStr>>,
+    nasm: bool,
     no_default_flags: bool,
-    files: Vec<Arc<Path>>,
+    files: Vec<SourceFile>,
     cpp: bool,
     cpp_link_stdlib: Option<Option<Arc<str>>>,
     cpp_link_stdlib_static: bool,
     cpp_set_stdlib: Option<Arc<str>>,
     cuda: bool,
     cudart: Option<Arc<str>>,
+    fortran: bool,
     ccbin: bool,
     std: Option<Arc<str>>,
     target: Option<Arc<str>>,
@@ -411,6 +482,9 @@ pub struct Build {
     compiler: Option<Arc<Path>>,
     archiver: Option<Arc<Path>>,
     ranlib: Option<Arc<Path>>,
+    toolchain_file: Option<Arc<Path>>,
+    sysroot: Option<Arc<Path>>,
+    required_compiler_versions: Vec<(CompilerVendor, CompilerVersion)>,
     cargo_output: CargoOutput,
     link_lib_modifiers: Vec<Arc<OsStr>>,
     pic: Option<bool>,
@@ -427,6 +501,21 @@ pub struct Build {
     inherit_rustflags: bool,
     inherit_trim_paths: bool,
     prefer_clang_cl_over_msvc: bool,
+    zig: Option<bool>,
+    android_api_level: Option<u32>,
+    sanity_check: Option<bool>,
+    for_host: bool,
+    freestanding: bool,
+    c_library: CLibrary,
+    mixed_languages: bool,
+    language_std: Vec<(Language, Arc<str>)>,
+    language_flags: Vec<(Language, Arc<OsStr>)>,
+    objc_arc: Option<bool>,
+    objc_exceptions: Option<bool>,
+    objc_runtime: Option<ObjcRuntime>,
+    multiversions: Vec<Arc<Multiversion>>,
+    precompiled_header: Option<Arc<Path>>,
+    cpp_modules: bool,
 }
 
 /// Represents the types of errors that may occur while using cc-rs.
@@ -455,6 +544,10 @@ enum ErrorKind {
     JobserverHelpThreadError,
     /// `cc` has been disabled by an environment variable.
     Disabled,
+    /// The compiler is older than required by [`Build::require_compiler_version`].
+    UnsupportedCompilerVersion,
+    /// The compiler failed the check enabled by [`Build::sanity_check`].
+    SanityCheckFailed,
 }
 
 /// Represents an internal error that occurred, with an explanation.
@@ -496,12 +589,13 @@ impl std::error::Error for Error {}
 struct Object {
     src: PathBuf,
     dst: PathBuf,
+    options: Option<Arc<FileOptions>>,
 }
 
 impl Object {
     /// Create a new source file -> object file pair.
-    fn new(src: PathBuf, dst: PathBuf) -> Object {
-        Object { src, dst }
+    fn new(src: PathBuf, dst: PathBuf, options: Option<Arc<FileOptions>>) -> Object {
+        Object { src, dst, options }
     }
 }
 
@@ -521,6 +615,7 @@ impl Build {
             flags_supported: Vec::new(),
             ar_flags: Vec::new(),
             asm_flags: Vec::new(),
+            nasm: false,
             no_default_flags: false,
             files: Vec::new(),
             shared_flag: None,
@@ -531,6 +626,7 @@ impl Build {
             cpp_set_stdlib: None,
             cuda: false,
             cudart: None,
+            fortran: false,
             ccbin: true,
             std: None,
             target: None,
@@ -543,6 +639,9 @@ impl Build {
             compiler: None,
             archiver: None,
             ranlib: None,
+            toolchain_file: None,
+            sysroot: None,
+            required_compiler_versions: Vec::new(),
             cargo_output: CargoOutput::new(),
             link_lib_modifiers: Vec::new(),
             pic: None,
@@ -557,6 +656,21 @@ impl Build {
             inherit_rustflags: true,
             inherit_trim_paths: true,
             prefer_clang_cl_over_msvc: false,
+            zig: None,
+            android_api_level: None,
+            sanity_check: None,
+            for_host: false,
+            freestanding: false,
+            c_library: CLibrary::None,
+            mixed_languages: false,
+            language_std: Vec::new(),
+            language_flags: Vec::new(),
+            objc_arc: None,
+            objc_exceptions: None,
+            objc_runtime: None,
+            multiversions: Vec::new(),
+            precompiled_header: None,
+            cpp_modules: false,
         }
     }
 
@@ -715,8 +829,8 @@ impl Build {
 
     /// Add a flag that will only be used with assembly files.
     ///
-    /// The flag will be applied to input files with either a `.s` or
-    /// `.asm` extension (case insensitive).
+    /// The flag will be applied to input files with either a `.s`, `.asm`
+    /// or `.nasm` extension (case insensitive).
     ///
     /// # Example
     ///
@@ -732,6 +846,36 @@ impl Build {
         self
     }
 
+    /// Assemble `.asm` files with NASM rather than the C compiler, or MASM on
+    /// MSVC targets.
+    ///
+    /// Files with a `.nasm` extension are always assembled with NASM. The
+    /// assembler is taken from the `NASM` environment variable, with the
+    /// usual target-specific variants, and defaults to `nasm`; YASM can be
+    /// used by pointing it to `yasm`. The output format is derived from the
+    /// target, and the include directories, definitions and [`asm_flag`]s
+    /// are passed along.
+    ///
+    /// Only `x86` and `x86_64` targets are supported.
+    ///
+    /// [`asm_flag`]: Build::asm_flag
+    ///
+    /// This option defaults to `false`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// cc::Build::new()
+    ///     .nasm(true)
+    ///     .define("ARCH_X86_64", "1")
+    ///     .file("src/x86/mc.asm")
+    ///     .compile("foo");
+    /// ```
+    pub fn nasm(&mut self, nasm: bool) -> &mut Build {
+        self.nasm = nasm;
+        self
+    }
+
     /// Add an arbitrary flag to the invocation of the compiler if it supports it
     ///
     /// # Example
@@ -839,7 +983,94 @@ impl Build {
 
     /// Add a file which will be compiled
     pub fn file<P: AsRef<Path>>(&mut self, p: P) -> &mut Build {
-        self.files.push(p.as_ref().into());
+        self.files.push(SourceFile {
+            path: p.as_ref().into(),
+            options: None,
+        });
+        self
+    }
+
+    /// Add a file which will be compiled with extra flags, definitions and
+    /// include directories, or without some of the flags of the build, see
+    /// [`FileOptions`].
+    ///
+    /// The options are part of the name of the object file, so the same file
+    /// can be added several times with different options, e.g. to compile a
+    /// SIMD kernel once for each instruction set.
+    pub fn file_with_options<P: AsRef<Path>>(&mut self, p: P, options: &FileOptions) -> &mut Build {
+        self.files.push(SourceFile {
+            path: p.as_ref().into(),
+            options: Some(Arc::new(options.clone())),
+        });
+        self
+    }
+
+    /// Add a file which will be compiled once for each of several instruction
+    /// set variants, optionally along with a dispatcher between them, see
+    /// [`Multiversion`].
+    pub fn multiversion(&mut self, multiversion: &Multiversion) -> &mut Build {
+        self.multiversions.push(Arc::new(multiversion.clone()));
+        self
+    }
+
+    /// Configures a header to precompile once and use for every file, which
+    /// saves re-parsing large headers such as those of Qt or Boost in each
+    /// file.
+    ///
+    /// The header is precompiled into `OUT_DIR` before any file is compiled,
+    /// into a `.gch` file for GCC and a `.pch` file for Clang and MSVC, and
+    /// then included in every file as if with `-include`. The header doesn't
+    /// have to be included by the files themselves.
+    ///
+    /// With [`Build::mixed_languages`], the header is precompiled as C++ if
+    /// there are C++ files and only used for those, otherwise as C. It's never
+    /// used for assembly files, and not at all by compilers without support
+    /// for precompiled headers, such as NVCC. Files with their own flags, such
+    /// as those added with [`Build::file_with_options`] or the variants of a
+    /// [`Multiversion`], include the header without the precompiled version.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// cc::Build::new()
+    ///     .cpp(true)
+    ///     .file("src/wrapper.cpp")
+    ///     .precompiled_header("src/qt_all.h")
+    ///     .compile("wrapper");
+    /// ```
+    pub fn precompiled_header<P: AsRef<Path>>(&mut self, header: P) -> &mut Build {
+        self.precompiled_header = Some(header.as_ref().into());
+        self
+    }
+
+    /// Configures whether C++ files may use C++20 modules.
+    ///
+    /// Before compiling, each C++ file is scanned for the modules it provides
+    /// and imports, with `clang-scan-deps` for Clang, `-fdeps-format=p1689r5`
+    /// for GCC and `/scanDependencies` for MSVC. The files are then compiled
+    /// in dependency order, also in parallel, with the built module
+    /// interfaces passed to the files importing them. Module interface units
+    /// can use the `.cppm`, `.ixx`, `.mpp`, `.cxxm` and `.ccm` extensions.
+    ///
+    /// This needs Clang 17, GCC 14 or Visual Studio 2022 or later, and the
+    /// C++20 standard or later, see [`Build::std`]. Modules that no file
+    /// provides, such as `std`, are left to the compiler.
+    ///
+    /// This option defaults to `false`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// cc::Build::new()
+    ///     .cpp(true)
+    ///     .std("c++20")
+    ///     .cpp_modules(true)
+    ///     .file("src/math.cppm")
+    ///     .file("src/bridge.cpp")
+    ///     .compile("bridge");
+    /// ```
+    pub fn cpp_modules(&mut self, cpp_modules: bool) -> &mut Build {
+        self.cpp_modules = cpp_modules;
         self
     }
 
@@ -857,7 +1088,7 @@ impl Build {
 
     /// Get the files which will be compiled
     pub fn get_files(&self) -> impl Iterator<Item = &Path> {
-        self.files.iter().map(AsRef::as_ref)
+        self.files.iter().map(|file| &*file.path)
     }
 
     /// Set C++ support.
@@ -870,6 +1101,9 @@ impl Build {
     /// 2. Else if the `CXXSTDLIB` environment variable is set, use its value.
     /// 3. Else the default is `c++` for OS X and BSDs, `c++_shared` for Android,
     ///    `None` for MSVC and `stdc++` for anything else.
+    ///
+    /// See [`Build::mixed_languages`] to compile both C and C++ files in one
+    /// `Build`.
     pub fn cpp(&mut self, cpp: bool) -> &mut Build {
         self.cpp = cpp;
         self
@@ -898,10 +1132,11 @@ impl Build {
     ///
     /// This option mimics the `--cudart` NVCC command-line option. Just like
     /// the original it accepts `{none|shared|static}`, with default being
-    /// `static`. The method has to be invoked after `.cuda(true)`, or not
-    /// at all, if the default is right for the project.
+    /// `static`. The method has to be invoked after `.cuda(true)` or
+    /// `.mixed_languages(true)`, or not at all, if the default is right for
+    /// the project.
     pub fn cudart(&mut self, cudart: &str) -> &mut Build {
-        if self.cuda {
+        if self.cuda || self.mixed_languages {
             self.cudart = Some(cudart.into());
         }
         self
@@ -950,6 +1185,119 @@ impl Build {
         self
     }
 
+    /// Compile each source file as the language its extension says it is,
+    /// see [`Language::from_path`], instead of as C or C++ depending on
+    /// [`Build::cpp`].
+    ///
+    /// This lets a single `Build` compile a library out of both C and C++
+    /// sources, sharing the include directories, definitions and other
+    /// options. C, Objective-C and assembly files are compiled with the C
+    /// compiler (`CC`) and `CFLAGS`, C++ and Objective-C++ files with the C++
+    /// compiler (`CXX`) and `CXXFLAGS`, and CUDA files with NVCC. Files with
+    /// an unknown extension are compiled according to [`Build::cpp`] as
+    /// usual.
+    ///
+    /// Everything is archived into one library, and the C++ standard library
+    /// is only linked if there are C++, Objective-C++ or CUDA files. The
+    /// `std` and flags of each language can be set with
+    /// [`Build::language_std`] and [`Build::language_flag`].
+    ///
+    /// This option defaults to `false`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// cc::Build::new()
+    ///     .mixed_languages(true)
+    ///     .file("src/foo.c")
+    ///     .file("src/bar.cpp")
+    ///     .language_std(cc::Language::C, "c11")
+    ///     .language_std(cc::Language::Cpp, "c++17")
+    ///     .compile("foo");
+    /// ```
+    pub fn mixed_languages(&mut self, mixed_languages: bool) -> &mut Build {
+        self.mixed_languages = mixed_languages;
+        self
+    }
+
+    /// Set the language standard for the files of `language`, overriding
+    /// [`Build::std`] for them.
+    ///
+    /// This only has an effect with [`Build::mixed_languages`], for Fortran
+    /// files, which are always compiled as their own language, and for
+    /// Objective-C and Objective-C++ files with [`Build::objc_runtime`].
+    pub fn language_std(&mut self, language: Language, std: &str) -> &mut Build {
+        self.language_std.retain(|(lang, _)| *lang != language);
+        self.language_std.push((language, std.into()));
+        self
+    }
+
+    /// Add an arbitrary flag for the files of `language`, after the flags
+    /// added with [`Build::flag`].
+    ///
+    /// This only has an effect with [`Build::mixed_languages`], for Fortran
+    /// files, and for Objective-C and Objective-C++ files with
+    /// [`Build::objc_runtime`].
+    pub fn language_flag(&mut self, language: Language, flag: impl AsRef<OsStr>) -> &mut Build {
+        self.language_flags.push((language, flag.as_ref().into()));
+        self
+    }
+
+    /// Enable or disable automatic reference counting for Objective-C and
+    /// Objective-C++ files, with `-fobjc-arc`.
+    ///
+    /// ARC is only supported by Clang. By default, the compiler's default is
+    /// used, which is to not use ARC.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// cc::Build::new()
+    ///     .file("src/foo.m")
+    ///     .objc_arc(true)
+    ///     .compile("foo");
+    /// ```
+    pub fn objc_arc(&mut self, objc_arc: bool) -> &mut Build {
+        self.objc_arc = Some(objc_arc);
+        self
+    }
+
+    /// Enable or disable the `@try`/`@catch` exception syntax for
+    /// Objective-C and Objective-C++ files, with `-fobjc-exceptions`.
+    ///
+    /// By default, the compiler's default is used.
+    pub fn objc_exceptions(&mut self, objc_exceptions: bool) -> &mut Build {
+        self.objc_exceptions = Some(objc_exceptions);
+        self
+    }
+
+    /// Set the Objective-C runtime to compile Objective-C and Objective-C++
+    /// files for.
+    ///
+    /// By default, the compiler's default is used, which is the Apple runtime
+    /// on Apple targets. On other targets, GCC and Clang both support the
+    /// GNU runtime.
+    ///
+    /// Setting a runtime also opts into compiling Objective-C files with the
+    /// C compiler and Objective-C++ files with the C++ compiler, which links
+    /// the C++ standard library, whatever [`Build::cpp`] is, and into linking
+    /// the runtime with `rustc-link-lib=objc`. Otherwise, they are compiled
+    /// like the other files of the build, unless with
+    /// [`Build::mixed_languages`], and the runtime is not linked.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// cc::Build::new()
+    ///     .file("src/foo.m")
+    ///     .objc_runtime(cc::ObjcRuntime::GnuStep)
+    ///     .compile("foo");
+    /// ```
+    pub fn objc_runtime(&mut self, runtime: ObjcRuntime) -> &mut Build {
+        self.objc_runtime = Some(runtime);
+        self
+    }
+
     /// Set warnings into errors flag.
     ///
     /// Disabled by default.
@@ -1158,6 +1506,41 @@ impl Build {
         self
     }
 
+    /// Configures whether to build for the host rather than the target.
+    ///
+    /// This is useful for build scripts that need to compile a helper program
+    /// or library that runs on the build machine, such as a code generator,
+    /// while cross-compiling. The compiler, flags, archiver and sysroot are then
+    /// resolved for the host triple: `CC_<host>`, `CFLAGS_<host>` and `HOST_CC`
+    /// are consulted, but the plain `CC`, `CFLAGS` etc., the rustflags inherited
+    /// with [`Build::inherit_rustflags`] and `CARGO_CFG_TARGET_FEATURE` are
+    /// ignored, since they describe the target.
+    ///
+    /// When not cross-compiling, these follow Cargo's `target-applies-to-host`
+    /// setting: they apply to the host unless `CARGO_TARGET_APPLIES_TO_HOST`
+    /// is `false`.
+    ///
+    /// Since the result can't be linked into the crate being built, you will
+    /// usually want to combine this with [`Build::cargo_metadata`] set to
+    /// `false`, or use [`Build::compile_intermediates`] or
+    /// [`Build::get_compiler`] directly.
+    ///
+    /// This option defaults to `false`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// cc::Build::new()
+    ///     .file("src/generator.c")
+    ///     .for_host(true)
+    ///     .cargo_metadata(false)
+    ///     .compile("generator");
+    /// ```
+    pub fn for_host(&mut self, for_host: bool) -> &mut Build {
+        self.for_host = for_host;
+        self
+    }
+
     /// Configures the optimization level of the generated object files.
     ///
     /// This option is automatically scraped from the `OPT_LEVEL` environment
@@ -1233,6 +1616,38 @@ impl Build {
         self
     }
 
+    /// Require a minimum version of the compiler if it is from the given
+    /// vendor.
+    ///
+    /// This can be called multiple times to set a minimum version for each
+    /// vendor the library supports. Compilers from other vendors, and
+    /// compilers whose vendor could not be detected, are not checked. See
+    /// [`Tool::info`] for how the vendor and version are determined.
+    ///
+    /// If the requirement is not satisfied, [`Build::try_get_compiler`] and
+    /// everything that uses it (such as [`Build::try_compile`]) fails with
+    /// an error naming the compiler, its version and the required version.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use cc::{CompilerVendor, CompilerVersion};
+    ///
+    /// cc::Build::new()
+    ///     .file("src/foo.c")
+    ///     .require_compiler_version(CompilerVendor::Gnu, CompilerVersion::new(10, 0, 0))
+    ///     .require_compiler_version(CompilerVendor::Msvc, CompilerVersion::new(19, 29, 0))
+    ///     .compile("foo");
+    /// ```
+    pub fn require_compiler_version(
+        &mut self,
+        vendor: CompilerVendor,
+        min_version: CompilerVersion,
+    ) -> &mut Build {
+        self.required_compiler_versions.push((vendor, min_version));
+        self
+    }
+
     /// Configures the tool used to assemble archives.
     ///
     /// This option is automatically determined from the target platform or a
@@ -1253,6 +1668,77 @@ impl Build {
         self
     }
 
+    /// Configures a toolchain file describing the compiler, archiver and
+    /// flags to use.
+    ///
+    /// If not set, the path is taken from the `CC_TOOLCHAIN_FILE` environment
+    /// variable (with the usual `_<target>` / `TARGET_` variants).
+    ///
+    /// The file uses the format of [Meson's cross files], so existing cross
+    /// files can be reused. Only the following entries are used, everything
+    /// else is ignored:
+    ///
+    /// * `[binaries]`: `c`, `cpp`, `ar`, `ranlib` and `cuda`, corresponding to
+    ///   the `CC`, `CXX`, `AR`, `RANLIB` and `NVCC` environment variables.
+    ///   Arrays such as `['ccache', 'gcc']` are treated like `CC='ccache gcc'`,
+    ///   whereas a string is the path of the program, even if it has spaces.
+    /// * `[built-in options]`: `c_args` and `cpp_args`, corresponding to
+    ///   `CFLAGS` and `CXXFLAGS`.
+    /// * `[properties]`: `sys_root`, passed to the compiler as `--sysroot`.
+    /// * `[constants]`: may be referenced from the values above.
+    ///
+    /// Values in the toolchain file take precedence over the plain `CC`,
+    /// `CFLAGS` etc. environment variables, but not over target-specific
+    /// variables such as `CC_<target>` or `TARGET_CC`.
+    ///
+    /// # Example
+    ///
+    /// ```ini
+    /// [constants]
+    /// prefix = '/opt/cross/bin/aarch64-linux-gnu-'
+    ///
+    /// [binaries]
+    /// c = prefix + 'gcc'
+    /// ar = prefix + 'ar'
+    ///
+    /// [built-in options]
+    /// c_args = ['-mcpu=cortex-a72']
+    /// ```
+    ///
+    /// [Meson's cross files]: https://mesonbuild.com/Machine-files.html
+    pub fn toolchain_file<P: AsRef<Path>>(&mut self, toolchain_file: P) -> &mut Build {
+        self.toolchain_file = Some(toolchain_file.as_ref().into());
+        self
+    }
+
+    /// Configures the sysroot to compile against, such as one produced by
+    /// Yocto or Buildroot.
+    ///
+    /// The sysroot is passed as `--sysroot` (or `-isysroot` for Apple
+    /// targets) to every compiler invocation, including the ones used for
+    /// [`Build::is_flag_supported`] and [`Build::expand`], and is part of
+    /// [`Tool::cflags_env`]. It has no effect on MSVC.
+    ///
+    /// If not set, the sysroot is taken from the `SYSROOT_<target>`,
+    /// `SYSROOT_<target_with_underscores>` or `TARGET_SYSROOT` (`HOST_SYSROOT`
+    /// when not cross-compiling) environment variables, and then from the
+    /// `sys_root` property of the [toolchain file](Build::toolchain_file).
+    /// The plain `SYSROOT` variable is deliberately not used, since it is
+    /// commonly set for unrelated purposes.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// cc::Build::new()
+    ///     .file("src/foo.c")
+    ///     .sysroot("/opt/poky/sysroots/cortexa72-poky-linux")
+    ///     .compile("foo");
+    /// ```
+    pub fn sysroot<P: AsRef<Path>>(&mut self, sysroot: P) -> &mut Build {
+        self.sysroot = Some(sysroot.as_ref().into());
+        self
+    }
+
     /// Define whether metadata should be emitted for cargo allowing it to
     /// automatically link the binary. Defaults to `true`.
     ///
@@ -1262,6 +1748,8 @@ impl Build {
     ///  - `rustc-link-search=native=`*target folder*
     ///  - When target is MSVC, the ATL-MFC libs are added via `rustc-link-search=native=`
     ///  - When C++ is enabled, the C++ stdlib is added via `rustc-link-lib`
+    ///  - With [`Build::objc_runtime`] and Objective-C files, `rustc-link-lib=objc`
+    ///  - When there are Fortran files, the Fortran runtime via `rustc-link-lib`
     ///  - If `emit_rerun_if_env_changed` is not `false`, `rerun-if-env-changed=`*env*
     ///
     pub fn cargo_metadata(&mut self, cargo_metadata: bool) -> &mut Build {
@@ -1316,8 +1804,9 @@ impl Build {
 
     /// Configures whether the compiler will emit position independent code.
     ///
-    /// This option defaults to `false` for `windows-gnu` and bare metal targets and
-    /// to `true` for all other targets.
+    /// This option defaults to `false` for `windows-gnu` and bare metal targets,
+    /// and for [freestanding](Build::freestanding) code, and to `true` for all
+    /// other targets.
     pub fn pic(&mut self, pic: bool) -> &mut Build {
         self.pic = Some(pic);
         self
@@ -1338,6 +1827,48 @@ impl Build {
         self
     }
 
+    /// Configures whether to compile freestanding code, e.g. for bare-metal
+    /// targets such as `thumbv7em-none-eabihf` or for a kernel.
+    ///
+    /// This passes `-ffreestanding -fno-builtin -fno-stack-protector` to
+    /// GCC-like compilers, along with the floating point ABI and FPU of the
+    /// target for Clang (GCC gets those in any case), and `-GS- -Zl` to MSVC.
+    /// Position independent code defaults to off, and no C++ standard library
+    /// is linked unless [`Build::cpp_link_stdlib`] is set.
+    ///
+    /// Which C library is available is set with [`Build::c_library`].
+    ///
+    /// This option defaults to `false`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// cc::Build::new()
+    ///     .file("src/startup.c")
+    ///     .freestanding(true)
+    ///     .c_library(cc::CLibrary::Picolibc)
+    ///     .compile("startup");
+    /// ```
+    pub fn freestanding(&mut self, freestanding: bool) -> &mut Build {
+        self.freestanding = freestanding;
+        self
+    }
+
+    /// Configures the C library that freestanding code is compiled against,
+    /// see [`Build::freestanding`].
+    ///
+    /// With GCC, Newlib and picolibc are selected with the `--specs` files
+    /// that bare-metal toolchains ship, along with `nosys.specs` for Newlib.
+    /// Clang doesn't support spec files, so the C library is taken from the
+    /// sysroot, see [`Build::sysroot`]. [`CLibrary::None`] passes `-nostdlib`
+    /// to GCC and `-nostdlibinc` to Clang.
+    ///
+    /// This option defaults to [`CLibrary::None`].
+    pub fn c_library(&mut self, c_library: CLibrary) -> &mut Build {
+        self.c_library = c_library;
+        self
+    }
+
     /// Define whether metadata should be emitted for cargo to only trigger
     /// rebuild when detected environment changes, by default build script is
     /// always run on every compilation if no rerun cargo metadata is emitted.
@@ -1418,13 +1949,76 @@ impl Build {
         self
     }
 
+    /// Use [Zig](https://ziglang.org/) as the toolchain, i.e. compile with
+    /// `zig cc` / `zig c++` and archive with `zig ar`.
+    ///
+    /// Zig ships with headers and libc for many targets, so this allows
+    /// cross-compiling without installing a cross toolchain. The Rust target
+    /// is translated to a Zig target and passed with `-target`. For GNU/Linux
+    /// targets, this includes the glibc version, which defaults to the
+    /// minimum version supported by `rustc` for the target and can be
+    /// changed with the `CC_ZIG_GLIBC_VERSION` environment variable (e.g.
+    /// `CC_ZIG_GLIBC_VERSION_aarch64_unknown_linux_gnu=2.31`).
+    ///
+    /// The `zig` executable is looked up in `PATH`, unless the `ZIG`
+    /// environment variable is set.
+    ///
+    /// When enabled, this takes precedence over the `CC`, `CXX`, `AR` and
+    /// `RANLIB` environment variables, but not over [`Build::compiler`],
+    /// [`Build::archiver`] and [`Build::ranlib`].
+    ///
+    /// This option defaults to the value of the `CC_USE_ZIG` environment
+    /// variable, or `false` if that is not set.
+    pub fn zig(&mut self, zig: bool) -> &mut Build {
+        self.zig = Some(zig);
+        self
+    }
+
+    /// Configures the Android API level to compile for when using an Android
+    /// NDK that `cc` located itself.
+    ///
+    /// When compiling for Android and no compiler is configured with
+    /// [`Build::compiler`], `CC`/`CXX` or Cargo's linker for the target, `cc`
+    /// looks for an NDK in `ANDROID_NDK_HOME`, `ANDROID_NDK_ROOT` and then the
+    /// newest version in `ANDROID_HOME/ndk`. If one is found, its Clang and
+    /// `llvm-ar` for the current host are used, even if another compiler is on
+    /// `PATH`.
+    ///
+    /// If not set, the API level is taken from the `ANDROID_API_LEVEL`
+    /// environment variable (with the usual `_<target>` / `TARGET_`
+    /// variants), and otherwise defaults to the lowest API level the NDK
+    /// supports for the target architecture.
+    pub fn android_api_level(&mut self, api_level: u32) -> &mut Build {
+        self.android_api_level = Some(api_level);
+        self
+    }
+
+    /// Configures whether to check that the compiler works before compiling
+    /// any sources.
+    ///
+    /// When enabled, `cc` compiles a trivial translation unit for the target
+    /// and verifies that the result is an object file of the expected format
+    /// (ELF, COFF, Mach-O or WebAssembly) and architecture. On failure, the
+    /// error describes where the compiler came from and, where possible, which
+    /// package provides it, e.g. for a missing cross compiler. This catches a
+    /// compiler that silently produces objects for the wrong target, which
+    /// would otherwise only fail when linking.
+    ///
+    /// This option defaults to the value of the `CC_SANITY_CHECK` environment
+    /// variable, or `false` if that is not set.
+    pub fn sanity_check(&mut self, sanity_check: bool) -> &mut Build {
+        self.sanity_check = Some(sanity_check);
+        self
+    }
+
     /// Set an environment variable for compiler invocations and other child processes.
     ///
     /// `cc` reads a lot of different variables from the current process' environment. It currently
     /// allows the following standard environment variables to be overwritten by this function:
     /// - `SDKROOT`
     /// - `*_DEPLOYMENT_TARGET`
-    /// - `WASI_SDK_ROOT`
+    /// - `WASI_SDK_PATH`
+    /// - `EMSDK`
     ///
     /// The logic here is "environment variables that the C compiler could itself reasonably have
     /// read".
@@ -1517,8 +2111,12 @@ impl Build {
 
         let mut compiler = {
             let mut cfg = Build::new();
+            if self.get_zig() {
+                cfg.zig(true);
+            } else {
+                cfg.compiler(tool.path());
+            }
             cfg.flag(flag)
-                .compiler(tool.path())
                 .cargo_metadata(self.cargo_output.metadata)
                 .opt_level(0)
                 .debug(false)
@@ -1530,6 +2128,12 @@ impl Build {
             if let Some(target) = &self.target {
                 cfg.target(target);
             }
+            if let Some(toolchain_file) = &self.toolchain_file {
+                cfg.toolchain_file(toolchain_file);
+            }
+            if let Some(sysroot) = self.get_sysroot()? {
+                cfg.sysroot(sysroot);
+            }
             if let Some(host) = &self.host {
                 cfg.host(host);
             }
@@ -1557,6 +2161,7 @@ impl Build {
                 msvc: compiler.is_like_msvc(),
                 clang: compiler.is_like_clang(),
                 gnu: compiler.is_like_gnu(),
+                ti: compiler.family == ToolFamily::Ti,
                 is_asm: false,
                 is_arm: is_arm(target),
             },
@@ -1624,9 +2229,9 @@ impl Build {
         };
         let dst = self.get_out_dir()?;
 
-        let objects = objects_from_files(&self.files, &dst)?;
+        let mut objects = objects_from_files(&self.source_files()?, &dst)?;
 
-        self.compile_objects(&objects)?;
+        self.compile_objects(&mut objects)?;
         self.assemble(lib_name, &dst.join(gnu_lib_name), &objects)?;
 
         let target = self.get_target()?;
@@ -1676,7 +2281,7 @@ impl Build {
         ));
 
         // Add specific C++ libraries, if enabled.
-        if self.cpp {
+        if self.has_cpp_files() {
             if let Some(stdlib) = self.get_cpp_link_stdlib()? {
                 if self.cpp_link_stdlib_static {
                     self.cargo_output.print_metadata(&format_args!(
@@ -1688,10 +2293,15 @@ impl Build {
                         .print_metadata(&format_args!("cargo:rustc-link-lib={}", stdlib.display()));
                 }
             }
-            // Link c++ lib from WASI sysroot
+            // Link c++ lib from WASI sysroot, or the one configured explicitly.
             if target.arch == "wasm32" {
+                let sysroot = self.get_sysroot()?;
                 if target.os == "wasi" {
-                    if let Ok(wasi_sysroot) = self.wasi_sysroot() {
+                    let wasi_sysroot = match &sysroot {
+                        Some(sysroot) => Some(sysroot.as_os_str().to_owned()),
+                        None => self.wasi_sysroot().ok(),
+                    };
+                    if let Some(wasi_sysroot) = wasi_sysroot {
                         self.cargo_output.print_metadata(&format_args!(
                             "cargo:rustc-flags=-L {}/lib/{} -lstatic=c++ -lstatic=c++abi",
                             Path::new(&wasi_sysroot).display(),
@@ -1699,7 +2309,10 @@ impl Build {
                         ));
                     }
                 } else if target.os == "linux" {
-                    let musl_sysroot = self.wasm_musl_sysroot().unwrap();
+                    let musl_sysroot = match &sysroot {
+                        Some(sysroot) => sysroot.as_os_str().to_owned(),
+                        None => self.wasm_musl_sysroot()?,
+                    };
                     self.cargo_output.print_metadata(&format_args!(
                         "cargo:rustc-flags=-L {}/lib -lstatic=c++ -lstatic=c++abi",
                         Path::new(&musl_sysroot).display(),
@@ -1716,12 +2329,42 @@ impl Build {
             }
         }
 
-        let cudart = match &self.cudart {
-            Some(opt) => opt, // {none|shared|static}
-            None => "none",
+        // Both the Apple and the GNU runtimes are `libobjc`.
+        if self.objc_runtime.is_some() && self.has_objc_files() {
+            self.cargo_output
+                .print_metadata(&format_args!("cargo:rustc-link-lib=objc"));
+        }
+
+        if self.has_fortran_files() {
+            let compiler = self
+                .for_language(Some(Language::Fortran))
+                .get_base_compiler()?;
+            let runtime = if compiler.is_like_clang() {
+                self.flang_runtime(&compiler, &target)
+            } else {
+                "gfortran"
+            };
+            self.cargo_output
+                .print_metadata(&format_args!("cargo:rustc-link-lib={runtime}"));
+        }
+
+        // With mixed languages, the CUDA runtime is only linked if there are
+        // CUDA files.
+        let cuda = if self.mixed_languages {
+            self.cuda_build()
+        } else {
+            Some(Cow::Borrowed(self))
         };
+        // {none|shared|static}
+        let cudart = cuda
+            .as_ref()
+            .and_then(|cuda| cuda.cudart.as_deref())
+            .unwrap_or("none");
         if cudart != "none" {
-            if let Some(nvcc) = self.which(&self.get_compiler().path, None) {
+            if let Some(nvcc) = cuda
+                .as_ref()
+                .and_then(|cuda| self.which(&cuda.get_compiler().path, None))
+            {
                 // Try to figure out the -L search path. If it fails,
                 // it's on user to specify one by passing it through
                 // RUSTFLAGS environment variable.
@@ -1834,14 +2477,76 @@ impl Build {
     /// This will return a result instead of panicking; see `compile_intermediates()` for the complete description.
     pub fn try_compile_intermediates(&self) -> Result<Vec<PathBuf>, Error> {
         let dst = self.get_out_dir()?;
-        let objects = objects_from_files(&self.files, &dst)?;
+        let mut objects = objects_from_files(&self.source_files()?, &dst)?;
 
-        self.compile_objects(&objects)?;
+        self.compile_objects(&mut objects)?;
 
         Ok(objects.into_iter().map(|v| v.dst).collect())
     }
 
-    fn compile_objects(&self, objs: &[Object]) -> Result<(), Error> {
+    /// The files to compile, including the variants of the files added with
+    /// [`Build::multiversion`] and their dispatchers.
+    fn source_files(&self) -> Result<Cow<'_, [SourceFile]>, Error> {
+        // A disabled build fails in `compile_objects` without probing flags.
+        if self.multiversions.is_empty() || self.is_disabled() {
+            return Ok(Cow::Borrowed(&self.files));
+        }
+
+        let out_dir = self.get_out_dir()?;
+        let mut files = self.files.clone();
+        for multiversion in &self.multiversions {
+            multiversion.validate()?;
+            let file = multiversion.file();
+            let build = self.for_language(self.language_of(file));
+            if multiversion.has_dispatcher() {
+                // The dispatcher uses `__builtin_cpu_supports`.
+                let target = self.get_target()?;
+                let compiler = build.try_get_compiler()?;
+                if !matches!(target.arch, "x86" | "x86_64")
+                    || !(compiler.is_like_gnu() || compiler.is_like_clang())
+                {
+                    return Err(Error::new(
+                        ErrorKind::InvalidArgument,
+                        format!(
+                            "the multiversion dispatcher of {} needs GCC or Clang and an x86 target",
+                            file.display()
+                        ),
+                    ));
+                }
+            }
+            let mut compiled = Vec::new();
+            'variants: for (name, flags) in multiversion.variant_flags() {
+                for flag in flags {
+                    if !build.is_flag_supported(flag)? {
+                        self.cargo_output.print_warning(&format_args!(
+                            "Skipping the `{name}` variant of {}: the compiler doesn't support `{}`",
+                            file.display(),
+                            flag.to_string_lossy()
+                        ));
+                        continue 'variants;
+                    }
+                }
+                files.push(SourceFile {
+                    path: file.into(),
+                    options: Some(Arc::new(multiversion.options(name)?)),
+                });
+                compiled.push(name);
+            }
+
+            if let Some(dispatcher) = multiversion.dispatcher_path(&out_dir) {
+                fs::write(&dispatcher, multiversion.dispatcher_source(&compiled)?)?;
+                files.push(SourceFile {
+                    path: dispatcher.into(),
+                    options: None,
+                });
+            }
+        }
+        Ok(Cow::Owned(files))
+    }
+
+    /// Compile the objects, adding the object that the precompiled header
+    /// produces with MSVC to `objs`.
+    fn compile_objects(&self, objs: &mut Vec<Object>) -> Result<(), Error> {
         if self.is_disabled() {
             return Err(Error::new(
                 ErrorKind::Disabled,
@@ -1849,32 +2554,616 @@ impl Build {
             ));
         }
 
-        #[cfg(feature = "parallel")]
-        if objs.len() > 1 {
-            return parallel::run_commands_in_parallel(
-                &self.cargo_output,
-                &mut objs.iter().map(|obj| self.create_compile_object_cmd(obj)),
-            );
+        if self.get_sanity_check() {
+            self.run_sanity_check()?;
         }
 
-        for obj in objs {
-            let mut cmd = self.create_compile_object_cmd(obj)?;
-            run(&mut cmd, &self.cargo_output)?;
-        }
+        // The precompiled header must exist before any object is compiled.
+        let pch = self
+            .compile_precompiled_header()
+            .map_err(|e| self.explain_missing_compiler(e))?;
 
+        self.run_compile_object_cmds(objs, pch.as_ref())
+            .map_err(|e| self.explain_missing_compiler(e))?;
+
+        if let Some((src, dst)) = pch.and_then(|pch| pch.object) {
+            objs.push(Object::new(src, dst, None));
+        }
         Ok(())
     }
 
-    fn create_compile_object_cmd(&self, obj: &Object) -> Result<Command, Error> {
-        let asm_ext = AsmFileExt::from_path(&obj.src);
-        let is_asm = asm_ext.is_some();
-        let target = self.get_target()?;
-        let msvc = target.env == "msvc";
-        let compiler = self.try_get_compiler()?;
-
-        let is_assembler_msvc = msvc && asm_ext == Some(AsmFileExt::DotAsm);
-        let mut cmd = if is_assembler_msvc {
-            self.msvc_macro_assembler()?
+    /// Precompile the header set with [`Build::precompiled_header`], if any.
+    fn compile_precompiled_header(&self) -> Result<Option<PrecompiledHeader>, Error> {
+        let header = match &self.precompiled_header {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        let build = if self.mixed_languages {
+            let language = if self.has_cpp_files() {
+                Language::Cpp
+            } else {
+                Language::C
+            };
+            self.for_language(Some(language))
+        } else {
+            Cow::Borrowed(self)
+        };
+        let compiler = build.try_get_compiler()?;
+        match PrecompiledHeader::new(&compiler, header, &self.get_out_dir()?, build.cpp)? {
+            Some((mut cmd, pch)) => {
+                run(&mut cmd, &self.cargo_output)?;
+                Ok(Some(pch))
+            }
+            None => {
+                self.cargo_output.print_warning(&format_args!(
+                    "Precompiled headers are not supported by {:?}, ignoring {}",
+                    compiler.path(),
+                    header.display()
+                ));
+                Ok(None)
+            }
+        }
+    }
+
+    fn run_compile_object_cmds(
+        &self,
+        objs: &[Object],
+        pch: Option<&PrecompiledHeader>,
+    ) -> Result<(), Error> {
+        // Derive the build of each language once, rather than per object.
+        let mut builds: Vec<(Option<Language>, Cow<'_, Build>)> = Vec::new();
+        for obj in objs {
+            let language = self.language_of(&obj.src);
+            if !builds.iter().any(|(lang, _)| *lang == language) {
+                builds.push((language, self.for_language(language)));
+            }
+        }
+        let build_of = |obj: &Object| {
+            let language = self.language_of(&obj.src);
+            builds
+                .iter()
+                .position(|(lang, _)| *lang == language)
+                .expect("all languages have a build")
+        };
+        let create_cmd = |obj: &Object| builds[build_of(obj)].1.create_compile_object_cmd(obj, pch);
+
+        // With modules, the objects are reordered so that modules are built
+        // before the objects importing them.
+        let scheduled;
+        let (objs, deps) = if self.cpp_modules {
+            let object_builds: Vec<&Build> =
+                objs.iter().map(|obj| &*builds[build_of(obj)].1).collect();
+            scheduled = self.schedule_modules(objs, &object_builds)?;
+            (&*scheduled.0, scheduled.1)
+        } else {
+            (objs, Vec::new())
+        };
+
+        #[cfg(feature = "parallel")]
+        if objs.len() > 1 {
+            return parallel::run_commands_in_parallel(
+                &self.cargo_output,
+                &mut objs.iter().map(create_cmd),
+                &deps,
+                &[],
+            );
+        }
+        // Without `parallel`, the objects are compiled in order anyway.
+        let _ = deps;
+
+        for obj in objs {
+            let mut cmd = create_cmd(obj)?;
+            run(&mut cmd, &self.cargo_output)?;
+        }
+
+        Ok(())
+    }
+
+    /// Scan the objects for the C++ modules they provide and import, and
+    /// return them in the order to compile them in along with the flags for
+    /// the modules, and the indices of the objects each of them waits for.
+    fn schedule_modules(
+        &self,
+        objs: &[Object],
+        builds: &[&Build],
+    ) -> Result<(Vec<Object>, Vec<Vec<usize>>), Error> {
+        let dir = self.get_out_dir()?.join("modules");
+        fs::create_dir_all(&dir)?;
+
+        let mut scans = Vec::with_capacity(objs.len());
+        let mut outputs = Vec::with_capacity(objs.len());
+        let mut families = Vec::with_capacity(objs.len());
+        for (obj, build) in objs.iter().zip(builds) {
+            if !build.cpp || build.cuda || AsmFileExt::from_path(&obj.src).is_some() {
+                families.push(None);
+                continue;
+            }
+            let mut compiler = build.try_get_compiler()?;
+            if let Some(options) = &obj.options {
+                options.apply(&mut compiler);
+            }
+            let (scan, output) =
+                modules::scan_command(&compiler, &obj.src, &obj.dst).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidArgument,
+                        format!(
+                            "C++ modules are not supported by {}",
+                            compiler.path().display()
+                        ),
+                    )
+                })?;
+            scans.push(scan);
+            outputs.push(output);
+            families.push(Some(compiler.family));
+        }
+        self.run_module_scans(scans, &outputs)?;
+
+        let mut outputs = outputs.iter();
+        let mut units = Vec::with_capacity(objs.len());
+        for (obj, family) in objs.iter().zip(&families) {
+            if family.is_none() {
+                units.push(ModuleDeps::none(&obj.src));
+                continue;
+            }
+            let output = outputs.next().expect("each scanned object has an output");
+            let json = fs::read(output.path())?;
+            let deps =
+                ModuleDeps::from_p1689(&obj.src, &String::from_utf8_lossy(&json)).map_err(|e| {
+                    Error::new(
+                        ErrorKind::ToolExecError,
+                        format!(
+                            "failed to read the module dependencies of {}: {e}",
+                            obj.src.display()
+                        ),
+                    )
+                })?;
+            units.push(deps);
+        }
+
+        let schedule = modules::Schedule::new(&units)
+            .map_err(|e| Error::new(ErrorKind::InvalidArgument, e))?;
+        let bmis: Vec<Option<PathBuf>> = units
+            .iter()
+            .zip(&families)
+            .map(|(unit, family)| {
+                Some(modules::bmi_path((*family)?, &dir, unit.provides.as_ref()?))
+            })
+            .collect();
+        let gcc_mapper = dir.join("gcc-module-mapper.txt");
+        if families.contains(&Some(ToolFamily::Gnu)) {
+            let modules = units
+                .iter()
+                .zip(&bmis)
+                .filter_map(|(unit, bmi)| Some((unit.provides.as_deref()?, bmi.as_deref()?)));
+            fs::write(&gcc_mapper, modules::gcc_module_mapper(modules))?;
+        }
+
+        let mut scheduled = Vec::with_capacity(objs.len());
+        for &i in &schedule.order {
+            let obj = &objs[i];
+            let family = match families[i] {
+                Some(family) => family,
+                None => {
+                    scheduled.push(obj.clone());
+                    continue;
+                }
+            };
+            let requires: Vec<(&str, &Path)> = schedule.transitive[i]
+                .iter()
+                .filter_map(|&dep| Some((units[dep].provides.as_deref()?, bmis[dep].as_deref()?)))
+                .collect();
+            let flags = modules::module_flags(
+                family,
+                &units[i],
+                bmis[i].as_deref(),
+                &requires,
+                &gcc_mapper,
+            );
+            let mut options = obj.options.as_deref().cloned().unwrap_or_default();
+            for flag in flags {
+                options.flag(flag);
+            }
+            scheduled.push(Object::new(
+                obj.src.clone(),
+                obj.dst.clone(),
+                Some(Arc::new(options)),
+            ));
+        }
+
+        // The dependencies refer to the objects by their new position.
+        let mut position = vec![0; objs.len()];
+        for (new, &old) in schedule.order.iter().enumerate() {
+            position[old] = new;
+        }
+        let deps = schedule
+            .order
+            .iter()
+            .map(|&i| schedule.deps[i].iter().map(|&dep| position[dep]).collect())
+            .collect();
+        Ok((scheduled, deps))
+    }
+
+    /// Run the commands that scan for module dependencies, in parallel if
+    /// possible.
+    fn run_module_scans(&self, scans: Vec<Command>, outputs: &[ScanOutput]) -> Result<(), Error> {
+        #[cfg(feature = "parallel")]
+        if scans.len() > 1 {
+            let stdouts: Vec<Option<PathBuf>> = outputs
+                .iter()
+                .map(|output| match output {
+                    ScanOutput::Stdout(path) => Some(path.clone()),
+                    ScanOutput::File(_) => None,
+                })
+                .collect();
+            return parallel::run_commands_in_parallel(
+                &self.cargo_output,
+                &mut scans.into_iter().map(Ok),
+                &[],
+                &stdouts,
+            );
+        }
+
+        for (mut cmd, output) in scans.into_iter().zip(outputs) {
+            match output {
+                ScanOutput::Stdout(path) => {
+                    run_with_stdout(&mut cmd, &self.cargo_output, fs::File::create(path)?.into())?
+                }
+                ScanOutput::File(_) => run(&mut cmd, &self.cargo_output)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// The language `file` is compiled as, or `None` if that's decided by
+    /// [`Build::cpp`] and [`Build::cuda`].
+    fn language_of(&self, file: &Path) -> Option<Language> {
+        Language::from_path(file).filter(|language| self.is_compiled_separately(*language))
+    }
+
+    /// Whether files of `language` are compiled as their own language.
+    fn is_compiled_separately(&self, language: Language) -> bool {
+        self.mixed_languages
+            || language == Language::Fortran
+            // Objective-C(++) files used to be compiled like any other file,
+            // and as C++ in `cpp(true)` builds, so this is opt-in.
+            || (language.is_objc() && self.objc_runtime.is_some())
+    }
+
+    /// The build to compile files of `language` with, see
+    /// [`Build::mixed_languages`].
+    fn for_language(&self, language: Option<Language>) -> Cow<'_, Build> {
+        let language = match language {
+            Some(language) if self.is_compiled_separately(language) => language,
+            _ => return Cow::Borrowed(self),
+        };
+        let mut build = self.clone();
+        build.mixed_languages = false;
+        build.cpp = language.is_cpp();
+        build.cuda = language == Language::Cuda;
+        build.fortran = language == Language::Fortran;
+        if build.cuda && build.cudart.is_none() {
+            build.cudart = Some("static".into());
+        }
+        match self.language_std.iter().find(|(lang, _)| *lang == language) {
+            Some((_, std)) => build.std = Some(std.clone()),
+            // A C standard doesn't apply to C++ or Fortran, and vice versa.
+            None if build.cpp != self.cpp || build.fortran != self.fortran => build.std = None,
+            None => {}
+        }
+        // The flags of the build are for C, Fortran only gets its own.
+        if build.fortran {
+            build.flags.clear();
+            build.flags_supported.clear();
+        }
+        build.flags.extend(
+            self.language_flags
+                .iter()
+                .filter(|(lang, _)| *lang == language)
+                .map(|(_, flag)| flag.clone()),
+        );
+        Cow::Owned(build)
+    }
+
+    /// Whether any of the files are compiled as C++, so that the C++
+    /// standard library has to be linked.
+    fn has_cpp_files(&self) -> bool {
+        self.cpp
+            || self.files.iter().any(|file| {
+                self.language_of(&file.path)
+                    .map_or(false, |language| language.is_cpp())
+            })
+    }
+
+    /// Whether any of the files are Objective-C or Objective-C++, so that
+    /// the Objective-C runtime has to be linked.
+    fn has_objc_files(&self) -> bool {
+        self.files.iter().any(|file| {
+            Language::from_path(&file.path).map_or(false, |language| language.is_objc())
+        })
+    }
+
+    /// The runtime library of `flang`. Newer versions ship `flang_rt.runtime`
+    /// in the resource directory of the driver, which is added to the search
+    /// path, and older ones `FortranRuntime` next to the driver.
+    fn flang_runtime(&self, flang: &Tool, target: &TargetInfo<'_>) -> &'static str {
+        let file_name = if target.env == "msvc" {
+            "flang_rt.runtime.lib"
+        } else {
+            "libflang_rt.runtime.a"
+        };
+        let mut cmd = self.cmd(flang.path());
+        cmd.arg(format!("-print-file-name={file_name}"));
+        // The file name is printed as is if the driver can't find the file.
+        let runtime = run_output(&mut cmd, &self.cargo_output)
+            .ok()
+            .map(|output| PathBuf::from(String::from_utf8_lossy(&output).trim()))
+            .filter(|runtime| runtime.is_absolute());
+        match runtime.as_deref().and_then(Path::parent) {
+            Some(dir) => {
+                self.cargo_output.print_metadata(&format_args!(
+                    "cargo:rustc-link-search=native={}",
+                    dir.display()
+                ));
+                "flang_rt.runtime"
+            }
+            None => "FortranRuntime",
+        }
+    }
+
+    /// Whether any of the files are Fortran, so that the Fortran runtime has
+    /// to be linked.
+    fn has_fortran_files(&self) -> bool {
+        self.files
+            .iter()
+            .any(|file| Language::from_path(&file.path) == Some(Language::Fortran))
+    }
+
+    /// Add the flags for [`Build::objc_arc`], [`Build::objc_exceptions`] and
+    /// [`Build::objc_runtime`].
+    fn add_objc_flags(&self, compiler: &mut Tool, target: &TargetInfo<'_>) {
+        let clang = compiler.is_like_clang();
+        if !clang && !compiler.is_like_gnu() {
+            return;
+        }
+        match self.objc_arc {
+            Some(true) if clang => compiler.args.push("-fobjc-arc".into()),
+            Some(false) if clang => compiler.args.push("-fno-objc-arc".into()),
+            Some(true) => self.cargo_output.print_warning(&format_args!(
+                "Objective-C ARC is not supported by {:?}",
+                compiler.path()
+            )),
+            _ => {}
+        }
+        match self.objc_exceptions {
+            Some(true) => compiler.args.push("-fobjc-exceptions".into()),
+            Some(false) => compiler.args.push("-fno-objc-exceptions".into()),
+            None => {}
+        }
+        let runtime = match (self.objc_runtime, clang) {
+            // Clang picks the runtime for the Apple target it compiles for.
+            (Some(ObjcRuntime::Apple), true) if target.vendor == "apple" => None,
+            (Some(ObjcRuntime::Apple), true) => Some("-fobjc-runtime=macosx"),
+            (Some(ObjcRuntime::GnuStep), true) => Some("-fobjc-runtime=gnustep-2.0"),
+            (Some(ObjcRuntime::Apple), false) => Some("-fnext-runtime"),
+            (Some(ObjcRuntime::GnuStep), false) => Some("-fgnu-runtime"),
+            (None, _) => None,
+        };
+        if let Some(runtime) = runtime {
+            compiler.args.push(runtime.into());
+        }
+    }
+
+    /// The build for the CUDA files, if there are any.
+    fn cuda_build(&self) -> Option<Cow<'_, Build>> {
+        if self.cuda_file_count() == 0 {
+            return None;
+        }
+        let build = self.for_language(self.mixed_languages.then_some(Language::Cuda));
+        if build.cuda {
+            Some(build)
+        } else {
+            None
+        }
+    }
+
+    /// Compile a trivial translation unit and check that the result is an
+    /// object for the target, see [`Build::sanity_check`].
+    fn run_sanity_check(&self) -> Result<(), Error> {
+        let compiler = self.try_get_compiler()?;
+        // The object is produced by the host compiler behind `nvcc`, which
+        // is checked for the host instead.
+        if compiler.cuda {
+            return Ok(());
+        }
+        let target = self.get_target()?;
+
+        let dir = self.get_out_dir()?.join("cc-sanity-check");
+        fs::create_dir_all(&dir)?;
+        let src = dir.join(if self.cpp {
+            "sanity_check.cpp"
+        } else {
+            "sanity_check.c"
+        });
+        fs::write(&src, "int cc_rs_sanity_check(void) { return 0; }\n")?;
+        let obj = dir.join("sanity_check.o");
+        let _ = fs::remove_file(&obj);
+
+        let mut cmd = compiler.to_command();
+        command_add_output_file(
+            &mut cmd,
+            &obj,
+            CmdAddOutputFileArgs {
+                cuda: false,
+                is_assembler_msvc: false,
+                msvc: compiler.is_like_msvc(),
+                clang: compiler.is_like_clang(),
+                gnu: compiler.is_like_gnu(),
+                ti: compiler.family == ToolFamily::Ti,
+                is_asm: false,
+                is_arm: is_arm(&target),
+            },
+        );
+        cmd.arg("-c");
+        if compiler.supports_path_delimiter() {
+            cmd.arg("--");
+        }
+        cmd.arg(&src);
+        if cfg!(target_os = "macos") {
+            self.fix_env_for_apple_os(&mut cmd)?;
+        }
+        self.cargo_output
+            .print_debug(&format_args!("running sanity check: {cmd:?}"));
+
+        let problem = match cmd.current_dir(&dir).output() {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                format!("failed to find tool {:?}: {e}", cmd.get_program())
+            }
+            Err(e) => format!("failed to run {:?}: {e}", cmd.get_program()),
+            Ok(output) if !output.status.success() => format!(
+                "compiling a trivial source file failed ({}):\n{}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim_end()
+            ),
+            Ok(_) => match fs::read(&obj) {
+                Err(e) => format!(
+                    "the compiler did not produce an object file at {}: {e}",
+                    obj.display()
+                ),
+                Ok(data) => match (
+                    sanity_check::Expected::for_target(&target),
+                    sanity_check::ObjectInfo::parse(&data),
+                ) {
+                    (Some(expected), Some(object)) if !expected.matches(&object) => format!(
+                        "the compiler produced an object for the wrong target: \
+                         expected {expected}, found {object}"
+                    ),
+                    // Unknown formats (e.g. LLVM bitcode with `-flto`) are
+                    // given the benefit of the doubt.
+                    _ => return Ok(()),
+                },
+            },
+        };
+
+        let mut message = format!("compiler sanity check failed: {problem}");
+        self.describe_compiler(&compiler, &mut message);
+        Err(Error::new(ErrorKind::SanityCheckFailed, message))
+    }
+
+    /// Add a description of the compiler to a "failed to find tool" error, if
+    /// it's the compiler that is missing.
+    fn explain_missing_compiler(&self, err: Error) -> Error {
+        if !matches!(err.kind, ErrorKind::ToolNotFound) {
+            return err;
+        }
+        let compiler = match self.try_get_compiler() {
+            Ok(compiler)
+                if self
+                    .which(&compiler.path, self.get_env_overridable("PATH").as_deref())
+                    .is_none() =>
+            {
+                compiler
+            }
+            _ => return err,
+        };
+        let mut message = err.message.into_owned();
+        self.describe_compiler(&compiler, &mut message);
+        Error::new(ErrorKind::ToolNotFound, message)
+    }
+
+    /// Describe the target, where the compiler came from and how to get it.
+    fn describe_compiler(&self, compiler: &Tool, message: &mut String) {
+        let raw_target = self.get_raw_target().unwrap_or_default();
+        message.push_str(&format!(
+            "\n  target: {raw_target}\n  compiler: {}",
+            compiler.path.display()
+        ));
+
+        let env = if self.cpp { "CXX" } else { "CC" };
+        let envs = self.target_envs(env).ok().map(|envs| {
+            let applicable = if self.target_config_applies() { 4 } else { 3 };
+            envs[..applicable].to_vec()
+        });
+        if self.compiler.is_some() {
+            message.push_str(" (set with `Build::compiler`)");
+        } else if let Some(envs) = &envs {
+            match envs.iter().find(|env| self.get_env(env).is_some()) {
+                Some(env) => message.push_str(&format!(" (from `{env}`)")),
+                None => match self.get_linker_driver() {
+                    Some((linker, env)) if linker.compiler(self.cpp) == compiler.path => {
+                        message.push_str(&format!(" (derived from the linker set in `{env}`)"))
+                    }
+                    _ => message.push_str(&format!(
+                        " (the default for the target, since none of `{}` are set)",
+                        envs.join("`, `")
+                    )),
+                },
+            }
+        }
+
+        if cfg!(target_os = "linux") {
+            let hint = compiler
+                .path
+                .file_name()
+                .and_then(|name| sanity_check::package_hint(&name.to_string_lossy()));
+            if let Some(hint) = hint {
+                message.push_str(&format!("\n  help: {hint}"));
+            }
+        }
+        if let Some(env) = envs.as_ref().and_then(|envs| envs.first()) {
+            let lang = if self.cpp { "C++" } else { "C" };
+            message.push_str(&format!(
+                "\n  help: set `{env}` to the {lang} compiler to use for this target"
+            ));
+        }
+    }
+
+    fn create_compile_object_cmd(
+        &self,
+        obj: &Object,
+        pch: Option<&PrecompiledHeader>,
+    ) -> Result<Command, Error> {
+        let asm_ext = AsmFileExt::from_path(&obj.src);
+        let is_asm = asm_ext.is_some();
+        let target = self.get_target()?;
+        let msvc = target.env == "msvc";
+        let options = obj.options.as_deref();
+
+        // NASM doesn't need a C compiler at all.
+        let is_nasm = asm_ext == Some(AsmFileExt::DotNasm)
+            || (self.nasm && asm_ext == Some(AsmFileExt::DotAsm));
+        if is_nasm {
+            let mut cmd = self.nasm_assembler(options)?;
+            cmd.args(self.asm_flags_for(options));
+            cmd.args(options.into_iter().flat_map(FileOptions::flags));
+            cmd.arg("-o").arg(&obj.dst).arg(&obj.src);
+            return Ok(cmd);
+        }
+
+        let mut compiler = self.try_get_compiler()?;
+        if let Some(pch) = pch {
+            if !is_asm && !compiler.cuda && !self.fortran && pch.cpp == self.cpp {
+                // Compilers reject precompiled headers built with different
+                // flags, so files with their own flags include the header.
+                let flags = match obj.options {
+                    Some(_) => &pch.include_flags,
+                    None => &pch.flags,
+                };
+                compiler.args.extend(flags.iter().cloned());
+            }
+        }
+        if Language::from_path(&obj.src).map_or(false, Language::is_objc) {
+            self.add_objc_flags(&mut compiler, &target);
+        }
+        if self.fortran && is_preprocessed_fortran(&obj.src) {
+            compiler.args.push("-cpp".into());
+        }
+        if let Some(options) = options {
+            options.apply(&mut compiler);
+        }
+
+        let is_assembler_msvc = msvc && asm_ext == Some(AsmFileExt::DotAsm);
+        let mut cmd = if is_assembler_msvc {
+            self.msvc_macro_assembler(options)?
         } else {
             compiler.to_command()
         };
@@ -1888,6 +3177,7 @@ impl Build {
                 msvc: compiler.is_like_msvc(),
                 clang: compiler.is_like_clang(),
                 gnu: compiler.is_like_gnu(),
+                ti: compiler.family == ToolFamily::Ti,
                 is_asm,
                 is_arm,
             },
@@ -1900,7 +3190,12 @@ impl Build {
             cmd.arg("--device-c");
         }
         if is_asm {
-            cmd.args(self.asm_flags.iter().map(std::ops::Deref::deref));
+            cmd.args(self.asm_flags_for(options));
+        }
+        if is_assembler_msvc {
+            // The compiler's arguments, which include the file's flags, are
+            // not used for the assembler.
+            cmd.args(options.into_iter().flat_map(FileOptions::flags));
         }
 
         if compiler.supports_path_delimiter() && !is_assembler_msvc {
@@ -1934,8 +3229,7 @@ impl Build {
         let is_asm = self
             .files
             .iter()
-            .map(std::ops::Deref::deref)
-            .find_map(AsmFileExt::from_path)
+            .find_map(|file| AsmFileExt::from_path(&file.path))
             .is_some();
 
         if compiler.family == (ToolFamily::Msvc { clang_cl: true }) && !is_asm {
@@ -1946,7 +3240,7 @@ impl Build {
             cmd.arg("--");
         }
 
-        cmd.args(self.files.iter().map(std::ops::Deref::deref));
+        cmd.args(self.files.iter().map(|file| &*file.path));
 
         run_output(&mut cmd, &self.cargo_output)
     }
@@ -1970,6 +3264,34 @@ impl Build {
         }
     }
 
+    /// Get information about the target that this configuration compiles for.
+    ///
+    /// This takes [`Build::target`], [`Build::host`] and [`Build::for_host`]
+    /// into account, and otherwise uses the target that Cargo is building for.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let mut build = cc::Build::new();
+    /// let target = build.try_get_target().unwrap();
+    /// if target.os == "linux" && target.arch == "x86_64" {
+    ///     build.file("src/x86_64_linux.c");
+    /// }
+    /// if let Some(flag) = target.apple_version_flag("11.0") {
+    ///     build.flag(flag);
+    /// }
+    /// ```
+    pub fn try_get_target(&self) -> Result<Target, Error> {
+        let raw_target = self.get_raw_target()?;
+        let target = self.get_target()?;
+        Ok(Target::new(
+            &raw_target,
+            &target,
+            self.get_llvm_target(&target, None)?.into_owned(),
+            self.get_is_cross_compile()?,
+        ))
+    }
+
     /// Get the compiler that's in use for this configuration.
     ///
     /// This function will return a `Tool` which represents the culmination
@@ -2004,6 +3326,7 @@ impl Build {
         let target = self.get_target()?;
 
         let mut cmd = self.get_base_compiler()?;
+        self.check_compiler_version(&cmd)?;
 
         // The flags below are added in roughly the following order:
         // 1. Default flags
@@ -2039,16 +3362,21 @@ impl Build {
         // Specify various flags that are not considered part of the default flags above.
         // FIXME(madsmtm): Should these be considered part of the defaults? If no, why not?
         if let Some(ref std) = self.std {
-            let separator = match cmd.family {
-                ToolFamily::Msvc { .. } => ':',
-                ToolFamily::Gnu | ToolFamily::Clang { .. } => '=',
-            };
-            cmd.push_cc_arg(format!("-std{separator}{std}").into());
+            let std_flag = cmd.family.std_flag(std);
+            cmd.push_cc_arg(std_flag.into());
         }
         for directory in self.include_directories.iter() {
-            cmd.args.push("-I".into());
+            cmd.args.push(cmd.family.include_flag().into());
             cmd.args.push(directory.as_os_str().into());
         }
+        // Apple targets pass the sysroot as `-isysroot` in `apple_flags`.
+        if let Some(sysroot) = self.get_sysroot()? {
+            if !cmd.is_like_msvc() && target.vendor != "apple" {
+                let mut arg = OsString::from("--sysroot=");
+                arg.push(&*sysroot);
+                cmd.push_cc_arg(arg);
+            }
+        }
         if self.warnings_into_errors {
             let warnings_to_errors_flag = cmd.family.warnings_to_errors_flag().into();
             cmd.push_cc_arg(warnings_to_errors_flag);
@@ -2058,7 +3386,13 @@ impl Build {
         // then we set them only if the environment doesn't already have
         // CFLAGS/CXXFLAGS, since those variables presumably already contain
         // the desired set of warnings flags.
-        let envflags = self.envflags(if self.cpp { "CXXFLAGS" } else { "CFLAGS" })?;
+        let envflags = self.envflags(if self.fortran {
+            "FFLAGS"
+        } else if self.cpp {
+            "CXXFLAGS"
+        } else {
+            "CFLAGS"
+        })?;
         match self.warnings {
             Some(true) => {
                 let wflags = cmd.family.warnings_flags().into();
@@ -2082,7 +3416,7 @@ impl Build {
         }
 
         // Add cc flags inherited from matching rustc flags.
-        if self.inherit_rustflags {
+        if self.inherit_rustflags && self.target_config_applies() {
             self.add_inherited_rustflags(&mut cmd, &target)?;
         }
 
@@ -2146,9 +3480,7 @@ impl Build {
                     Some(true) => "-MT",
                     Some(false) => "-MD",
                     None => {
-                        let features = cargo_env_var_os("CARGO_CFG_TARGET_FEATURE");
-                        let features = features.as_deref().unwrap_or_default();
-                        if features.to_string_lossy().contains("crt-static") {
+                        if self.target_feature_enabled("crt-static") {
                             "-MT"
                         } else {
                             "-MD"
@@ -2195,14 +3527,19 @@ impl Build {
                 //
                 // `rustc` also defaults to disable PIC on WASM:
                 // <https://github.com/rust-lang/rust/blob/1.82.0/compiler/rustc_target/src/spec/base/wasm.rs#L101-L108>
-                if self.pic.unwrap_or(
-                    target.os != "windows"
-                        && target.os != "none"
-                        && target.os != "uefi"
-                        && target.os != "vita"
-                        && target.arch != "wasm32"
-                        && target.arch != "wasm64",
-                ) {
+                if self
+                    .pic
+                    .or(self.get_target_spec()?.and_then(|spec| spec.pic()))
+                    .unwrap_or(
+                        !self.freestanding
+                            && target.os != "windows"
+                            && target.os != "none"
+                            && target.os != "uefi"
+                            && target.os != "vita"
+                            && target.arch != "wasm32"
+                            && target.arch != "wasm64",
+                    )
+                {
                     cmd.push_cc_arg("-fPIC".into());
                     // PLT only applies if code is compiled with PIC support,
                     // and only for ELF targets.
@@ -2214,17 +3551,24 @@ impl Build {
                 }
 
                 if target.os == "wasi" {
-                    // Link clang sysroot
-                    if let Ok(wasi_sysroot) = self.wasi_sysroot() {
-                        cmd.push_cc_arg(
-                            format!("--sysroot={}", Path::new(&wasi_sysroot).display()).into(),
-                        );
+                    // Link clang sysroot, unless one was configured explicitly.
+                    if self.get_sysroot()?.is_none() {
+                        if let Ok(wasi_sysroot) = self.wasi_sysroot() {
+                            cmd.push_cc_arg(
+                                format!("--sysroot={}", Path::new(&wasi_sysroot).display()).into(),
+                            );
+                        }
                     }
+                }
 
-                    // FIXME(madsmtm): Read from `target_features` instead?
-                    if raw_target.contains("threads") {
+                if target.os == "wasi" || target.os == "emscripten" {
+                    if raw_target.contains("threads") || self.target_feature_enabled("atomics") {
                         cmd.push_cc_arg("-pthread".into());
                     }
+                    // Match the exception handling ABI that `rustc` uses.
+                    if self.target_feature_enabled("exception-handling") {
+                        cmd.push_cc_arg("-fwasm-exceptions".into());
+                    }
                 }
 
                 if target.os == "nto" || target.os == "qnx" {
@@ -2247,6 +3591,29 @@ impl Build {
                     cmd.push_cc_arg(arg.into());
                 }
             }
+            ToolFamily::NvHpc => {
+                // `nvc` has no `-Os` or `-Oz`.
+                match opt_level {
+                    "z" | "s" => cmd.push_opt_unless_duplicate("-O1".into()),
+                    _ => cmd.push_opt_unless_duplicate(format!("-O{opt_level}").into()),
+                }
+
+                // The NVIDIA HPC compilers only target Linux.
+                if self.pic.unwrap_or(true) {
+                    cmd.push_cc_arg("-fPIC".into());
+                }
+            }
+            ToolFamily::Ti => {
+                // The TI compilers don't generate position-independent code.
+                match opt_level {
+                    "0" => cmd.push_cc_arg("--opt_level=off".into()),
+                    "z" | "s" => {
+                        cmd.push_opt_unless_duplicate("-O2".into());
+                        cmd.push_cc_arg("--opt_for_speed=0".into());
+                    }
+                    _ => cmd.push_opt_unless_duplicate(format!("-O{opt_level}").into()),
+                }
+            }
         }
 
         if self.get_debug() {
@@ -2295,7 +3662,21 @@ impl Build {
         // Target flags
         match cmd.family {
             ToolFamily::Clang { .. } => {
-                if !(cmd.has_internal_target_arg
+                if cmd.info.vendor == CompilerVendor::ArmClang {
+                    // armclang only supports these two triples, and selects the architecture
+                    // with `-march` instead.
+                    if target.arch == "aarch64" {
+                        cmd.push_cc_arg("--target=aarch64-arm-none-eabi".into());
+                    } else {
+                        cmd.push_cc_arg("--target=arm-arm-none-eabi".into());
+                        if let Some(march) = armclang_march(target.full_arch) {
+                            cmd.push_cc_arg(format!("-march={march}").into());
+                        }
+                        if target.abi == "eabihf" {
+                            cmd.push_cc_arg("-mfloat-abi=hard".into());
+                        }
+                    }
+                } else if !(cmd.has_internal_target_arg
                     || (target.os == "android"
                         && android_clang_compiler_uses_target_arg_internally(&cmd.path)))
                 {
@@ -2334,10 +3715,13 @@ impl Build {
                         for x in &["wasm-exceptions", "declspec"] {
                             cmd.push_cc_arg(format!("-f{x}").into());
                         }
-                        let musl_sysroot = self.wasm_musl_sysroot().unwrap();
-                        cmd.push_cc_arg(
-                            format!("--sysroot={}", Path::new(&musl_sysroot).display()).into(),
-                        );
+                        // Unless a sysroot was configured explicitly.
+                        if self.get_sysroot()?.is_none() {
+                            let musl_sysroot = self.wasm_musl_sysroot().unwrap();
+                            cmd.push_cc_arg(
+                                format!("--sysroot={}", Path::new(&musl_sysroot).display()).into(),
+                            );
+                        }
                         cmd.push_cc_arg("-pthread".into());
                     } else if target.abi == "pauthtest" {
                         let pauthtest_sysroot = self.pauthtest_sysroot()?;
@@ -2395,8 +3779,7 @@ impl Build {
                         None
                     };
 
-                    let clang_target =
-                        target.llvm_target(&self.get_raw_target()?, version.as_deref());
+                    let clang_target = self.get_llvm_target(target, version.as_deref())?;
                     cmd.push_cc_arg(format!("--target={clang_target}").into());
                 }
             }
@@ -2408,11 +3791,7 @@ impl Build {
 
                 if clang_cl {
                     cmd.push_cc_arg(
-                        format!(
-                            "--target={}",
-                            target.llvm_target(&self.get_raw_target()?, None)
-                        )
-                        .into(),
+                        format!("--target={}", self.get_llvm_target(target, None)?).into(),
                     );
 
                     if target.arch == "x86" {
@@ -2446,17 +3825,14 @@ impl Build {
                         .push("-D_ARM_WINAPI_PARTITION_DESKTOP_SDK_AVAILABLE=1".into());
                 }
             }
+            ToolFamily::NvHpc | ToolFamily::Ti => {}
             ToolFamily::Gnu => {
                 if target.vendor == "kmc" {
                     cmd.args.push("-finput-charset=utf-8".into());
                 }
 
-                if self.static_flag.is_none() {
-                    let features = cargo_env_var_os("CARGO_CFG_TARGET_FEATURE");
-                    let features = features.as_deref().unwrap_or_default();
-                    if features.to_string_lossy().contains("crt-static") {
-                        cmd.args.push("-static".into());
-                    }
+                if self.static_flag.is_none() && self.target_feature_enabled("crt-static") {
+                    cmd.args.push("-static".into());
                 }
 
                 // armv7 targets get to use armv7 instructions
@@ -2645,6 +4021,16 @@ impl Build {
             }
         }
 
+        if let Some(spec) = self.get_target_spec()? {
+            self.add_target_spec_flags(cmd, target, &spec);
+        }
+
+        if self.freestanding {
+            for flag in freestanding::flags(cmd.family, target, self.c_library) {
+                cmd.push_cc_arg(flag);
+            }
+        }
+
         if raw_target == "wasm32v1-none" {
             // `wasm32v1-none` target only exists in `rustc`, so we need to change the compilation flags:
             // https://doc.rust-lang.org/rustc/platform-support/wasm32v1-none.html
@@ -2843,7 +4229,19 @@ impl Build {
         supported
     }
 
-    fn msvc_macro_assembler(&self) -> Result<Command, Error> {
+    /// The assembler flags of the build, without those removed for a file by
+    /// its `options`.
+    fn asm_flags_for<'a>(
+        &'a self,
+        options: Option<&'a FileOptions>,
+    ) -> impl Iterator<Item = &'a OsStr> + 'a {
+        self.asm_flags
+            .iter()
+            .map(std::ops::Deref::deref)
+            .filter(move |flag| !options.map_or(false, |options| options.removes(flag)))
+    }
+
+    fn msvc_macro_assembler(&self, options: Option<&FileOptions>) -> Result<Command, Error> {
         let target = self.get_target()?;
         let tool = match target.arch {
             "x86_64" => "ml64.exe",
@@ -2852,12 +4250,18 @@ impl Build {
             _ => "ml.exe",
         };
         let mut cmd = self
-            .find_msvc_tools_find(&target, tool)
+            .find_msvc_tools_find(&target, tool)?
             .unwrap_or_else(|| self.cmd(tool));
         cmd.arg("-nologo"); // undocumented, yet working with armasm[64]
-        for directory in self.include_directories.iter() {
+        let file_include_directories = options.map_or(&[][..], FileOptions::include_directories);
+        for directory in self
+            .include_directories
+            .iter()
+            .chain(file_include_directories)
+        {
             cmd.arg("-I").arg(&**directory);
         }
+        let file_definitions = options.map_or(&[][..], FileOptions::definitions);
         if is_arm(&target) {
             if self.get_debug() {
                 cmd.arg("-g");
@@ -2867,7 +4271,7 @@ impl Build {
                 cmd.args(["-machine", "ARM64EC"]);
             }
 
-            for (key, value) in self.definitions.iter() {
+            for (key, value) in self.definitions.iter().chain(file_definitions) {
                 cmd.arg("-PreDefine");
                 if let Some(ref value) = *value {
                     if let Ok(i) = value.parse::<i32>() {
@@ -2886,7 +4290,7 @@ impl Build {
                 cmd.arg("-Zi");
             }
 
-            for (key, value) in self.definitions.iter() {
+            for (key, value) in self.definitions.iter().chain(file_definitions) {
                 if let Some(ref value) = *value {
                     cmd.arg(format!("-D{key}={value}"));
                 } else {
@@ -2902,6 +4306,64 @@ impl Build {
         Ok(cmd)
     }
 
+    fn nasm_assembler(&self, options: Option<&FileOptions>) -> Result<Command, Error> {
+        let target = self.get_target()?;
+        let windows = target.os == "windows" || target.os == "uefi";
+        let apple = target.vendor == "apple";
+        let format = match target.arch {
+            "x86_64" if windows => "win64",
+            "x86_64" if apple => "macho64",
+            "x86_64" if target.abi == "x32" => "elfx32",
+            "x86_64" => "elf64",
+            "x86" if windows => "win32",
+            "x86" if apple => "macho32",
+            "x86" => "elf32",
+            arch => {
+                return Err(Error::new(
+                    ErrorKind::InvalidTarget,
+                    format!("NASM can't assemble for the {arch} architecture"),
+                ))
+            }
+        };
+
+        let nasm = match self.getenv_with_target_prefixes("NASM") {
+            Ok(nasm) => PathBuf::from(nasm),
+            Err(_) => PathBuf::from("nasm"),
+        };
+        let yasm = nasm
+            .file_stem()
+            .and_then(OsStr::to_str)
+            .map_or(false, |stem| stem.starts_with("yasm"));
+        let mut cmd = self.cmd(&nasm);
+        cmd.arg("-f").arg(format);
+        let file_include_directories = options.map_or(&[][..], FileOptions::include_directories);
+        for directory in self
+            .include_directories
+            .iter()
+            .chain(file_include_directories)
+        {
+            cmd.arg("-I").arg(&**directory);
+        }
+        let file_definitions = options.map_or(&[][..], FileOptions::definitions);
+        for (key, value) in self.definitions.iter().chain(file_definitions) {
+            if let Some(ref value) = *value {
+                cmd.arg(format!("-D{key}={value}"));
+            } else {
+                cmd.arg(format!("-D{key}"));
+            }
+        }
+        if self.get_debug() {
+            // YASM requires the debug format, NASM picks the output format's
+            // default one.
+            cmd.arg("-g");
+            if yasm {
+                cmd.arg(if windows { "cv8" } else { "dwarf2" });
+            }
+        }
+
+        Ok(cmd)
+    }
+
     fn assemble(&self, lib_name: &str, dst: &Path, objs: &[Object]) -> Result<(), Error> {
         // Delete the destination if it exists as we want to
         // create on the first iteration instead of appending.
@@ -2935,13 +4397,13 @@ impl Build {
             batch.clear();
         }
 
-        if self.cuda && self.cuda_file_count() > 0 {
+        if let Some(cuda) = self.cuda_build() {
             // Link the device-side code and add it to the target library,
             // so that non-CUDA linker can link the final binary.
 
             let out_dir = self.get_out_dir()?;
             let dlink = out_dir.join(lib_name.to_owned() + "_dlink.o");
-            let mut nvcc = self.get_compiler().to_command();
+            let mut nvcc = cuda.get_compiler().to_command();
             nvcc.arg("--device-link").arg("-o").arg(&dlink).arg(dst);
             run(&mut nvcc, &self.cargo_output)?;
             self.assemble_progressive(dst, &[dlink.as_path()], &mut deterministic_ar)?;
@@ -3006,7 +4468,8 @@ impl Build {
         let target = self.get_target()?;
 
         let (mut cmd, program, any_flags) = self.try_get_archiver_and_flags()?;
-        if target.env == "msvc" && !program.to_string_lossy().contains("llvm-ar") {
+        if target.env == "msvc" && !program.to_string_lossy().contains("llvm-ar") && !self.get_zig()
+        {
             // NOTE: -out: here is an I/O flag, and so must be included even if $ARFLAGS/ar_flag is
             // in use. -nologo on the other hand is just a regular flag, and one that we'll skip if
             // the caller has explicitly dictated the flags they want. See
@@ -3112,7 +4575,10 @@ impl Build {
                 target.os,
                 target.apple_sdk_name(),
             ));
-            let sdk_path = self.apple_sdk_root(&target)?;
+            let sdk_path = match self.get_sysroot()? {
+                Some(sysroot) => sysroot.as_os_str().into(),
+                None => self.apple_sdk_root(&target)?,
+            };
 
             cmd.args.push("-isysroot".into());
             cmd.args.push(OsStr::new(&sdk_path).to_owned());
@@ -3162,7 +4628,41 @@ impl Build {
         cmd
     }
 
+    /// The Fortran compiler, from `FC` or `gfortran` for the target.
+    ///
+    /// gfortran is a GCC driver and flang a Clang one, so the compiler is
+    /// told apart by its name rather than by running it on a C file.
+    fn get_fortran_compiler(&self) -> Result<Tool, Error> {
+        let fc = match self.getenv_with_target_prefixes("FC") {
+            Ok(fc) => PathBuf::from(fc),
+            Err(_) if self.get_is_cross_compile()? => {
+                match self.prefix_for_target(&self.get_raw_target()?) {
+                    Some(prefix) => PathBuf::from(format!("{prefix}-gfortran")),
+                    None => PathBuf::from("gfortran"),
+                }
+            }
+            Err(_) => PathBuf::from("gfortran"),
+        };
+        let flang = fc
+            .file_stem()
+            .and_then(OsStr::to_str)
+            .map_or(false, |stem| stem.contains("flang"));
+        let family = if flang {
+            ToolFamily::Clang { zig_cc: false }
+        } else {
+            ToolFamily::Gnu
+        };
+        let mut tool = Tool::with_family(fc, family);
+        if let Some(cc_wrapper) = self.rustc_wrapper_fallback() {
+            tool.cc_wrapper_path = Some(Path::new(&cc_wrapper).to_owned());
+        }
+        Ok(tool)
+    }
+
     fn prefer_clang(&self) -> bool {
+        if !self.target_config_applies() {
+            return false;
+        }
         if let Some(env) = cargo_env_var_os("CARGO_ENCODED_RUSTFLAGS") {
             env.to_string_lossy().contains("linker-plugin-lto")
         } else {
@@ -3174,6 +4674,9 @@ impl Build {
         let out_dir = self.get_out_dir().ok();
         let out_dir = out_dir.as_deref();
 
+        if self.fortran {
+            return self.get_fortran_compiler();
+        }
         if let Some(c) = &self.compiler {
             return Ok(Tool::new(
                 (**c).to_owned(),
@@ -3206,16 +4709,19 @@ impl Build {
             Cow::Borrowed(Path::new(gnu))
         } else if self.prefer_clang() || target.abi == "pauthtest" {
             self.which(Path::new(clang), None)
+                .or_else(|| self.find_newest_versioned_tool(clang).map(PathBuf::from))
                 .map(Cow::Owned)
                 .unwrap_or(fallback)
         } else {
             fallback
         };
 
-        let cl_exe = self.find_msvc_tools_find_tool(&target, msvc);
+        if self.get_zig() {
+            return self.get_zig_compiler(&target);
+        }
 
         let tool_opt: Option<Tool> = self
-            .env_tool(env)
+            .env_tool(env)?
             .map(|(tool, wrapper, args)| {
                 // Chop off leading/trailing whitespace to work around
                 // semi-buggy build scripts which are shared in
@@ -3228,17 +4734,39 @@ impl Build {
                     &self.cargo_output,
                     out_dir,
                 );
-                if let Some(cc_wrapper) = wrapper {
+                if let Some(cc_wrapper) = wrapper {
+                    t.cc_wrapper_path = Some(Path::new(&cc_wrapper).to_owned());
+                }
+                for arg in args {
+                    t.cc_wrapper_args.push(arg.into());
+                }
+                t
+            })
+            .or_else(|| {
+                if target.env == "msvc" {
+                    return None;
+                }
+                let (linker, env) = self.get_linker_driver()?;
+                let compiler = linker.compiler(self.cpp);
+                self.cargo_output.print_debug(&format_args!(
+                    "Using `{}` as the compiler, derived from the linker set in `{env}`",
+                    compiler.display()
+                ));
+                let mut t = Tool::new(
+                    compiler,
+                    &self.build_cache.cached_compiler_family,
+                    &self.cargo_output,
+                    out_dir,
+                );
+                if let Some(cc_wrapper) = self.rustc_wrapper_fallback() {
                     t.cc_wrapper_path = Some(Path::new(&cc_wrapper).to_owned());
                 }
-                for arg in args {
-                    t.cc_wrapper_args.push(arg.into());
-                }
-                t
+                Some(t)
             })
             .or_else(|| {
                 if target.os == "emscripten" {
                     let tool = if self.cpp { "em++" } else { "emcc" };
+                    let emsdk_tool = self.find_emsdk().and_then(|emsdk| emsdk.tool(tool));
                     // Windows uses bat file so we have to be a bit more specific
                     if cfg!(windows) {
                         let mut t = Tool::with_family(
@@ -3246,11 +4774,14 @@ impl Build {
                             ToolFamily::Clang { zig_cc: false },
                         );
                         t.args.push("/c".into());
-                        t.args.push(format!("{tool}.bat").into());
+                        match emsdk_tool {
+                            Some(path) => t.args.push(path.into()),
+                            None => t.args.push(format!("{tool}.bat").into()),
+                        }
                         Some(t)
                     } else {
                         Some(Tool::new(
-                            PathBuf::from(tool),
+                            emsdk_tool.unwrap_or_else(|| PathBuf::from(tool)),
                             &self.build_cache.cached_compiler_family,
                             &self.cargo_output,
                             out_dir,
@@ -3259,8 +4790,16 @@ impl Build {
                 } else {
                     None
                 }
-            })
-            .or_else(|| cl_exe.clone());
+            });
+
+        let tool_opt = match tool_opt {
+            None if target.os == "android" => self.android_ndk_compiler(&target, &raw_target)?,
+            // Only look for `cl.exe` if nothing else is configured, so that
+            // e.g. a `VCToolsVersion` that isn't installed doesn't fail builds
+            // that use another compiler.
+            None => self.find_msvc_tools_find_tool(&target, msvc)?,
+            tool_opt => tool_opt,
+        };
 
         let tool = match tool_opt {
             Some(t) => t,
@@ -3291,7 +4830,9 @@ impl Build {
                 } else if target.arch == "wasm32" || target.arch == "wasm64" {
                     // Compiling WASM is not currently supported by GCC, so
                     // let's default to Clang.
-                    clang.into()
+                    self.resolve_versioned_tool(clang)
+                        .unwrap_or_else(|| clang.to_string())
+                        .into()
                 } else if target.os == "vxworks" {
                     if self.cpp { "wr-c++" } else { "wr-cc" }.into()
                 } else if target.arch == "arm" && target.vendor == "kmc" {
@@ -3306,7 +4847,10 @@ impl Build {
                     match prefix {
                         Some(prefix) => {
                             let cc = if target.abi == "llvm" { clang } else { gnu };
-                            format!("{prefix}-{cc}").into()
+                            let cc = format!("{prefix}-{cc}");
+                            // Distributions sometimes only ship versioned
+                            // cross compilers, e.g. `aarch64-linux-gnu-gcc-12`.
+                            self.resolve_versioned_tool(&cc).unwrap_or(cc).into()
                         }
                         None => default.into(),
                     }
@@ -3409,9 +4953,9 @@ impl Build {
             tool.has_internal_target_arg = true;
         }
 
-        // If we found `cl.exe` in our environment, the tool we're returning is
-        // an MSVC-like tool, *and* no env vars were set then set env vars for
-        // the tool that we're returning.
+        // If the tool we're returning is an MSVC-like tool, no env vars were set
+        // *and* we can find `cl.exe` in our environment, then set its env vars
+        // for the tool that we're returning.
         //
         // Env vars are needed for things like `link.exe` being put into PATH as
         // well as header include paths sometimes. These paths are automatically
@@ -3419,11 +4963,11 @@ impl Build {
         // won't be used. This'll ensure that when the env vars are used to
         // configure for invocations like `clang-cl` we still get a "works out
         // of the box" experience.
-        if let Some(cl_exe) = cl_exe {
-            if tool.family == (ToolFamily::Msvc { clang_cl: true })
-                && tool.env.is_empty()
-                && target.env == "msvc"
-            {
+        if tool.family == (ToolFamily::Msvc { clang_cl: true })
+            && tool.env.is_empty()
+            && target.env == "msvc"
+        {
+            if let Ok(Some(cl_exe)) = self.find_msvc_tools_find_tool(&target, msvc) {
                 for (k, v) in cl_exe.env.iter() {
                     tool.env.push((k.to_owned(), v.to_owned()));
                 }
@@ -3473,23 +5017,43 @@ impl Build {
         }
     }
 
-    /// Returns compiler path, optional modifier name from whitelist, and arguments vec
-    fn env_tool(&self, name: &str) -> Option<(PathBuf, Option<Cow<'_, OsStr>>, Vec<String>)> {
-        let tool = self.getenv_with_target_prefixes(name).ok()?;
-        let tool = tool.to_string_lossy();
-        let tool = tool.trim();
+    /// The tool set by the environment variable `name` or its toolchain file
+    /// equivalent, along with its wrapper and arguments.
+    #[allow(clippy::type_complexity)]
+    fn env_tool(
+        &self,
+        name: &str,
+    ) -> Result<Option<(PathBuf, Option<Cow<'_, OsStr>>, Vec<String>)>, Error> {
+        let parts = match self.getenv_value(name)? {
+            Some(Ok(tool)) => {
+                let tool = tool.to_string_lossy();
+                let tool = tool.trim();
+                if tool.is_empty() {
+                    return Ok(None);
+                }
 
-        if tool.is_empty() {
-            return None;
-        }
+                // If this is an exact path on the filesystem we don't want to do any
+                // interpretation at all, just pass it on through. This'll hopefully get
+                // us to support spaces-in-paths.
+                if let Some(exe) = check_exe(Path::new(tool).into()) {
+                    return Ok(Some((exe, self.rustc_wrapper_fallback(), Vec::new())));
+                }
 
-        // If this is an exact path on the filesystem we don't want to do any
-        // interpretation at all, just pass it on through. This'll hopefully get
-        // us to support spaces-in-paths.
-        if let Some(exe) = check_exe(Path::new(tool).into()) {
-            return Some((exe, self.rustc_wrapper_fallback(), Vec::new()));
-        }
+                tool.split_whitespace().map(str::to_string).collect()
+            }
+            // Toolchain files give a single program as a string and a program
+            // with arguments as an array, so neither is split on spaces.
+            Some(Err(toolchain_file::Value::Array(parts))) => parts,
+            Some(Err(toolchain_file::Value::String(tool))) => vec![tool],
+            None => return Ok(None),
+        };
+        Ok(self.split_env_tool(parts))
+    }
 
+    fn split_env_tool(
+        &self,
+        parts: Vec<String>,
+    ) -> Option<(PathBuf, Option<Cow<'_, OsStr>>, Vec<String>)> {
         // Ok now we want to handle a couple of scenarios. We'll assume from
         // here on out that spaces are splitting separate arguments. Two major
         // features we want to support are:
@@ -3524,16 +5088,16 @@ impl Build {
             known_wrappers.push(custom_wrapper.as_deref().unwrap().to_str().unwrap());
         }
 
-        let mut parts = tool.split_whitespace();
+        let mut parts = parts.into_iter();
         let maybe_wrapper = parts.next()?;
 
-        let file_stem = Path::new(maybe_wrapper).file_stem()?.to_str()?;
+        let file_stem = Path::new(&maybe_wrapper).file_stem()?.to_str()?;
         if known_wrappers.contains(&file_stem) {
             if let Some(compiler) = parts.next() {
                 return Some((
                     compiler.into(),
                     Some(Cow::Owned(maybe_wrapper.into())),
-                    parts.map(|s| s.to_string()).collect(),
+                    parts.collect(),
                 ));
             }
         }
@@ -3541,7 +5105,7 @@ impl Build {
         Some((
             maybe_wrapper.into(),
             self.rustc_wrapper_fallback(),
-            parts.map(|s| s.to_string()).collect(),
+            parts.collect(),
         ))
     }
 
@@ -3562,7 +5126,7 @@ impl Build {
                     }
                 } else {
                     let target = self.get_target()?;
-                    if target.env == "msvc" {
+                    if target.env == "msvc" || self.freestanding {
                         Ok(None)
                     } else if target.vendor == "apple"
                         || target.os == "freebsd"
@@ -3681,29 +5245,63 @@ impl Build {
         env: &str,
         tool: &str,
     ) -> Result<(Command, PathBuf), Error> {
+        if self.get_zig() {
+            let zig = self.get_zig_executable();
+            let mut cmd = self.cmd(&zig);
+            cmd.arg(tool);
+            return Ok((cmd, zig));
+        }
+
         let target = self.get_target()?;
         let mut name = PathBuf::new();
         let tool_opt: Option<Command> = self
-            .env_tool(env)
+            .env_tool(env)?
             .map(|(tool, _wrapper, args)| {
                 name.clone_from(&tool);
                 let mut cmd = self.cmd(tool);
                 cmd.args(args);
                 cmd
             })
+            .or_else(|| {
+                if target.env == "msvc" {
+                    return None;
+                }
+                let (linker, env) = self.get_linker_driver()?;
+                let linker_tool = linker.tool(tool)?;
+                self.which(&linker_tool, self.get_env_overridable("PATH").as_deref())?;
+                self.cargo_output.print_debug(&format_args!(
+                    "Using `{}` as `{tool}`, derived from the linker set in `{env}`",
+                    linker_tool.display()
+                ));
+                name.clone_from(&linker_tool);
+                Some(self.cmd(linker_tool))
+            })
             .or_else(|| {
                 if target.os == "emscripten" {
+                    let emsdk_tool = self
+                        .find_emsdk()
+                        .and_then(|emsdk| emsdk.tool(&format!("em{tool}")));
                     // Windows use bat files so we have to be a bit more specific
                     if cfg!(windows) {
                         let mut cmd = self.cmd("cmd");
-                        name = format!("em{tool}.bat").into();
+                        name = emsdk_tool.unwrap_or_else(|| format!("em{tool}.bat").into());
                         cmd.arg("/c").arg(&name);
                         Some(cmd)
                     } else {
-                        name = format!("em{tool}").into();
+                        name = emsdk_tool.unwrap_or_else(|| format!("em{tool}").into());
                         Some(self.cmd(&name))
                     }
                 } else if target.arch == "wasm32" || target.arch == "wasm64" {
+                    // Use the `llvm-ar` that comes with the WASI SDK, if any.
+                    if target.os == "wasi" {
+                        let sdk_tool = self
+                            .find_wasi_sdk()
+                            .and_then(|sdk| sdk.tool(&format!("llvm-{tool}")));
+                        if let Some(sdk_tool) = sdk_tool {
+                            name = sdk_tool;
+                            return Some(self.cmd(&name));
+                        }
+                    }
                     // Formally speaking one should be able to use this approach,
                     // parsing -print-search-dirs output, to cover all clang targets,
                     // including Android SDKs and other cross-compilation scenarios...
@@ -3726,13 +5324,18 @@ impl Build {
             Some(t) => t,
             None => {
                 if target.os == "android" {
-                    name = format!("llvm-{tool}").into();
-                    match Command::new(&name).arg("--version").status() {
-                        Ok(status) if status.success() => (),
-                        _ => {
-                            // FIXME: Use parsed target.
-                            let raw_target = self.get_raw_target()?;
-                            name = format!("{}-{}", raw_target.replace("armv7", "arm"), tool).into()
+                    if let Some(ndk) = self.find_android_ndk() {
+                        name = ndk.tool(&format!("llvm-{tool}"));
+                    } else {
+                        name = format!("llvm-{tool}").into();
+                        match Command::new(&name).arg("--version").status() {
+                            Ok(status) if status.success() => (),
+                            _ => {
+                                // FIXME: Use parsed target.
+                                let raw_target = self.get_raw_target()?;
+                                name = format!("{}-{}", raw_target.replace("armv7", "arm"), tool)
+                                    .into()
+                            }
                         }
                     }
                     self.cmd(&name)
@@ -3768,7 +5371,13 @@ impl Build {
                         self.cmd(&name)
                     } else {
                         name = PathBuf::from("lib.exe");
-                        let mut cmd = match self.find_msvc_tools_find(&target, "lib.exe") {
+                        let lib_exe = match self.find_msvc_tools_find(&target, "lib.exe") {
+                            // Ranlib is never run for MSVC, so don't fail on
+                            // the MSVC installation for it.
+                            Err(_) if env == "RANLIB" => None,
+                            lib_exe => lib_exe?,
+                        };
+                        let mut cmd = match lib_exe {
                             Some(t) => t,
                             None => self.cmd("lib.exe"),
                         };
@@ -3831,12 +5440,16 @@ impl Build {
                             self.cmd(&name)
                         }
                         None => {
-                            name = tool.into();
+                            name = self
+                                .llvm_tool_matching_compiler(tool)
+                                .unwrap_or_else(|| tool.into());
                             self.cmd(&name)
                         }
                     }
                 } else {
-                    name = tool.into();
+                    name = self
+                        .llvm_tool_matching_compiler(tool)
+                        .unwrap_or_else(|| tool.into());
                     self.cmd(&name)
                 }
             }
@@ -3845,6 +5458,32 @@ impl Build {
         Ok((tool, name))
     }
 
+    /// The gcc/clang-style driver Cargo links with, from
+    /// `CARGO_TARGET_<TRIPLE>_LINKER` or `RUSTC_LINKER`, along with the
+    /// variable it was taken from.
+    fn get_linker_driver(&self) -> Option<(LinkerDriver, String)> {
+        let raw_target = self.get_raw_target().ok()?;
+        let target_env = format!(
+            "CARGO_TARGET_{}_LINKER",
+            raw_target.to_uppercase().replace(['-', '.'], "_")
+        );
+        let (linker, env) = match self.get_env(&target_env) {
+            Some(linker) => (linker, target_env),
+            // `RUSTC_LINKER` is the linker for `TARGET`, which may not be what
+            // we are building for.
+            None if self.target_config_applies()
+                && cargo_env_var("TARGET").ok().as_deref() == Some(&*raw_target) =>
+            {
+                (
+                    cargo_env_var_os("RUSTC_LINKER")?,
+                    "RUSTC_LINKER".to_string(),
+                )
+            }
+            None => return None,
+        };
+        Some((LinkerDriver::parse(Path::new(&linker))?, env))
+    }
+
     // FIXME: Use parsed target instead of raw target.
     fn prefix_for_target(&self, target: &str) -> Option<Cow<'static, str>> {
         // CROSS_COMPILE is of the form: "arm-linux-gnueabi-"
@@ -4052,6 +5691,14 @@ impl Build {
                     None
                 })
             })
+            // Distributions sometimes only ship versioned cross compilers, so
+            // only scan `PATH` for those once no unversioned one was found.
+            .or_else(|| {
+                prefixes.iter().find(|prefix| {
+                    self.find_newest_versioned_tool(&format!("{prefix}{suffix}"))
+                        .is_some()
+                })
+            })
             .copied()
             // If no toolchain was found, provide the first toolchain that was passed in.
             // This toolchain has been shown not to exist, however it will appear in the
@@ -4061,9 +5708,13 @@ impl Build {
     }
 
     fn get_target(&self) -> Result<TargetInfo<'_>, Error> {
+        if self.for_host {
+            // `CARGO_CFG_TARGET_*` describe the target, not the host.
+            return TargetInfo::from_rustc_target(self.get_host()?);
+        }
         match &self.target {
             Some(t) if Some(OsStr::new(&**t)) != cargo_env_var_os("TARGET").as_deref() => {
-                TargetInfo::from_rustc_target(t)
+                TargetInfo::from_rustc_target(TargetSpec::target_name(t))
             }
             // Fetch target information from environment if not set, or if the
             // target was the same as the TARGET environment variable, in
@@ -4076,19 +5727,68 @@ impl Build {
     }
 
     fn get_raw_target(&self) -> Result<Cow<'_, str>, Error> {
+        if self.for_host {
+            return self.get_host().map(Cow::Borrowed);
+        }
         match &self.target {
             Some(t) => Ok(Cow::Borrowed(t)),
             None => cargo_env_var("TARGET").map(Cow::Owned),
         }
     }
 
+    fn get_host(&self) -> Result<&str, Error> {
+        match &self.host {
+            Some(h) => Ok(h),
+            None => match self
+                .build_cache
+                .host
+                .get_or_init(|| cargo_env_var("HOST").map(String::into_boxed_str))
+            {
+                Ok(host) => Ok(host),
+                Err(e) => Err(e.clone()),
+            },
+        }
+    }
+
     fn get_is_cross_compile(&self) -> Result<bool, Error> {
-        let target = self.get_raw_target()?;
-        let host: Cow<'_, str> = match &self.host {
-            Some(h) => Cow::Borrowed(h),
-            None => Cow::Owned(cargo_env_var("HOST")?),
+        Ok(self.get_host()? != self.get_raw_target()?)
+    }
+
+    /// Whether the configuration meant for the target also applies to this
+    /// build: the unprefixed environment variables such as `CC` and `CFLAGS`,
+    /// the rustflags inherited from Cargo and `CARGO_CFG_TARGET_FEATURE`.
+    ///
+    /// This is only not the case when building for the host with
+    /// [`Build::for_host`] while cross-compiling, or when
+    /// `CARGO_TARGET_APPLIES_TO_HOST` is `false`.
+    fn target_config_applies(&self) -> bool {
+        if !self.for_host {
+            return true;
+        }
+        let target = match &self.target {
+            Some(t) => Cow::Borrowed(&**t),
+            None => match cargo_env_var("TARGET") {
+                Ok(t) => Cow::Owned(t),
+                Err(_) => return true,
+            },
         };
-        Ok(host != target)
+        match self.get_host() {
+            Ok(host) if host == target => self
+                .get_env("CARGO_TARGET_APPLIES_TO_HOST")
+                .map_or(true, |applies| applies != "false"),
+            _ => false,
+        }
+    }
+
+    /// Whether the Rust target feature `feature` is enabled, according to
+    /// `CARGO_CFG_TARGET_FEATURE`.
+    fn target_feature_enabled(&self, feature: &str) -> bool {
+        if !self.target_config_applies() {
+            return false;
+        }
+        cargo_env_var_os("CARGO_CFG_TARGET_FEATURE").map_or(false, |features| {
+            features.to_string_lossy().split(',').any(|f| f == feature)
+        })
     }
 
     fn get_opt_level(&self) -> Result<Cow<'_, str>, Error> {
@@ -4119,6 +5819,150 @@ impl Build {
         }
     }
 
+    fn get_zig(&self) -> bool {
+        self.zig
+            .unwrap_or_else(|| self.get_env_boolean("CC_USE_ZIG"))
+    }
+
+    fn get_zig_executable(&self) -> PathBuf {
+        self.get_env("ZIG")
+            .filter(|zig| !zig.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("zig"))
+    }
+
+    fn get_zig_compiler(&self, target: &TargetInfo<'_>) -> Result<Tool, Error> {
+        let glibc_version = self
+            .getenv_with_target_prefixes("CC_ZIG_GLIBC_VERSION")
+            .ok();
+        let glibc_version = glibc_version
+            .as_deref()
+            .and_then(OsStr::to_str)
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+
+        let mut tool = Tool::with_family(
+            self.get_zig_executable(),
+            ToolFamily::Clang { zig_cc: true },
+        );
+        // `zig cc` is modelled the same way as `CC="zig cc"`.
+        tool.cc_wrapper_args
+            .push(if self.cpp { "c++" } else { "cc" }.into());
+        // Zig uses its own target names, so don't pass `--target=` with the
+        // LLVM target in `add_default_flags`.
+        tool.has_internal_target_arg = true;
+        tool.args.push("-target".into());
+        tool.args.push(target.zig_target(glibc_version).into());
+        Ok(tool)
+    }
+
+    /// Locate an Android NDK from `ANDROID_NDK_HOME`, `ANDROID_NDK_ROOT` or
+    /// `ANDROID_HOME/ndk/<version>`.
+    fn find_android_ndk(&self) -> Option<AndroidNdk> {
+        let root = self
+            .get_env("ANDROID_NDK_HOME")
+            .or_else(|| self.get_env("ANDROID_NDK_ROOT"))
+            .filter(|root| !root.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                let ndks = PathBuf::from(self.get_env("ANDROID_HOME")?).join("ndk");
+                fs::read_dir(ndks)
+                    .ok()?
+                    .filter_map(|entry| {
+                        let entry = entry.ok()?;
+                        let version = parse_version(entry.file_name().to_str()?)?;
+                        Some((version, entry.path()))
+                    })
+                    .max()
+                    .map(|(_, path)| path)
+            })?;
+
+        match AndroidNdk::from_root(&root) {
+            Ok(ndk) => {
+                self.cargo_output.print_debug(&format_args!(
+                    "Found Android NDK {} at {}",
+                    ndk.version,
+                    root.display()
+                ));
+                Some(ndk)
+            }
+            Err(e) => {
+                self.cargo_output.print_warning(&e);
+                None
+            }
+        }
+    }
+
+    fn get_android_api_level(&self, ndk: &AndroidNdk, arch: &str) -> Result<u32, Error> {
+        let min = ndk.min_api_level_for_arch(arch);
+        let api_level = match self.android_api_level {
+            Some(api_level) => api_level,
+            None => match self.getenv_with_target_prefixes("ANDROID_API_LEVEL") {
+                Ok(api_level) => api_level
+                    .to_str()
+                    .and_then(|api_level| api_level.trim().parse().ok())
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::InvalidArgument,
+                            format!("invalid ANDROID_API_LEVEL: {}", api_level.to_string_lossy()),
+                        )
+                    })?,
+                Err(_) => return Ok(min),
+            },
+        };
+
+        if api_level < min {
+            return Err(Error::new(
+                ErrorKind::InvalidArgument,
+                format!(
+                    "Android API level {api_level} is not supported by Android NDK {} for `{arch}`, the minimum is {min}",
+                    ndk.version
+                ),
+            ));
+        }
+        if let Some(max) = ndk.max_api_level.filter(|max| api_level > *max) {
+            self.cargo_output.print_warning(&format_args!(
+                "Android API level {api_level} is newer than the maximum supported by Android NDK {} ({max})",
+                ndk.version
+            ));
+        }
+        Ok(api_level)
+    }
+
+    fn android_ndk_compiler(
+        &self,
+        target: &TargetInfo<'_>,
+        raw_target: &str,
+    ) -> Result<Option<Tool>, Error> {
+        let ndk = match self.find_android_ndk() {
+            Some(ndk) => ndk,
+            None => return Ok(None),
+        };
+        let api_level = self.get_android_api_level(&ndk, target.arch)?;
+
+        let out_dir = self.get_out_dir().ok();
+        let mut tool = Tool::new(
+            ndk.tool(if self.cpp { "clang++" } else { "clang" }),
+            &self.build_cache.cached_compiler_family,
+            &self.cargo_output,
+            out_dir.as_deref(),
+        );
+        // Invoke clang directly instead of the `<target><api>-clang` wrapper
+        // scripts, which are `.cmd` files on Windows.
+        tool.has_internal_target_arg = true;
+        tool.args.push(
+            format!(
+                "--target={}",
+                AndroidNdk::clang_target(raw_target, target.arch, api_level)
+            )
+            .into(),
+        );
+        if let Some(cc_wrapper) = self.rustc_wrapper_fallback() {
+            tool.cc_wrapper_path = Some(Path::new(&cc_wrapper).to_owned());
+        }
+        Ok(Some(tool))
+    }
+
     fn get_shell_escaped_flags(&self) -> bool {
         self.shell_escaped_flags
             .unwrap_or_else(|| self.get_env_boolean("CC_SHELL_ESCAPED_FLAGS"))
@@ -4160,6 +6004,26 @@ impl Build {
         }
     }
 
+    fn check_compiler_version(&self, tool: &Tool) -> Result<(), Error> {
+        let info = tool.info();
+        let version = match info.version {
+            Some(version) => version,
+            None => return Ok(()),
+        };
+        for (vendor, min_version) in &self.required_compiler_versions {
+            if *vendor == info.vendor && version < *min_version {
+                return Err(Error::new(
+                    ErrorKind::UnsupportedCompilerVersion,
+                    format!(
+                        "compiler `{}` is {vendor} {version}, but {vendor} {min_version} or newer is required",
+                        tool.path().display(),
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     /// Look up an environment variable, and tell Cargo that we used it.
     fn get_env(&self, v: &str) -> Option<OsString> {
         // Excluding `PATH` prevents spurious rebuilds on Windows, see
@@ -4228,15 +6092,183 @@ impl Build {
         ])
     }
 
-    /// Get a single-valued environment variable with target variants.
-    fn getenv_with_target_prefixes(&self, env: &str) -> Result<OsString, Error> {
+    /// Load the toolchain file configured via [`Build::toolchain_file`] or
+    /// `CC_TOOLCHAIN_FILE`, if any.
+    fn get_toolchain_file(&self) -> Result<Option<Arc<ToolchainFile>>, Error> {
+        let path = match &self.toolchain_file {
+            Some(path) => Cow::Borrowed(&**path),
+            None => {
+                let [target, target_u, kind, plain] = self.target_envs("CC_TOOLCHAIN_FILE")?;
+                let plain = self.target_config_applies().then_some(plain);
+                match [Some(target), Some(target_u), Some(kind), plain]
+                    .iter()
+                    .flatten()
+                    .filter_map(|env| self.get_env(env))
+                    .find(|path| !path.is_empty())
+                {
+                    Some(path) => Cow::Owned(PathBuf::from(path)),
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        if let Some(file) = self.build_cache.toolchain_files.read().unwrap().get(&*path) {
+            return Ok(Some(file.clone()));
+        }
+
+        let file = Arc::new(ToolchainFile::load(&path)?);
+        if self.emit_rerun_if_env_changed {
+            self.cargo_output.print_metadata(&format_args!(
+                "cargo:rerun-if-changed={}",
+                file.path().display()
+            ));
+        }
+        self.build_cache
+            .toolchain_files
+            .write()
+            .unwrap()
+            .insert(path.into(), file.clone());
+        Ok(Some(file))
+    }
+
+    /// The custom target JSON spec, if the target is not built into `rustc`
+    /// and its spec could be found, see [`TargetSpec::find`].
+    fn get_target_spec(&self) -> Result<Option<Arc<TargetSpec>>, Error> {
+        let rust_target_path = self.get_env("RUST_TARGET_PATH");
+        let path = match TargetSpec::find(&self.get_raw_target()?, rust_target_path.as_deref()) {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+
+        if let Some(spec) = self.build_cache.target_specs.read().unwrap().get(&*path) {
+            return Ok(Some(spec.clone()));
+        }
+
+        let json = fs::read_to_string(&path).map_err(|e| {
+            Error::new(
+                ErrorKind::IOError,
+                format!("failed to read target spec `{}`: {e}", path.display()),
+            )
+        })?;
+        let spec = TargetSpec::parse(&json).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidTarget,
+                format!("failed to parse target spec `{}`: {e}", path.display()),
+            )
+        })?;
+        let spec = Arc::new(spec);
+        if self.emit_rerun_if_env_changed {
+            self.cargo_output
+                .print_metadata(&format_args!("cargo:rerun-if-changed={}", path.display()));
+        }
+        self.build_cache
+            .target_specs
+            .write()
+            .unwrap()
+            .insert(path.into(), spec.clone());
+        Ok(Some(spec))
+    }
+
+    /// The LLVM/Clang target triple, preferring the `llvm-target` of a custom
+    /// target spec over the one derived from the target name.
+    fn get_llvm_target(
+        &self,
+        target: &TargetInfo<'_>,
+        version: Option<&str>,
+    ) -> Result<Cow<'static, str>, Error> {
+        if let Some(llvm_target) = self
+            .get_target_spec()?
+            .and_then(|spec| spec.llvm_target.clone())
+        {
+            return Ok(Cow::Owned(llvm_target));
+        }
+        let raw_target = self.get_raw_target()?;
+        Ok(target.llvm_target(TargetSpec::target_name(&raw_target), version))
+    }
+
+    /// Pass the CPU and target features of a custom target spec on to the
+    /// compiler.
+    fn add_target_spec_flags(&self, cmd: &mut Tool, target: &TargetInfo<'_>, spec: &TargetSpec) {
+        let x86 = target.arch == "x86" || target.arch == "x86_64";
+        if !(cmd.is_like_gnu() || cmd.is_like_clang()) {
+            return;
+        }
+
+        if let Some(cpu) = spec.cpu.as_deref().filter(|cpu| *cpu != "generic") {
+            // GCC and Clang select the CPU on x86 with `-march`, `-mcpu` is
+            // deprecated or an alias for `-mtune` there.
+            let flag = if x86 { "-march" } else { "-mcpu" };
+            cmd.push_cc_arg(format!("{flag}={cpu}").into());
+        }
+
+        for feature in spec.features() {
+            if cmd.is_like_clang() {
+                // The exact equivalent of `llc -mattr`.
+                for arg in ["-Xclang", "-target-feature", "-Xclang", feature] {
+                    cmd.push_cc_arg(arg.into());
+                }
+            } else if x86 {
+                // GCC names most x86 features the same way as LLVM.
+                let (enable, name) = feature.split_at(1);
+                let flag = if enable == "+" {
+                    format!("-m{name}")
+                } else {
+                    format!("-mno-{name}")
+                };
+                cmd.push_cc_arg(flag.into());
+            } else {
+                self.cargo_output.print_debug(&format_args!(
+                    "Not passing target feature `{feature}` from the target spec to GCC"
+                ));
+            }
+        }
+    }
+
+    /// Look up the toolchain file equivalent of the environment variable `env`.
+    fn toolchain_file_value(&self, env: &str) -> Result<Option<toolchain_file::Value>, Error> {
+        match self.get_toolchain_file()? {
+            Some(file) => file.lookup(env),
+            None => Ok(None),
+        }
+    }
+
+    /// Get a single-valued environment variable with target variants, or its
+    /// toolchain file equivalent.
+    ///
+    /// Target-specific variables take precedence over the toolchain file,
+    /// which in turn takes precedence over the plain variable.
+    ///
+    /// Toolchain file values are returned as `Err`, as they may be arrays.
+    fn getenv_value(
+        &self,
+        env: &str,
+    ) -> Result<Option<Result<OsString, toolchain_file::Value>>, Error> {
+        let [target, target_u, kind, plain] = self.target_envs(env)?;
+
         // Take from first environment variable in the environment.
-        let res = self
-            .target_envs(env)?
+        let res = [target, target_u, kind]
             .iter()
             .filter_map(|env| self.get_env(env))
             .next();
+        let res = match res {
+            Some(res) => Some(res),
+            None => match self.toolchain_file_value(env)? {
+                Some(value) => return Ok(Some(Err(value))),
+                None if self.target_config_applies() => self.get_env(&plain),
+                None => None,
+            },
+        };
+        Ok(res.map(Ok))
+    }
 
+    /// Get a single-valued environment variable with target variants.
+    ///
+    /// See [`Build::getenv_value`] for the precedence.
+    fn getenv_with_target_prefixes(&self, env: &str) -> Result<OsString, Error> {
+        let res = self.getenv_value(env)?.map(|res| match res {
+            Ok(var) => var,
+            Err(value) => value.into_os_string(),
+        });
         match res {
             Some(res) => Ok(res),
             None => Err(Error::new(
@@ -4254,22 +6286,51 @@ impl Build {
         // `CFLAGS`).
         let mut any_set = false;
         let mut res = vec![];
-        for env in self.target_envs(env)?.iter().rev() {
-            if let Some(var) = self.get_env(env) {
+        let [target, target_u, kind, plain] = self.target_envs(env)?;
+        if let Some(var) = self
+            .target_config_applies()
+            .then(|| self.get_env(&plain))
+            .flatten()
+        {
+            any_set = true;
+            res.extend(self.split_envflags(&var.to_string_lossy()));
+        }
+        match self.toolchain_file_value(env)? {
+            Some(toolchain_file::Value::String(s)) => {
                 any_set = true;
-
-                let var = var.to_string_lossy();
-                if self.get_shell_escaped_flags() {
-                    res.extend(Shlex::new(&var));
-                } else {
-                    res.extend(var.split_ascii_whitespace().map(ToString::to_string));
-                }
+                res.extend(self.split_envflags(&s));
+            }
+            Some(toolchain_file::Value::Array(a)) => {
+                any_set = true;
+                res.extend(a);
+            }
+            None => {}
+        }
+        for env in [kind, target_u, target] {
+            if let Some(var) = self.get_env(&env) {
+                any_set = true;
+                res.extend(self.split_envflags(&var.to_string_lossy()));
             }
         }
 
         Ok(if any_set { Some(res) } else { None })
     }
 
+    fn split_envflags(&self, var: &str) -> Vec<String> {
+        if self.get_shell_escaped_flags() {
+            Shlex::new(var).collect()
+        } else {
+            var.split_ascii_whitespace()
+                .map(ToString::to_string)
+                .collect()
+        }
+    }
+
+    fn get_sanity_check(&self) -> bool {
+        self.sanity_check
+            .unwrap_or_else(|| self.get_env_boolean("CC_SANITY_CHECK"))
+    }
+
     /// Returns true if `cc` has been disabled by `CC_FORCE_DISABLE`.
     fn is_disabled(&self) -> bool {
         self.get_env_boolean("CC_FORCE_DISABLE")
@@ -4489,6 +6550,26 @@ impl Build {
         version
     }
 
+    /// The sysroot configured via [`Build::sysroot`], `SYSROOT_<target>` or
+    /// the toolchain file, if any.
+    fn get_sysroot(&self) -> Result<Option<Cow<'_, Path>>, Error> {
+        if let Some(sysroot) = &self.sysroot {
+            return Ok(Some(Cow::Borrowed(sysroot)));
+        }
+        let [target, target_u, kind, _] = self.target_envs("SYSROOT")?;
+        if let Some(sysroot) = [target, target_u, kind]
+            .iter()
+            .filter_map(|env| self.get_env(env))
+            .find(|sysroot| !sysroot.is_empty())
+        {
+            return Ok(Some(Cow::Owned(sysroot.into())));
+        }
+        match self.get_toolchain_file()? {
+            Some(file) => Ok(file.property_path("sys_root")?.map(Cow::Owned)),
+            None => Ok(None),
+        }
+    }
+
     fn wasm_musl_sysroot(&self) -> Result<OsString, Error> {
         if let Some(musl_sysroot_path) = self.get_env("WASM_MUSL_SYSROOT") {
             Ok(musl_sysroot_path)
@@ -4503,10 +6584,12 @@ impl Build {
     fn wasi_sysroot(&self) -> Result<OsString, Error> {
         if let Some(wasi_sysroot_path) = self.get_env("WASI_SYSROOT") {
             Ok(wasi_sysroot_path)
+        } else if let Some(sysroot) = self.find_wasi_sdk().and_then(|sdk| sdk.sysroot()) {
+            Ok(sysroot.into_os_string())
         } else {
             Err(Error::new(
                 ErrorKind::EnvVarNotFound,
-                "Environment variable WASI_SYSROOT not defined. Download sysroot from GitHub & setup environment variable WASI_SYSROOT targeting the folder.",
+                "Environment variable WASI_SYSROOT not defined. Download sysroot from GitHub & setup environment variable WASI_SYSROOT targeting the folder, or point WASI_SDK_PATH at a WASI SDK.",
             ))
         }
     }
@@ -4514,7 +6597,7 @@ impl Build {
     fn cuda_file_count(&self) -> usize {
         self.files
             .iter()
-            .filter(|file| file.extension() == Some(OsStr::new("cu")))
+            .filter(|file| file.path.extension() == Some(OsStr::new("cu")))
             .count()
     }
 
@@ -4534,6 +6617,53 @@ impl Build {
         }
     }
 
+    /// Find the newest versioned variant of |tool| in `PATH`, e.g.
+    /// `aarch64-linux-gnu-gcc-12` or `clang-17`, and return its file name.
+    fn find_newest_versioned_tool(&self, tool: &str) -> Option<String> {
+        let path_entries = self.get_env("PATH")?;
+        let mut newest: Option<(Vec<u32>, String)> = None;
+        for path_entry in env::split_paths(&path_entries) {
+            let (version, name) = match newest_versioned_tool_in_dir(&path_entry, tool) {
+                Some(found) => found,
+                None => continue,
+            };
+            // Prefer earlier `PATH` entries if the versions are the same.
+            if newest
+                .as_ref()
+                .map_or(true, |(newest, _)| version > *newest)
+            {
+                newest = Some((version, name));
+            }
+        }
+        newest.map(|(_, name)| name)
+    }
+
+    /// Resolve |tool| to itself if it is in `PATH`, or otherwise to the
+    /// newest versioned variant of it in `PATH`, if any.
+    fn resolve_versioned_tool(&self, tool: &str) -> Option<String> {
+        if self.which(Path::new(tool), None).is_some() {
+            Some(tool.to_string())
+        } else {
+            self.find_newest_versioned_tool(tool)
+        }
+    }
+
+    /// If the compiler is a versioned Clang such as `clang-17`, find the
+    /// LLVM tool with the same version, e.g. `llvm-ar-17`.
+    fn llvm_tool_matching_compiler(&self, tool: &str) -> Option<PathBuf> {
+        let compiler = self.get_base_compiler().ok()?;
+        let file_stem = compiler.path.file_stem()?.to_str()?;
+        let (name, version) = file_stem.rsplit_once('-')?;
+        if !(name.ends_with("clang") || name.ends_with("clang++"))
+            || parse_version(version).is_none()
+        {
+            return None;
+        }
+        let llvm_tool = format!("llvm-{tool}-{version}");
+        self.which(Path::new(&llvm_tool), None)
+            .map(|_| PathBuf::from(llvm_tool))
+    }
+
     /// search for |prog| on 'programs' path in '|cc| --print-search-dirs' output
     fn search_programs(
         &self,
@@ -4541,8 +6671,14 @@ impl Build {
         prog: &Path,
         cargo_output: &CargoOutput,
     ) -> Option<PathBuf> {
+        let mut cmd = self.cmd(cc);
+        if let Ok(Some(sysroot)) = self.get_sysroot() {
+            let mut arg = OsString::from("--sysroot=");
+            arg.push(&*sysroot);
+            cmd.arg(arg);
+        }
         let search_dirs = run_output(
-            self.cmd(cc).arg("--print-search-dirs"),
+            cmd.arg("--print-search-dirs"),
             // this doesn't concern the compilation so we always want to show warnings.
             cargo_output,
         )
@@ -4558,12 +6694,21 @@ impl Build {
         None
     }
 
-    fn find_msvc_tools_find(&self, target: &TargetInfo<'_>, tool: &str) -> Option<Command> {
-        self.find_msvc_tools_find_tool(target, tool)
-            .map(|c| c.to_command())
+    fn find_msvc_tools_find(
+        &self,
+        target: &TargetInfo<'_>,
+        tool: &str,
+    ) -> Result<Option<Command>, Error> {
+        Ok(self
+            .find_msvc_tools_find_tool(target, tool)?
+            .map(|c| c.to_command()))
     }
 
-    fn find_msvc_tools_find_tool(&self, target: &TargetInfo<'_>, tool: &str) -> Option<Tool> {
+    fn find_msvc_tools_find_tool(
+        &self,
+        target: &TargetInfo<'_>,
+        tool: &str,
+    ) -> Result<Option<Tool>, Error> {
         struct BuildEnvGetter<'s>(&'s Build);
 
         impl ::find_msvc_tools::EnvGetter for BuildEnvGetter<'_> {
@@ -4575,11 +6720,12 @@ impl Build {
         }
 
         if target.env != "msvc" {
-            return None;
+            return Ok(None);
         }
 
-        ::find_msvc_tools::find_tool_with_env(target.full_arch, tool, &BuildEnvGetter(self))
-            .map(Tool::from_find_msvc_tools)
+        ::find_msvc_tools::try_find_tool_with_env(target.full_arch, tool, &BuildEnvGetter(self))
+            .map(|tool| tool.map(Tool::from_find_msvc_tools))
+            .map_err(|e| Error::new(ErrorKind::ToolNotFound, e.to_string()))
     }
 
     /// Compiling for WASI targets typically uses the [wasi-sdk] project and
@@ -4592,16 +6738,33 @@ impl Build {
     ///
     /// [wasi-sdk]: https://github.com/WebAssembly/wasi-sdk
     fn autodetect_wasi_compiler(&self, raw_target: &str, clang: &str) -> PathBuf {
-        if let Some(path) = self.get_env_overridable("WASI_SDK_PATH") {
-            let target_clang = Path::new(&path)
-                .join("bin")
-                .join(format!("{raw_target}-clang"));
-            if let Some(path) = self.which(&target_clang, None) {
-                return path;
-            }
+        self.find_wasi_sdk()
+            .and_then(|sdk| sdk.compiler(raw_target, clang))
+            .unwrap_or_else(|| clang.into())
+    }
+
+    fn find_wasi_sdk(&self) -> Option<WasiSdk> {
+        let root = self.get_env_overridable("WASI_SDK_PATH")?;
+        let sdk = WasiSdk::from_root(Path::new(&root));
+        if sdk.is_none() {
+            self.cargo_output.print_warning(&format_args!(
+                "WASI_SDK_PATH is set to {}, but it does not contain a WASI SDK",
+                Path::new(&root).display()
+            ));
         }
+        sdk
+    }
 
-        clang.into()
+    fn find_emsdk(&self) -> Option<Emsdk> {
+        let root = self.get_env_overridable("EMSDK")?;
+        let emsdk = Emsdk::from_root(Path::new(&root));
+        if emsdk.is_none() {
+            self.cargo_output.print_warning(&format_args!(
+                "EMSDK is set to {}, but it does not contain `upstream/emscripten`",
+                Path::new(&root).display()
+            ));
+        }
+        emsdk
     }
 
     fn pauthtest_sysroot(&self) -> Result<OsString, Error> {
@@ -4753,6 +6916,23 @@ fn map_darwin_target_from_rust_to_compiler_architecture<'a>(target: &TargetInfo<
     }
 }
 
+/// The `-march` value for armclang corresponding to a 32-bit Arm target.
+fn armclang_march(full_arch: &str) -> Option<&'static str> {
+    Some(match full_arch {
+        "armv4t" => "armv4t",
+        "armv5te" => "armv5te",
+        "thumbv6m" => "armv6-m",
+        "armv7a" | "thumbv7a" => "armv7-a",
+        "armv7r" | "armebv7r" => "armv7-r",
+        "thumbv7m" => "armv7-m",
+        "thumbv7em" => "armv7e-m",
+        "armv8r" => "armv8-r",
+        "thumbv8m.base" => "armv8-m.base",
+        "thumbv8m.main" => "armv8-m.main",
+        _ => return None,
+    })
+}
+
 fn is_arm(target: &TargetInfo<'_>) -> bool {
     matches!(target.arch, "aarch64" | "arm64ec" | "arm")
 }
@@ -4764,6 +6944,8 @@ enum AsmFileExt {
     DotAsm,
     /// `.s` or `.S` files, which do not have the special handling on MSVC targets.
     DotS,
+    /// `.nasm` files, which are always passed to NASM.
+    DotNasm,
 }
 
 impl AsmFileExt {
@@ -4774,6 +6956,7 @@ impl AsmFileExt {
                 match &*ext {
                     "asm" => return Some(AsmFileExt::DotAsm),
                     "s" => return Some(AsmFileExt::DotS),
+                    "nasm" => return Some(AsmFileExt::DotNasm),
                     _ => return None,
                 }
             }
@@ -4782,6 +6965,35 @@ impl AsmFileExt {
     }
 }
 
+/// Whether `file` is Fortran with an upper case extension, like `.F90`,
+/// which is run through the preprocessor.
+fn is_preprocessed_fortran(file: &Path) -> bool {
+    file.extension()
+        .and_then(OsStr::to_str)
+        .map_or(false, |ext| ext.starts_with('F'))
+}
+
+/// Parse a version such as `12` or `4.9` in a tool name like `gcc-4.9`.
+fn parse_version(version: &str) -> Option<Vec<u32>> {
+    version.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Find the newest `{tool}-{version}` executable in |dir|.
+fn newest_versioned_tool_in_dir(dir: &Path, tool: &str) -> Option<(Vec<u32>, String)> {
+    let prefix = format!("{tool}-");
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| {
+            let file_name = entry.ok()?.file_name().into_string().ok()?;
+            let name = file_name
+                .strip_suffix(std::env::consts::EXE_SUFFIX)
+                .unwrap_or(&file_name);
+            let version = parse_version(name.strip_prefix(&prefix)?)?;
+            Some((version, name.to_string()))
+        })
+        .max()
+}
+
 fn check_exe(mut exe: PathBuf) -> Option<PathBuf> {
     let exe_ext = std::env::consts::EXE_EXTENSION;
     let check = exe.exists() || (!exe_ext.is_empty() && exe.set_extension(exe_ext) && exe.exists());
@@ -0,0 +1,186 @@
+//! Deriving the compiler and archiver from the linker Cargo is configured
+//! with through `target.<triple>.linker`.
+
+use std::path::{Path, PathBuf};
+
+/// A gcc/clang-style compiler driver used as the linker.
+///
+/// For example, `/opt/bin/aarch64-none-linux-gnu-gcc-12` is split into the
+/// directory `/opt/bin`, the prefix `aarch64-none-linux-gnu-`, the driver
+/// `gcc` and the version suffix `-12`.
+#[derive(Debug, PartialEq)]
+pub(crate) struct LinkerDriver {
+    dir: Option<PathBuf>,
+    prefix: String,
+    driver: Driver,
+    suffix: String,
+    extension: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Driver {
+    Gcc,
+    Clang,
+    Cc,
+}
+
+impl LinkerDriver {
+    /// Parse the linker, returning `None` if it's not a gcc/clang-style
+    /// driver, e.g. `ld`, `rust-lld` or `link.exe`.
+    pub(crate) fn parse(linker: &Path) -> Option<Self> {
+        let stem = linker.file_stem()?.to_str()?;
+        let extension = match linker.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("exe") => format!(".{ext}"),
+            // `.` is not part of the name of the driver, e.g. `clang-17.0`.
+            Some(ext) => return Self::parse_name(linker, &format!("{stem}.{ext}"), String::new()),
+            None => String::new(),
+        };
+        Self::parse_name(linker, stem, extension)
+    }
+
+    fn parse_name(linker: &Path, name: &str, extension: String) -> Option<Self> {
+        // Distributions sometimes only ship versioned drivers, e.g.
+        // `aarch64-linux-gnu-gcc-12` or `clang-17`.
+        let (name, suffix) = match name.rsplit_once('-') {
+            Some((name, version))
+                if !version.is_empty()
+                    && version.chars().all(|c| c.is_ascii_digit() || c == '.') =>
+            {
+                (name, format!("-{version}"))
+            }
+            _ => (name, String::new()),
+        };
+
+        // The order matters: `clang++` ends with `g++`, and `gcc` with `cc`.
+        let (prefix, driver) = [
+            ("clang++", Driver::Clang),
+            ("clang", Driver::Clang),
+            ("g++", Driver::Gcc),
+            ("gcc", Driver::Gcc),
+            ("c++", Driver::Cc),
+            ("cc", Driver::Cc),
+        ]
+        .iter()
+        .find_map(|(driver, kind)| Some((name.strip_suffix(driver)?, *kind)))?;
+        if !prefix.is_empty() && !prefix.ends_with('-') {
+            return None;
+        }
+
+        Some(Self {
+            dir: linker
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map(Path::to_path_buf),
+            prefix: prefix.to_string(),
+            driver,
+            suffix,
+            extension,
+        })
+    }
+
+    /// The C or C++ compiler next to the linker, e.g. `aarch64-linux-gnu-g++`
+    /// for `aarch64-linux-gnu-gcc`.
+    pub(crate) fn compiler(&self, cpp: bool) -> PathBuf {
+        let compiler = match (self.driver, cpp) {
+            (Driver::Gcc, false) => "gcc",
+            (Driver::Gcc, true) => "g++",
+            (Driver::Clang, false) => "clang",
+            (Driver::Clang, true) => "clang++",
+            (Driver::Cc, false) => "cc",
+            (Driver::Cc, true) => "c++",
+        };
+        self.sibling(&format!("{}{compiler}{}", self.prefix, self.suffix))
+    }
+
+    /// The binutils-style tool next to the linker, such as `ar` or `ranlib`:
+    /// `aarch64-linux-gnu-ar` for `aarch64-linux-gnu-gcc`, or `llvm-ar-17`
+    /// for `clang-17`.
+    ///
+    /// Returns `None` for unprefixed GNU drivers, the default tool is already
+    /// the right one for those.
+    pub(crate) fn tool(&self, tool: &str) -> Option<PathBuf> {
+        match self.driver {
+            Driver::Clang => Some(self.sibling(&format!("llvm-{tool}{}", self.suffix))),
+            Driver::Gcc | Driver::Cc if !self.prefix.is_empty() => {
+                Some(self.sibling(&format!("{}{tool}", self.prefix)))
+            }
+            Driver::Gcc | Driver::Cc => None,
+        }
+    }
+
+    fn sibling(&self, name: &str) -> PathBuf {
+        let name = format!("{name}{}", self.extension);
+        match &self.dir {
+            Some(dir) => dir.join(name),
+            None => PathBuf::from(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::LinkerDriver;
+
+    fn parse(linker: &str) -> Option<LinkerDriver> {
+        LinkerDriver::parse(Path::new(linker))
+    }
+
+    #[test]
+    fn compilers() {
+        let gcc = parse("/opt/bin/aarch64-none-linux-gnu-gcc").unwrap();
+        assert_eq!(
+            gcc.compiler(false),
+            PathBuf::from("/opt/bin/aarch64-none-linux-gnu-gcc")
+        );
+        assert_eq!(
+            gcc.compiler(true),
+            PathBuf::from("/opt/bin/aarch64-none-linux-gnu-g++")
+        );
+
+        let versioned = parse("aarch64-linux-gnu-g++-12").unwrap();
+        assert_eq!(
+            versioned.compiler(false),
+            PathBuf::from("aarch64-linux-gnu-gcc-12")
+        );
+
+        let clang = parse("clang-17").unwrap();
+        assert_eq!(clang.compiler(true), PathBuf::from("clang++-17"));
+
+        let mingw = parse("x86_64-w64-mingw32-cc.exe").unwrap();
+        assert_eq!(
+            mingw.compiler(true),
+            PathBuf::from("x86_64-w64-mingw32-c++.exe")
+        );
+
+        for linker in [
+            "ld",
+            "rust-lld",
+            "link.exe",
+            "lld-link",
+            "mygcc",
+            "my-linker.sh",
+        ] {
+            assert_eq!(parse(linker), None, "{linker}");
+        }
+    }
+
+    #[test]
+    fn tools() {
+        let gcc = parse("/opt/bin/arm-none-eabi-gcc").unwrap();
+        assert_eq!(
+            gcc.tool("ar"),
+            Some(PathBuf::from("/opt/bin/arm-none-eabi-ar"))
+        );
+        assert_eq!(
+            gcc.tool("ranlib"),
+            Some(PathBuf::from("/opt/bin/arm-none-eabi-ranlib"))
+        );
+
+        let clang = parse("aarch64-linux-gnu-clang-17").unwrap();
+        assert_eq!(clang.tool("ar"), Some(PathBuf::from("llvm-ar-17")));
+
+        assert_eq!(parse("gcc").unwrap().tool("ar"), None);
+    }
+}
@@ -0,0 +1,489 @@
+//! C++20 modules, see [`Build::cpp_modules`].
+//!
+//! Each C++ file is scanned for the module it provides and the modules it
+//! imports, which the compilers report in the P1689 format. The files are
+//! then compiled in dependency order, with the built module interfaces
+//! (`.pcm` for Clang, `.gcm` for GCC and `.ifc` for MSVC) passed to the files
+//! that import them.
+//!
+//! [`Build::cpp_modules`]: crate::Build::cpp_modules
+
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    json::Value,
+    tool::{Tool, ToolFamily},
+};
+
+/// The modules a file provides and imports.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ModuleDeps {
+    pub(crate) src: PathBuf,
+    /// The module (or partition, e.g. `foo:bar`) the file provides.
+    pub(crate) provides: Option<String>,
+    /// Whether the file is an interface unit, rather than an implementation
+    /// partition.
+    pub(crate) is_interface: bool,
+    pub(crate) requires: Vec<String>,
+}
+
+impl ModuleDeps {
+    /// A file that doesn't use modules, e.g. a C file.
+    pub(crate) fn none(src: &Path) -> Self {
+        Self {
+            src: src.to_path_buf(),
+            ..Self::default()
+        }
+    }
+
+    /// Read the P1689 dependency file of `src`, see
+    /// <https://www.open-std.org/jtc1/sc22/wg21/docs/papers/2022/p1689r5.html>.
+    pub(crate) fn from_p1689(src: &Path, json: &str) -> Result<Self, String> {
+        let json = Value::parse(json)?;
+        let mut deps = Self::none(src);
+        for rule in json.get("rules").ok_or("expected `rules`")?.elements() {
+            if let Some(provides) = rule
+                .get("provides")
+                .and_then(|provides| provides.elements().first())
+            {
+                let name = provides
+                    .get("logical-name")
+                    .and_then(Value::as_str)
+                    .ok_or("expected the `logical-name` of a provided module")?;
+                deps.provides = Some(name.to_string());
+                deps.is_interface = provides.get("is-interface") != Some(&Value::Bool(false));
+            }
+            for requires in rule.get("requires").map_or(&[][..], Value::elements) {
+                let name = requires
+                    .get("logical-name")
+                    .and_then(Value::as_str)
+                    .ok_or("expected the `logical-name` of a required module")?;
+                deps.requires.push(name.to_string());
+            }
+        }
+        Ok(deps)
+    }
+}
+
+/// Where a scan command writes the dependencies: to its output, which is to
+/// be redirected into the file, or to the file itself.
+pub(crate) enum ScanOutput {
+    Stdout(PathBuf),
+    File(PathBuf),
+}
+
+impl ScanOutput {
+    /// The file the dependencies end up in.
+    pub(crate) fn path(&self) -> &Path {
+        match self {
+            ScanOutput::Stdout(path) | ScanOutput::File(path) => path,
+        }
+    }
+}
+
+/// The command that scans `src`, which is compiled into `obj` with
+/// `compiler`, for its module dependencies, or `None` if the compiler doesn't
+/// support modules.
+pub(crate) fn scan_command(
+    compiler: &Tool,
+    src: &Path,
+    obj: &Path,
+) -> Option<(Command, ScanOutput)> {
+    if compiler.cuda {
+        return None;
+    }
+    let ddi = obj.with_extension("ddi");
+    match compiler.family {
+        ToolFamily::Clang { zig_cc: false } => {
+            let mut cmd = Command::new(clang_scan_deps(compiler.path()));
+            cmd.arg("-format=p1689")
+                .arg("--")
+                .arg(compiler.path())
+                .args(
+                    compiler
+                        .args()
+                        .iter()
+                        .filter(|arg| !compiler.removed_args.contains(arg)),
+                )
+                .arg("-c")
+                .arg(src)
+                .arg("-o")
+                .arg(obj);
+            for (key, value) in compiler.env() {
+                cmd.env(key, value);
+            }
+            Some((cmd, ScanOutput::Stdout(ddi)))
+        }
+        ToolFamily::Gnu => {
+            let mut deps_file = OsString::from("-fdeps-file=");
+            deps_file.push(&ddi);
+            let mut deps_target = OsString::from("-fdeps-target=");
+            deps_target.push(obj);
+            let mut cmd = compiler.to_command();
+            cmd.arg("-E")
+                .arg("-x")
+                .arg("c++")
+                .arg(src)
+                .arg("-fmodules-ts")
+                .arg("-fdeps-format=p1689r5")
+                .arg(deps_file)
+                .arg(deps_target)
+                .arg("-MD")
+                .arg("-MF")
+                .arg(obj.with_extension("d"))
+                .arg("-o")
+                .arg(obj.with_extension("i"));
+            Some((cmd, ScanOutput::File(ddi)))
+        }
+        ToolFamily::Msvc { clang_cl: false } => {
+            let mut cmd = compiler.to_command();
+            cmd.arg("-TP").arg("-scanDependencies").arg(&ddi).arg(src);
+            Some((cmd, ScanOutput::File(ddi)))
+        }
+        _ => None,
+    }
+}
+
+/// `clang-scan-deps` next to `clang`, with the same version suffix, e.g.
+/// `/usr/bin/clang-scan-deps-17` for `/usr/bin/clang++-17`.
+fn clang_scan_deps(clang: &Path) -> PathBuf {
+    let stem = clang.file_stem().unwrap_or_default().to_string_lossy();
+    let suffix = match stem.rsplit_once('-') {
+        Some((_, version)) if version.chars().all(|c| c.is_ascii_digit() || c == '.') => {
+            format!("-{version}")
+        }
+        _ => String::new(),
+    };
+    let extension = clang
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    clang.with_file_name(format!("clang-scan-deps{suffix}{extension}"))
+}
+
+/// The built module interface of `module` in `dir`.
+pub(crate) fn bmi_path(family: ToolFamily, dir: &Path, module: &str) -> PathBuf {
+    let extension = match family {
+        ToolFamily::Gnu => "gcm",
+        ToolFamily::Msvc { .. } => "ifc",
+        _ => "pcm",
+    };
+    // `:` separates partitions, but isn't allowed in file names on Windows.
+    dir.join(format!("{}.{extension}", module.replace(':', "-")))
+}
+
+/// The module map file for GCC, listing the built module interface of each
+/// module.
+pub(crate) fn gcc_module_mapper<'a>(modules: impl Iterator<Item = (&'a str, &'a Path)>) -> String {
+    modules
+        .map(|(module, bmi)| format!("{module} {}\n", bmi.display()))
+        .collect()
+}
+
+/// Whether the extension is only used for module interface units, so the
+/// compilers might not know it's C++.
+fn is_interface_extension(src: &Path) -> bool {
+    matches!(
+        src.extension().and_then(|ext| ext.to_str()),
+        Some("cppm" | "ixx" | "mpp" | "cxxm" | "ccm")
+    )
+}
+
+/// The flags to compile `deps.src`, providing the module interface at `bmi`
+/// and importing `requires`, the modules it depends on transitively.
+pub(crate) fn module_flags(
+    family: ToolFamily,
+    deps: &ModuleDeps,
+    bmi: Option<&Path>,
+    requires: &[(&str, &Path)],
+    gcc_mapper: &Path,
+) -> Vec<OsString> {
+    let mut flags: Vec<OsString> = Vec::new();
+    if bmi.is_none() && requires.is_empty() {
+        return flags;
+    }
+    match family {
+        ToolFamily::Clang { .. } => {
+            if let Some(bmi) = bmi {
+                if deps.src.extension().and_then(|ext| ext.to_str()) != Some("cppm") {
+                    flags.push("-x".into());
+                    flags.push("c++-module".into());
+                }
+                let mut output = OsString::from("-fmodule-output=");
+                output.push(bmi);
+                flags.push(output);
+            }
+            for (module, bmi) in requires {
+                let mut file = OsString::from(format!("-fmodule-file={module}="));
+                file.push(bmi);
+                flags.push(file);
+            }
+        }
+        ToolFamily::Gnu => {
+            if is_interface_extension(&deps.src) {
+                flags.push("-x".into());
+                flags.push("c++".into());
+            }
+            flags.push("-fmodules-ts".into());
+            let mut mapper = OsString::from("-fmodule-mapper=");
+            mapper.push(gcc_mapper);
+            flags.push(mapper);
+        }
+        ToolFamily::Msvc { .. } => {
+            if let Some(bmi) = bmi {
+                if deps.is_interface {
+                    flags.push("-interface".into());
+                } else {
+                    flags.push("-internalPartition".into());
+                }
+                flags.push("-TP".into());
+                flags.push("-ifcOutput".into());
+                flags.push(bmi.into());
+            }
+            for (module, bmi) in requires {
+                flags.push("-reference".into());
+                let mut reference = OsString::from(format!("{module}="));
+                reference.push(bmi);
+                flags.push(reference);
+            }
+        }
+        ToolFamily::NvHpc | ToolFamily::Ti => {}
+    }
+    flags
+}
+
+/// The order to compile files in so that modules are built before they are
+/// imported.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Schedule {
+    /// The indices of the files, in the order to compile them in.
+    pub(crate) order: Vec<usize>,
+    /// The files providing the modules each file imports directly.
+    pub(crate) deps: Vec<Vec<usize>>,
+    /// The files providing the modules each file imports directly or
+    /// indirectly, which the compilers need to know about.
+    pub(crate) transitive: Vec<Vec<usize>>,
+}
+
+impl Schedule {
+    pub(crate) fn new(units: &[ModuleDeps]) -> Result<Self, String> {
+        let mut providers: Vec<(&str, usize)> = Vec::new();
+        for (i, unit) in units.iter().enumerate() {
+            if let Some(module) = &unit.provides {
+                if let Some((_, other)) = providers.iter().find(|(name, _)| name == module) {
+                    return Err(format!(
+                        "module `{module}` is provided by both {} and {}",
+                        units[*other].src.display(),
+                        unit.src.display()
+                    ));
+                }
+                providers.push((module, i));
+            }
+        }
+
+        // Modules that aren't provided by any file, such as `std` or those of
+        // other libraries, are left to the compiler.
+        let deps: Vec<Vec<usize>> = units
+            .iter()
+            .map(|unit| {
+                let mut deps: Vec<usize> = unit
+                    .requires
+                    .iter()
+                    .filter_map(|module| {
+                        providers
+                            .iter()
+                            .find(|(name, _)| name == module)
+                            .map(|(_, i)| *i)
+                    })
+                    .collect();
+                deps.sort_unstable();
+                deps.dedup();
+                deps
+            })
+            .collect();
+
+        // Kahn's algorithm, keeping the original order of the files where
+        // possible.
+        let mut order = Vec::with_capacity(units.len());
+        let mut scheduled = vec![false; units.len()];
+        while order.len() < units.len() {
+            let next = (0..units.len())
+                .find(|&i| !scheduled[i] && deps[i].iter().all(|&dep| scheduled[dep]));
+            match next {
+                Some(i) => {
+                    scheduled[i] = true;
+                    order.push(i);
+                }
+                None => {
+                    let cycle: Vec<_> = (0..units.len())
+                        .filter(|&i| !scheduled[i])
+                        .map(|i| units[i].src.display().to_string())
+                        .collect();
+                    return Err(format!(
+                        "cyclic module dependencies between {}",
+                        cycle.join(", ")
+                    ));
+                }
+            }
+        }
+
+        let mut transitive: Vec<Vec<usize>> = vec![Vec::new(); units.len()];
+        for &i in &order {
+            let mut all = deps[i].clone();
+            for &dep in &deps[i] {
+                all.extend_from_slice(&transitive[dep]);
+            }
+            all.sort_unstable();
+            all.dedup();
+            transitive[i] = all;
+        }
+
+        Ok(Schedule {
+            order,
+            deps,
+            transitive,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{clang_scan_deps, module_flags, ModuleDeps, Schedule};
+    use crate::tool::ToolFamily;
+
+    fn unit(src: &str, provides: Option<&str>, requires: &[&str]) -> ModuleDeps {
+        ModuleDeps {
+            src: src.into(),
+            provides: provides.map(Into::into),
+            is_interface: provides.is_some(),
+            requires: requires.iter().map(|&module| module.into()).collect(),
+        }
+    }
+
+    #[test]
+    fn p1689() {
+        let deps = ModuleDeps::from_p1689(
+            Path::new("math.cppm"),
+            r#"{
+                "revision": 0,
+                "rules": [{
+                    "primary-output": "math.o",
+                    "provides": [{ "is-interface": true, "logical-name": "math", "source-path": "math.cppm" }],
+                    "requires": [{ "logical-name": "math:detail" }, { "logical-name": "std" }]
+                }],
+                "version": 1
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            deps,
+            unit("math.cppm", Some("math"), &["math:detail", "std"])
+        );
+
+        let deps = ModuleDeps::from_p1689(
+            Path::new("main.cpp"),
+            r#"{"revision": 0, "rules": [{"primary-output": "main.o"}], "version": 1}"#,
+        )
+        .unwrap();
+        assert_eq!(deps, ModuleDeps::none(Path::new("main.cpp")));
+    }
+
+    #[test]
+    fn schedule() {
+        let units = [
+            unit("main.cpp", None, &["app"]),
+            unit("app.cppm", Some("app"), &["math", "std"]),
+            unit("util.c", None, &[]),
+            unit("math.cppm", Some("math"), &[]),
+        ];
+        let schedule = Schedule::new(&units).unwrap();
+        assert_eq!(schedule.order, [2, 3, 1, 0]);
+        assert_eq!(schedule.deps, [vec![1], vec![3], vec![], vec![]]);
+        assert_eq!(schedule.transitive[0], [1, 3]);
+
+        let cyclic = [
+            unit("a.cppm", Some("a"), &["b"]),
+            unit("b.cppm", Some("b"), &["a"]),
+        ];
+        assert!(Schedule::new(&cyclic).unwrap_err().contains("cyclic"));
+        let duplicate = [
+            unit("a.cppm", Some("a"), &[]),
+            unit("b.cppm", Some("a"), &[]),
+        ];
+        assert!(Schedule::new(&duplicate)
+            .unwrap_err()
+            .contains("provided by both"));
+    }
+
+    #[test]
+    fn flags() {
+        let app = unit("app.ixx", Some("app"), &["math"]);
+        let requires = [("math", Path::new("math.pcm"))];
+        let mapper = Path::new("mapper.txt");
+        assert_eq!(
+            module_flags(
+                ToolFamily::Clang { zig_cc: false },
+                &app,
+                Some(Path::new("app.pcm")),
+                &requires,
+                mapper,
+            ),
+            [
+                "-x",
+                "c++-module",
+                "-fmodule-output=app.pcm",
+                "-fmodule-file=math=math.pcm"
+            ]
+        );
+        assert_eq!(
+            module_flags(
+                ToolFamily::Gnu,
+                &app,
+                Some(Path::new("app.gcm")),
+                &[],
+                mapper
+            ),
+            ["-x", "c++", "-fmodules-ts", "-fmodule-mapper=mapper.txt"]
+        );
+        assert_eq!(
+            module_flags(
+                ToolFamily::Msvc { clang_cl: false },
+                &app,
+                Some(Path::new("app.ifc")),
+                &requires,
+                mapper,
+            ),
+            [
+                "-interface",
+                "-TP",
+                "-ifcOutput",
+                "app.ifc",
+                "-reference",
+                "math=math.pcm"
+            ]
+        );
+        assert!(module_flags(
+            ToolFamily::Gnu,
+            &unit("main.cpp", None, &[]),
+            None,
+            &[],
+            mapper
+        )
+        .is_empty());
+
+        assert_eq!(
+            clang_scan_deps(Path::new("/usr/bin/clang++-17")),
+            PathBuf::from("/usr/bin/clang-scan-deps-17")
+        );
+        assert_eq!(
+            clang_scan_deps(Path::new("clang")),
+            PathBuf::from("clang-scan-deps")
+        );
+    }
+}
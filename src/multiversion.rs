@@ -0,0 +1,461 @@
+//! Compiling a source file once per instruction set variant, see
+//! [`Build::multiversion`].
+//!
+//! [`Build::multiversion`]: crate::Build::multiversion
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    ffi::OsStr,
+    fmt::Write,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{Error, ErrorKind, FileOptions};
+
+/// A source file that is compiled once for each of several instruction set
+/// variants, e.g. SSE4.2, AVX2 and AVX-512, see
+/// [`Build::multiversion`](crate::Build::multiversion).
+///
+/// Each variant is compiled with its own flags, and the functions listed
+/// with [`Multiversion::function`] are renamed with the name of the variant
+/// as a suffix, so `sum` becomes `sum_avx2`. The source doesn't have to know
+/// about this, the renaming is done with `-Dsum=sum_avx2`. This is the only
+/// renaming there is, the objects aren't rewritten with e.g. `objcopy`, so
+/// symbols the preprocessor doesn't see, such as those of inline assembly,
+/// keep their names. Variants with flags the compiler doesn't support are
+/// skipped with a warning.
+///
+/// Optionally, a C dispatcher is generated which defines `sum` itself and
+/// calls the first variant that the CPU supports at runtime, using
+/// `__builtin_cpu_supports`. This needs GCC or Clang and an x86 target, the
+/// build fails otherwise, and the functions to have C linkage.
+///
+/// # Example
+///
+/// ```no_run
+/// let mut kernel = cc::Multiversion::new("src/kernel.c");
+/// kernel
+///     .variant("avx512", Some("avx512f"), &["-mavx512f"])
+///     .variant("avx2", Some("avx2"), &["-mavx2"])
+///     .variant("sse42", None, &["-msse4.2"])
+///     .function("int sum(const int *data, size_t len)")
+///     .dispatcher(true);
+///
+/// cc::Build::new().multiversion(&kernel).compile("kernel");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Multiversion {
+    file: Arc<Path>,
+    variants: Vec<Variant>,
+    functions: Vec<Arc<str>>,
+    headers: Vec<Arc<str>>,
+    dispatcher: bool,
+}
+
+#[derive(Clone, Debug)]
+struct Variant {
+    name: Arc<str>,
+    cpu_feature: Option<Arc<str>>,
+    flags: Vec<Arc<OsStr>>,
+}
+
+impl Multiversion {
+    /// Construct a new set of variants of `file`.
+    pub fn new<P: AsRef<Path>>(file: P) -> Multiversion {
+        Multiversion {
+            file: file.as_ref().into(),
+            variants: Vec::new(),
+            functions: Vec::new(),
+            headers: Vec::new(),
+            dispatcher: false,
+        }
+    }
+
+    /// Add a variant called `name`, compiled with `flags`.
+    ///
+    /// `cpu_feature` is the feature the dispatcher checks with
+    /// `__builtin_cpu_supports`, such as `avx2`. A variant without one is the
+    /// fallback. The dispatcher tries the variants in the order they are
+    /// added, so the fallback should be added last.
+    pub fn variant(
+        &mut self,
+        name: &str,
+        cpu_feature: Option<&str>,
+        flags: &[&str],
+    ) -> &mut Multiversion {
+        self.variants.push(Variant {
+            name: name.into(),
+            cpu_feature: cpu_feature.map(Into::into),
+            flags: flags.iter().map(|flag| OsStr::new(flag).into()).collect(),
+        });
+        self
+    }
+
+    /// Add a function defined in the file, renamed for each variant, given
+    /// as its C prototype such as `int sum(const int *data, size_t len)`.
+    ///
+    /// The parameters must be named for the dispatcher to forward them.
+    pub fn function(&mut self, prototype: &str) -> &mut Multiversion {
+        self.functions.push(prototype.into());
+        self
+    }
+
+    /// Add a header to include in the dispatcher, for the types used by the
+    /// functions. `<stddef.h>` and `<stdint.h>` are always included.
+    pub fn header(&mut self, header: &str) -> &mut Multiversion {
+        self.headers.push(header.into());
+        self
+    }
+
+    /// Configures whether to generate a dispatcher.
+    ///
+    /// This option defaults to `false`.
+    pub fn dispatcher(&mut self, dispatcher: bool) -> &mut Multiversion {
+        self.dispatcher = dispatcher;
+        self
+    }
+
+    pub(crate) fn file(&self) -> &Path {
+        &self.file
+    }
+
+    /// The flags of each variant, for probing.
+    pub(crate) fn variant_flags(&self) -> impl Iterator<Item = (&str, &[Arc<OsStr>])> {
+        self.variants
+            .iter()
+            .map(|variant| (&*variant.name, &*variant.flags))
+    }
+
+    /// Check the names of the variants and the function prototypes, before
+    /// anything is compiled.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        for (i, variant) in self.variants.iter().enumerate() {
+            if variant.name.is_empty() || !variant.name.chars().all(is_ident_char) {
+                return Err(Error::new(
+                    ErrorKind::InvalidArgument,
+                    format!(
+                        "multiversion variant `{}` is not a valid identifier suffix",
+                        variant.name
+                    ),
+                ));
+            }
+            if self.variants[..i]
+                .iter()
+                .any(|other| other.name == variant.name)
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidArgument,
+                    format!("multiversion variant `{}` is added twice", variant.name),
+                ));
+            }
+        }
+        for prototype in &self.functions {
+            Prototype::parse(prototype)?;
+        }
+        Ok(())
+    }
+
+    /// The options to compile the variant `name` with.
+    pub(crate) fn options(&self, name: &str) -> Result<FileOptions, Error> {
+        let variant = self.find_variant(name)?;
+        let mut options = FileOptions::new();
+        for flag in &variant.flags {
+            options.flag(flag);
+        }
+        for prototype in &self.functions {
+            let function = Prototype::parse(prototype)?;
+            options.define(
+                function.name,
+                &*format!("{}_{}", function.name, variant.name),
+            );
+        }
+        Ok(options)
+    }
+
+    /// Whether a dispatcher is generated.
+    pub(crate) fn has_dispatcher(&self) -> bool {
+        self.dispatcher
+    }
+
+    /// The path to generate the dispatcher at in `out_dir`, if enabled.
+    pub(crate) fn dispatcher_path(&self, out_dir: &Path) -> Option<PathBuf> {
+        if !self.dispatcher {
+            return None;
+        }
+        // Hash the path, so that e.g. `a/kernel.c` and `b/kernel.c` don't
+        // share a dispatcher.
+        let mut hasher = DefaultHasher::new();
+        self.file.hash(&mut hasher);
+        let stem = self.file.file_stem().unwrap_or_default().to_string_lossy();
+        Some(out_dir.join(format!("{:016x}-{stem}_dispatch.c", hasher.finish())))
+    }
+
+    /// Generate the dispatcher between the `variants` that were compiled.
+    pub(crate) fn dispatcher_source(&self, variants: &[&str]) -> Result<String, Error> {
+        let variants = variants
+            .iter()
+            .map(|name| self.find_variant(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let functions = self
+            .functions
+            .iter()
+            .map(|prototype| Prototype::parse(prototype))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut src = format!(
+            "/* Dispatcher between the variants of `{}`, generated by the cc crate. */\n\n",
+            self.file.display()
+        );
+        src.push_str("#include <stddef.h>\n#include <stdint.h>\n");
+        for header in &self.headers {
+            if header.starts_with('<') {
+                let _ = writeln!(src, "#include {header}");
+            } else {
+                let _ = writeln!(src, "#include \"{header}\"");
+            }
+        }
+
+        for function in &functions {
+            src.push('\n');
+            for variant in &variants {
+                let _ = writeln!(
+                    src,
+                    "{} {}_{}({});",
+                    function.ret, function.name, variant.name, function.params
+                );
+            }
+
+            let _ = writeln!(
+                src,
+                "\n{} {}({}) {{",
+                function.ret, function.name, function.params
+            );
+            src.push_str("    __builtin_cpu_init();\n");
+            let call = |variant: &Variant| {
+                let call = format!(
+                    "{}_{}({})",
+                    function.name,
+                    variant.name,
+                    function.args.join(", ")
+                );
+                if function.ret == "void" {
+                    format!("{call};\n        return;")
+                } else {
+                    format!("return {call};")
+                }
+            };
+            let mut fallback = None;
+            for variant in &variants {
+                match &variant.cpu_feature {
+                    Some(feature) => {
+                        let _ = writeln!(
+                            src,
+                            "    if (__builtin_cpu_supports(\"{feature}\")) {{\n        {}\n    }}",
+                            call(variant)
+                        );
+                    }
+                    None => {
+                        fallback = Some(variant);
+                        break;
+                    }
+                }
+            }
+            match fallback {
+                // Dedent the call, it's not in an `if` block.
+                Some(variant) => {
+                    let _ = writeln!(src, "    {}", call(variant).replace("\n        ", "\n    "));
+                }
+                None => src.push_str("    __builtin_trap();\n"),
+            }
+            src.push_str("}\n");
+        }
+        Ok(src)
+    }
+
+    fn find_variant(&self, name: &str) -> Result<&Variant, Error> {
+        self.variants
+            .iter()
+            .find(|variant| &*variant.name == name)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidArgument,
+                    format!("unknown multiversion variant `{name}`"),
+                )
+            })
+    }
+}
+
+/// A C function prototype, split into the parts the dispatcher needs.
+#[derive(Debug, PartialEq)]
+struct Prototype<'a> {
+    ret: &'a str,
+    name: &'a str,
+    params: &'a str,
+    args: Vec<&'a str>,
+}
+
+impl<'a> Prototype<'a> {
+    fn parse(prototype: &'a str) -> Result<Self, Error> {
+        let invalid = |reason: &str| {
+            Error::new(
+                ErrorKind::InvalidArgument,
+                format!("invalid multiversion function `{prototype}`: {reason}"),
+            )
+        };
+
+        let prototype = prototype.trim().trim_end_matches(';').trim_end();
+        let (head, params) = prototype
+            .split_once('(')
+            .ok_or_else(|| invalid("expected a parameter list"))?;
+        let params = params
+            .strip_suffix(')')
+            .ok_or_else(|| invalid("expected `)` at the end"))?
+            .trim();
+        let head = head.trim_end();
+        let name = trailing_ident(head);
+        let ret = head[..head.len() - name.len()].trim_end();
+        if name.is_empty() || ret.is_empty() {
+            return Err(invalid("expected a return type and a name"));
+        }
+
+        let mut args = Vec::new();
+        if !params.is_empty() && params != "void" {
+            for param in params.split(',') {
+                let param = param.trim();
+                if param == "..." {
+                    return Err(invalid("variadic functions can't be dispatched"));
+                }
+                // Strip array brackets, e.g. `float out[4]`.
+                let declarator = match param.find('[') {
+                    Some(bracket) => param[..bracket].trim_end(),
+                    None => param,
+                };
+                let arg = trailing_ident(declarator);
+                if arg.is_empty() || arg.len() == declarator.len() {
+                    return Err(invalid("parameters must be named"));
+                }
+                args.push(arg);
+            }
+        }
+
+        Ok(Prototype {
+            ret,
+            name,
+            params,
+            args,
+        })
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// The identifier at the end of `s`, e.g. `len` in `size_t len`.
+fn trailing_ident(s: &str) -> &str {
+    let start = s
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_ident_char(*c))
+        .last()
+        .map_or(s.len(), |(i, _)| i);
+    &s[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Multiversion, Prototype};
+    use crate::FileOptions;
+
+    #[test]
+    fn parse_prototype() {
+        assert_eq!(
+            Prototype::parse("int sum(const int *data, size_t len)").unwrap(),
+            Prototype {
+                ret: "int",
+                name: "sum",
+                params: "const int *data, size_t len",
+                args: vec!["data", "len"],
+            }
+        );
+        let prototype = Prototype::parse("const char *name(void);").unwrap();
+        assert_eq!((prototype.ret, prototype.name), ("const char *", "name"));
+        assert!(prototype.args.is_empty());
+        assert_eq!(
+            Prototype::parse("void scale(float out[4], float k)")
+                .unwrap()
+                .args,
+            ["out", "k"]
+        );
+
+        assert!(Prototype::parse("int sum(const int *, size_t)").is_err());
+        assert!(Prototype::parse("int log(const char *fmt, ...)").is_err());
+        assert!(Prototype::parse("sum(int a)").is_err());
+        assert!(Prototype::parse("int sum").is_err());
+    }
+
+    #[test]
+    fn dispatcher() {
+        let mut kernel = Multiversion::new("src/kernel.c");
+        kernel
+            .variant("avx2", Some("avx2"), &["-mavx2"])
+            .variant("sse42", None, &["-msse4.2"])
+            .function("int sum(const int *data, size_t len)")
+            .function("void clear(int *data)")
+            .header("kernel.h")
+            .dispatcher(true);
+
+        let src = kernel.dispatcher_source(&["avx2", "sse42"]).unwrap();
+        assert!(src.contains("#include \"kernel.h\"\n"), "{src}");
+        assert!(
+            src.contains("int sum_sse42(const int *data, size_t len);\n"),
+            "{src}"
+        );
+        assert!(
+            src.contains(
+                "    if (__builtin_cpu_supports(\"avx2\")) {\n        return sum_avx2(data, len);\n    }\n    return sum_sse42(data, len);\n}\n"
+            ),
+            "{src}"
+        );
+        assert!(
+            src.contains(
+                "        clear_avx2(data);\n        return;\n    }\n    clear_sse42(data);\n"
+            ),
+            "{src}"
+        );
+
+        // Without the fallback, there's nothing to call on older CPUs.
+        let src = kernel.dispatcher_source(&["avx2"]).unwrap();
+        assert!(src.contains("__builtin_trap();"), "{src}");
+
+        let mut options = FileOptions::new();
+        options
+            .flag("-mavx2")
+            .define("sum", "sum_avx2")
+            .define("clear", "clear_avx2");
+        assert_eq!(kernel.options("avx2").unwrap(), options);
+        assert!(kernel.validate().is_ok());
+        assert!(kernel.options("avx512").is_err());
+    }
+
+    #[test]
+    fn validate() {
+        let mut kernel = Multiversion::new("src/kernel.c");
+        kernel.variant("avx-2", Some("avx2"), &["-mavx2"]);
+        assert!(kernel.validate().is_err());
+
+        let mut kernel = Multiversion::new("src/kernel.c");
+        kernel
+            .variant("avx2", Some("avx2"), &["-mavx2"])
+            .variant("avx2", None, &[]);
+        assert!(kernel.validate().is_err());
+
+        let mut kernel = Multiversion::new("src/kernel.c");
+        kernel
+            .variant("avx2", Some("avx2"), &["-mavx2"])
+            .function("int sum(int, int)");
+        assert!(kernel.validate().is_err());
+    }
+}
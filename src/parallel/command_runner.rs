@@ -1,6 +1,8 @@
 use std::{
     cell::Cell,
+    fs,
     io::{self, Write as _},
+    path::PathBuf,
     process::{Child, Command},
 };
 
@@ -9,7 +11,7 @@ use crate::{
         async_executor::{block_on, YieldOnce},
         job_token,
     },
-    spawn, CargoOutput, Error, ErrorKind, StderrForwarder,
+    spawn, spawn_with_stdout, CargoOutput, Error, ErrorKind, StderrForwarder,
 };
 
 struct KillOnDrop(Child, StderrForwarder);
@@ -66,9 +68,18 @@ fn try_wait_on_child(
     }
 }
 
+/// Run `cmds` in parallel, where `deps[i]` are the indices of the earlier
+/// commands that have to finish before the `i`th command is started, e.g.
+/// because they build a module it imports. Commands without an entry in
+/// `deps` can start right away.
+///
+/// The output of the `i`th command is written to `stdouts[i]`, if set, and
+/// otherwise handled as configured in `cargo_output`.
 pub(crate) fn run_commands_in_parallel(
     cargo_output: &CargoOutput,
     cmds: &mut dyn Iterator<Item = Result<Command, Error>>,
+    deps: &[Vec<usize>],
+    stdouts: &[Option<PathBuf>],
 ) -> Result<(), Error> {
     // Limit our parallelism globally with a jobserver.
     let mut tokens = job_token::ActiveJobTokenServer::new();
@@ -92,9 +103,13 @@ pub(crate) fn run_commands_in_parallel(
     // acquire the appropriate tokens, Once all objects have been compiled
     // we wait on all the processes and propagate the results of compilation.
 
-    let pendings = Cell::new(Vec::<(Command, KillOnDrop, job_token::JobToken)>::new());
+    let pendings = Cell::new(Vec::<(usize, Command, KillOnDrop, job_token::JobToken)>::new());
     let is_disconnected = Cell::new(false);
     let has_made_progress = Cell::new(false);
+    // The indices of the commands that finished successfully, and whether
+    // any failed, for commands waiting on others.
+    let finished = Cell::new(Vec::<usize>::new());
+    let has_failed = Cell::new(false);
 
     let wait_future = async {
         let mut error = None;
@@ -113,11 +128,15 @@ pub(crate) fn run_commands_in_parallel(
 
             cell_update(&pendings, |mut pendings| {
                 // Try waiting on them.
-                pendings.retain_mut(|(cmd, child, _token)| {
+                pendings.retain_mut(|(i, cmd, child, _token)| {
                     match try_wait_on_child(cmd, &mut child.0, &mut stdout, &mut child.1) {
                         Ok(Some(())) => {
                             // Task done, remove the entry
                             has_made_progress.set(true);
+                            cell_update(&finished, |mut finished| {
+                                finished.push(*i);
+                                finished
+                            });
                             false
                         }
                         Ok(None) => true, // Task still not finished, keep the entry
@@ -126,6 +145,7 @@ pub(crate) fn run_commands_in_parallel(
                             // Since we can only return one error, log the error to make
                             // sure users always see all the compilation failures.
                             has_made_progress.set(true);
+                            has_failed.set(true);
 
                             if cargo_output.warnings {
                                 let _ = writeln!(stdout, "cargo:warning={}", err);
@@ -152,15 +172,39 @@ pub(crate) fn run_commands_in_parallel(
         }
     };
     let spawn_future = async {
-        for res in cmds {
+        for (i, res) in cmds.enumerate() {
             let mut cmd = res?;
+
+            // Wait for the commands this one depends on before acquiring a
+            // token, so that waiting doesn't take up a job. If one of them
+            // failed, the error is reported by the other future.
+            let deps = deps.get(i).map_or(&[][..], Vec::as_slice);
+            while !deps.is_empty() {
+                let done = finished.take();
+                let is_ready = deps.iter().all(|dep| done.contains(dep));
+                finished.set(done);
+                if is_ready {
+                    break;
+                }
+                if has_failed.get() {
+                    is_disconnected.set(true);
+                    return Ok(());
+                }
+                YieldOnce::default().await;
+            }
+
             let token = tokens.acquire().await?;
-            let mut child = spawn(&mut cmd, cargo_output)?;
+            let mut child = match stdouts.get(i).and_then(Option::as_ref) {
+                Some(stdout) => {
+                    spawn_with_stdout(&mut cmd, cargo_output, fs::File::create(stdout)?.into())?
+                }
+                None => spawn(&mut cmd, cargo_output)?,
+            };
             let mut stderr_forwarder = StderrForwarder::new(&mut child);
             stderr_forwarder.set_non_blocking()?;
 
             cell_update(&pendings, |mut pendings| {
-                pendings.push((cmd, KillOnDrop(child, stderr_forwarder), token));
+                pendings.push((i, cmd, KillOnDrop(child, stderr_forwarder), token));
                 pendings
             });
 
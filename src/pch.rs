@@ -0,0 +1,189 @@
+//! Precompiled headers, see [`Build::precompiled_header`].
+//!
+//! [`Build::precompiled_header`]: crate::Build::precompiled_header
+
+use std::{
+    collections::hash_map,
+    env,
+    ffi::OsString,
+    fs,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::tool::{Tool, ToolFamily};
+
+/// A header compiled once, and how to use it when compiling each object.
+#[derive(Debug)]
+pub(crate) struct PrecompiledHeader {
+    /// Whether the header was compiled as C++. It can only be used for
+    /// objects of the same language.
+    pub(crate) cpp: bool,
+    /// The flags that make the compiler use the precompiled header.
+    pub(crate) flags: Vec<OsString>,
+    /// The flags that include the header itself instead, for objects with
+    /// their own flags, which the precompiled header may not be valid for.
+    pub(crate) include_flags: Vec<OsString>,
+    /// The object MSVC produces along with the precompiled header, which has
+    /// to be archived with the other objects.
+    pub(crate) object: Option<(PathBuf, PathBuf)>,
+}
+
+impl PrecompiledHeader {
+    /// The command that precompiles `header` with `compiler` into `out_dir`,
+    /// and how to use the result, or `None` if the compiler doesn't support
+    /// precompiled headers.
+    ///
+    /// As in `CMake`, the header is included from a generated wrapper, so that
+    /// the compiler falls back to the header itself if the precompiled one
+    /// can't be used.
+    pub(crate) fn new(
+        compiler: &Tool,
+        header: &Path,
+        out_dir: &Path,
+        cpp: bool,
+    ) -> io::Result<Option<(Command, Self)>> {
+        if compiler.cuda || matches!(compiler.family, ToolFamily::NvHpc | ToolFamily::Ti) {
+            return Ok(None);
+        }
+
+        let header = if header.is_relative() {
+            env::current_dir()?.join(header)
+        } else {
+            header.to_path_buf()
+        };
+        let dir = out_dir.join("pch");
+        fs::create_dir_all(&dir)?;
+        let mut hasher = hash_map::DefaultHasher::new();
+        header.hash(&mut hasher);
+        cpp.hash(&mut hasher);
+        let stem = header.file_stem().unwrap_or_default().to_string_lossy();
+        let wrapper = dir.join(format!("{:016x}-{stem}.h", hasher.finish()));
+        fs::write(&wrapper, format!("#include \"{}\"\n", header.display()))?;
+
+        let mut cmd = compiler.to_command();
+        let mut flags: Vec<OsString> = Vec::new();
+        let include_flags: Vec<OsString>;
+        let mut object = None;
+        match compiler.family {
+            ToolFamily::Msvc { .. } => {
+                // `/Yc` precompiles everything up to and including the header
+                // while compiling a source file, which is forced to include
+                // it with `/FI` like the objects are.
+                let src = wrapper.with_extension(if cpp { "cpp" } else { "c" });
+                fs::write(&src, "")?;
+                let obj = wrapper.with_extension("obj");
+                let pch = wrapper.with_extension("pch");
+                let mut create = OsString::from("-Yc");
+                create.push(&wrapper);
+                let mut use_pch = OsString::from("-Yu");
+                use_pch.push(&wrapper);
+                let mut force_include = OsString::from("-FI");
+                force_include.push(&wrapper);
+                let mut pch_file = OsString::from("-Fp");
+                pch_file.push(&pch);
+                let mut obj_file = OsString::from("-Fo");
+                obj_file.push(&obj);
+
+                cmd.arg(create)
+                    .arg(&force_include)
+                    .arg(&pch_file)
+                    .arg(obj_file)
+                    .arg("-c")
+                    .arg(&src);
+                flags = vec![use_pch, force_include, pch_file];
+                let mut force_include = OsString::from("-FI");
+                force_include.push(&header);
+                include_flags = vec![force_include];
+                object = Some((src, obj));
+            }
+            ToolFamily::Clang { .. } => {
+                let pch = wrapper.with_extension("h.pch");
+                cmd.arg("-x")
+                    .arg(if cpp { "c++-header" } else { "c-header" })
+                    .arg(&wrapper)
+                    .arg("-o")
+                    .arg(&pch);
+                flags.push("-include-pch".into());
+                flags.push(pch.into());
+                include_flags = vec!["-include".into(), header.into()];
+            }
+            ToolFamily::Gnu => {
+                // GCC picks up `wrapper.h.gch` when including `wrapper.h`.
+                cmd.arg("-x")
+                    .arg(if cpp { "c++-header" } else { "c-header" })
+                    .arg(&wrapper)
+                    .arg("-o")
+                    .arg(wrapper.with_extension("h.gch"));
+                flags.push("-include".into());
+                flags.push(wrapper.into());
+                // Not the wrapper, GCC would try the precompiled one for that.
+                include_flags = vec!["-include".into(), header.into()];
+            }
+            ToolFamily::NvHpc | ToolFamily::Ti => unreachable!(),
+        }
+        Ok(Some((
+            cmd,
+            PrecompiledHeader {
+                cpp,
+                flags,
+                include_flags,
+                object,
+            },
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::PrecompiledHeader;
+    use crate::tool::{Tool, ToolFamily};
+
+    fn precompile(family: ToolFamily, cpp: bool) -> (Vec<String>, PrecompiledHeader) {
+        let compiler = Tool::with_family(PathBuf::from("cc"), family);
+        let out_dir = tempfile::tempdir().unwrap();
+        let (cmd, pch) =
+            PrecompiledHeader::new(&compiler, Path::new("/src/big.h"), out_dir.path(), cpp)
+                .unwrap()
+                .unwrap();
+        let args = cmd
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        (args, pch)
+    }
+
+    #[test]
+    fn pch_flags() {
+        let (args, pch) = precompile(ToolFamily::Gnu, true);
+        assert_eq!(args[..2], ["-x", "c++-header"]);
+        assert!(args[4].ends_with("-big.h.gch"), "{args:?}");
+        assert_eq!(pch.flags[0], "-include");
+        assert_eq!(pch.flags[1], *args[2]);
+        assert_eq!(pch.include_flags, ["-include", "/src/big.h"]);
+
+        let (args, pch) = precompile(ToolFamily::Clang { zig_cc: false }, false);
+        assert_eq!(args[..2], ["-x", "c-header"]);
+        assert_eq!(pch.flags[0], "-include-pch");
+        assert_eq!(pch.flags[1], *args[4]);
+
+        let (args, pch) = precompile(ToolFamily::Msvc { clang_cl: false }, true);
+        assert!(args[0].starts_with("-Yc") && args[1].starts_with("-FI"));
+        assert!(pch.flags[0].to_string_lossy().starts_with("-Yu"));
+        assert_eq!(pch.flags[1], *args[1]);
+        let (src, obj) = pch.object.unwrap();
+        assert_eq!(src.extension().unwrap(), "cpp");
+        assert_eq!(obj.extension().unwrap(), "obj");
+
+        let compiler = Tool::with_family(PathBuf::from("nvc"), ToolFamily::NvHpc);
+        assert!(
+            PrecompiledHeader::new(&compiler, Path::new("big.h"), Path::new("."), false)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,408 @@
+//! Inspection of the object produced by the compiler sanity check, see
+//! [`Build::sanity_check`](crate::Build::sanity_check).
+
+use std::fmt;
+
+use crate::target::TargetInfo;
+
+/// The file format of an object file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ObjectFormat {
+    Elf,
+    Coff,
+    MachO,
+    Wasm,
+}
+
+impl fmt::Display for ObjectFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ObjectFormat::Elf => "ELF",
+            ObjectFormat::Coff => "COFF",
+            ObjectFormat::MachO => "Mach-O",
+            ObjectFormat::Wasm => "WebAssembly",
+        })
+    }
+}
+
+/// The format and machine of an object file, as read from its header.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ObjectInfo {
+    pub(crate) format: ObjectFormat,
+    /// `e_machine` for ELF, `Machine` for COFF and `cputype` for Mach-O.
+    pub(crate) machine: u32,
+    /// Whether this is a 64-bit ELF object, `None` for other formats.
+    pub(crate) elf64: Option<bool>,
+}
+
+impl ObjectInfo {
+    /// Parse the header of an object file. Returns `None` if the format is
+    /// not recognized, e.g. for LLVM bitcode produced with `-flto`.
+    pub(crate) fn parse(data: &[u8]) -> Option<Self> {
+        let u16_at = |offset: usize, big_endian: bool| {
+            let bytes = [*data.get(offset)?, *data.get(offset + 1)?];
+            Some(if big_endian {
+                u16::from_be_bytes(bytes)
+            } else {
+                u16::from_le_bytes(bytes)
+            })
+        };
+        let u32_at = |offset: usize| {
+            let bytes = data.get(offset..offset + 4)?;
+            Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        };
+
+        match data.get(..4)? {
+            [0x7f, b'E', b'L', b'F'] => {
+                let elf64 = *data.get(4)? == 2;
+                let big_endian = *data.get(5)? == 2;
+                Some(Self {
+                    format: ObjectFormat::Elf,
+                    machine: u16_at(18, big_endian)?.into(),
+                    elf64: Some(elf64),
+                })
+            }
+            [0xce | 0xcf, 0xfa, 0xed, 0xfe] => Some(Self {
+                format: ObjectFormat::MachO,
+                machine: u32_at(4)?,
+                elf64: None,
+            }),
+            [0x00, b'a', b's', b'm'] => Some(Self {
+                format: ObjectFormat::Wasm,
+                machine: 0,
+                elf64: None,
+            }),
+            // The "big object" header used by MSVC's `/bigobj`.
+            [0x00, 0x00, 0xff, 0xff] => Some(Self {
+                format: ObjectFormat::Coff,
+                machine: u16_at(6, false)?.into(),
+                elf64: None,
+            }),
+            _ => {
+                let machine = u16_at(0, false)?.into();
+                COFF_MACHINES.contains(&machine).then_some(Self {
+                    format: ObjectFormat::Coff,
+                    machine,
+                    elf64: None,
+                })
+            }
+        }
+    }
+}
+
+const IMAGE_FILE_MACHINE_I386: u32 = 0x14c;
+const IMAGE_FILE_MACHINE_ARMNT: u32 = 0x1c4;
+const IMAGE_FILE_MACHINE_AMD64: u32 = 0x8664;
+const IMAGE_FILE_MACHINE_ARM64: u32 = 0xaa64;
+const IMAGE_FILE_MACHINE_ARM64EC: u32 = 0xa641;
+const IMAGE_FILE_MACHINE_ARM64X: u32 = 0xa64e;
+
+const COFF_MACHINES: &[u32] = &[
+    IMAGE_FILE_MACHINE_I386,
+    IMAGE_FILE_MACHINE_ARMNT,
+    IMAGE_FILE_MACHINE_AMD64,
+    IMAGE_FILE_MACHINE_ARM64,
+    IMAGE_FILE_MACHINE_ARM64EC,
+    IMAGE_FILE_MACHINE_ARM64X,
+];
+
+/// What the object file for a target is expected to look like.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Expected {
+    pub(crate) format: ObjectFormat,
+    /// The accepted machine values, empty if unknown.
+    pub(crate) machines: &'static [u32],
+    pub(crate) elf64: Option<bool>,
+}
+
+impl Expected {
+    /// Returns `None` for targets whose object format isn't known, e.g. AIX
+    /// with XCOFF.
+    pub(crate) fn for_target(target: &TargetInfo<'_>) -> Option<Self> {
+        if target.vendor == "apple" {
+            Some(Self {
+                format: ObjectFormat::MachO,
+                machines: match target.arch {
+                    "x86" => &[7],
+                    "x86_64" => &[0x0100_0007],
+                    "arm" => &[12],
+                    // `arm64_32` on watchOS.
+                    "aarch64" => &[0x0100_000c, 0x0200_000c],
+                    _ => &[],
+                },
+                elf64: None,
+            })
+        } else if target.arch == "wasm32" || target.arch == "wasm64" {
+            Some(Self {
+                format: ObjectFormat::Wasm,
+                machines: &[],
+                elf64: None,
+            })
+        } else if target.os == "windows" || target.os == "uefi" {
+            let machines: &[u32] = match target.arch {
+                "x86" => &[IMAGE_FILE_MACHINE_I386],
+                "x86_64" => &[IMAGE_FILE_MACHINE_AMD64],
+                "arm" => &[IMAGE_FILE_MACHINE_ARMNT],
+                "aarch64" => &[IMAGE_FILE_MACHINE_ARM64],
+                "arm64ec" => &[
+                    IMAGE_FILE_MACHINE_ARM64EC,
+                    IMAGE_FILE_MACHINE_ARM64X,
+                    IMAGE_FILE_MACHINE_ARM64,
+                ],
+                _ => &[],
+            };
+            Some(Self {
+                format: ObjectFormat::Coff,
+                machines,
+                elf64: None,
+            })
+        } else if target.os == "aix" {
+            None
+        } else {
+            let (machines, elf64): (&[u32], _) = match target.arch {
+                "x86" => (&[3], None),
+                "x86_64" => (&[62], None),
+                "arm" => (&[40], None),
+                "aarch64" => (&[183], None),
+                "riscv32" => (&[243], Some(false)),
+                "riscv64" => (&[243], Some(true)),
+                "powerpc" => (&[20], None),
+                "powerpc64" => (&[21], None),
+                "mips" | "mips32r6" | "mips64" | "mips64r6" => (&[8], None),
+                "s390x" => (&[22], None),
+                "sparc" => (&[2, 18], None),
+                "sparc64" => (&[43], None),
+                "loongarch32" => (&[258], Some(false)),
+                "loongarch64" => (&[258], Some(true)),
+                "m68k" => (&[4], None),
+                "msp430" => (&[105], None),
+                "avr" => (&[83], None),
+                "hexagon" => (&[164], None),
+                "csky" => (&[252], None),
+                "xtensa" => (&[94], None),
+                "bpf" => (&[247], None),
+                _ => (&[], None),
+            };
+            Some(Self {
+                format: ObjectFormat::Elf,
+                machines,
+                elf64,
+            })
+        }
+    }
+
+    /// Whether `object` matches these expectations.
+    pub(crate) fn matches(&self, object: &ObjectInfo) -> bool {
+        object.format == self.format
+            && (self.machines.is_empty() || self.machines.contains(&object.machine))
+            && (self.elf64.is_none() || self.elf64 == object.elf64)
+    }
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format)?;
+        match self.elf64 {
+            Some(true) => f.write_str(" (64-bit)")?,
+            Some(false) => f.write_str(" (32-bit)")?,
+            None => {}
+        }
+        match self.machines {
+            [] => Ok(()),
+            [machine] => write!(f, " with machine {machine:#x}"),
+            [machines @ .., last] => {
+                f.write_str(" with machine ")?;
+                for machine in machines {
+                    write!(f, "{machine:#x}, ")?;
+                }
+                write!(f, "or {last:#x}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ObjectInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format)?;
+        match self.elf64 {
+            Some(true) => f.write_str(" (64-bit)")?,
+            Some(false) => f.write_str(" (32-bit)")?,
+            None => {}
+        }
+        if self.format != ObjectFormat::Wasm {
+            write!(f, " with machine {:#x}", self.machine)?;
+        }
+        Ok(())
+    }
+}
+
+/// Which distribution packages provide the compiler `name` (the file name
+/// without extension, e.g. `aarch64-linux-gnu-gcc`), if it's well known.
+pub(crate) fn package_hint(name: &str) -> Option<String> {
+    // Distributions sometimes only ship versioned cross compilers, e.g.
+    // `aarch64-linux-gnu-gcc-12`.
+    let name = match name.rsplit_once('-') {
+        Some((name, version)) if version.parse::<u32>().is_ok() => name,
+        _ => name,
+    };
+    if name.starts_with("clang") {
+        return Some(format!(
+            "`{name}` is provided by the `clang` package on most distributions"
+        ));
+    }
+    let (prefix, tool) = name.rsplit_once('-')?;
+    let cpp = match tool {
+        "gcc" | "cc" => false,
+        "g++" | "c++" => true,
+        _ => return None,
+    };
+    let gcc = if cpp { "g++" } else { "gcc" };
+
+    let (debian, fedora, arch) = if let Some(arch) = prefix.strip_suffix("-w64-mingw32") {
+        let bits = if arch == "x86_64" { 64 } else { 32 };
+        let fedora_gcc = if cpp { "gcc-c++" } else { "gcc" };
+        (
+            format!("{gcc}-mingw-w64-{}", arch.replace('_', "-")),
+            format!("mingw{bits}-{fedora_gcc}"),
+            "mingw-w64-gcc".to_string(),
+        )
+    } else if prefix.ends_with("-none-eabi") {
+        (
+            format!("gcc-{prefix}"),
+            format!("{prefix}-gcc-cs{}", if cpp { "-c++" } else { "" }),
+            format!("{prefix}-gcc"),
+        )
+    } else if prefix.contains("-linux-") {
+        let fedora_gcc = if cpp { "gcc-c++" } else { "gcc" };
+        (
+            format!("{gcc}-{prefix}"),
+            format!("{fedora_gcc}-{prefix}"),
+            format!("{prefix}-gcc"),
+        )
+    } else {
+        return None;
+    };
+    Some(format!(
+        "`{name}` is provided by the `{debian}` package on Debian and Ubuntu, \
+         `{fedora}` on Fedora and `{arch}` on Arch Linux"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elf(class: u8, data: u8, machine: [u8; 2]) -> Vec<u8> {
+        let mut header = vec![0; 64];
+        header[..4].copy_from_slice(b"\x7fELF");
+        header[4] = class;
+        header[5] = data;
+        header[18..20].copy_from_slice(&machine);
+        header
+    }
+
+    #[test]
+    fn parse_objects() {
+        assert_eq!(
+            ObjectInfo::parse(&elf(2, 1, [62, 0])),
+            Some(ObjectInfo {
+                format: ObjectFormat::Elf,
+                machine: 62,
+                elf64: Some(true),
+            })
+        );
+        // Big-endian PowerPC.
+        assert_eq!(
+            ObjectInfo::parse(&elf(1, 2, [0, 20])).map(|o| o.machine),
+            Some(20)
+        );
+        // x86_64 COFF.
+        assert_eq!(
+            ObjectInfo::parse(&[0x64, 0x86, 0x05, 0x00, 0x00, 0x00]),
+            Some(ObjectInfo {
+                format: ObjectFormat::Coff,
+                machine: IMAGE_FILE_MACHINE_AMD64,
+                elf64: None,
+            })
+        );
+        // `/bigobj` COFF.
+        assert_eq!(
+            ObjectInfo::parse(&[0x00, 0x00, 0xff, 0xff, 0x02, 0x00, 0x64, 0xaa])
+                .map(|o| (o.format, o.machine)),
+            Some((ObjectFormat::Coff, IMAGE_FILE_MACHINE_ARM64))
+        );
+        // arm64 Mach-O.
+        assert_eq!(
+            ObjectInfo::parse(&[0xcf, 0xfa, 0xed, 0xfe, 0x0c, 0x00, 0x00, 0x01])
+                .map(|o| (o.format, o.machine)),
+            Some((ObjectFormat::MachO, 0x0100_000c))
+        );
+        assert_eq!(
+            ObjectInfo::parse(b"\0asm\x01\0\0\0").map(|o| o.format),
+            Some(ObjectFormat::Wasm)
+        );
+        // LLVM bitcode, as produced with `-flto`.
+        assert_eq!(ObjectInfo::parse(b"BC\xc0\xde\x35\x14\0\0"), None);
+        assert_eq!(ObjectInfo::parse(b"\x7fEL"), None);
+    }
+
+    #[test]
+    fn expected_for_targets() {
+        let target = |arch, vendor, os| TargetInfo {
+            full_arch: arch,
+            arch,
+            vendor,
+            os,
+            env: "",
+            abi: "",
+        };
+        let x86_64_elf = ObjectInfo::parse(&elf(2, 1, [62, 0])).unwrap();
+
+        let expected = Expected::for_target(&target("x86_64", "unknown", "linux")).unwrap();
+        assert!(expected.matches(&x86_64_elf));
+
+        let expected = Expected::for_target(&target("aarch64", "unknown", "linux")).unwrap();
+        assert!(!expected.matches(&x86_64_elf));
+        assert_eq!(expected.to_string(), "ELF with machine 0xb7");
+        assert_eq!(x86_64_elf.to_string(), "ELF (64-bit) with machine 0x3e");
+
+        let expected = Expected::for_target(&target("riscv32", "unknown", "none")).unwrap();
+        assert!(!expected.matches(&ObjectInfo::parse(&elf(2, 1, [243, 0])).unwrap()));
+        assert!(expected.matches(&ObjectInfo::parse(&elf(1, 1, [243, 0])).unwrap()));
+
+        let expected = Expected::for_target(&target("x86_64", "pc", "windows")).unwrap();
+        assert_eq!(expected.format, ObjectFormat::Coff);
+        assert!(!expected.matches(&x86_64_elf));
+
+        let expected = Expected::for_target(&target("aarch64", "apple", "macos")).unwrap();
+        assert_eq!(expected.format, ObjectFormat::MachO);
+
+        assert_eq!(
+            Expected::for_target(&target("powerpc64", "ibm", "aix")),
+            None
+        );
+    }
+
+    #[test]
+    fn package_hints() {
+        assert_eq!(
+            package_hint("aarch64-linux-gnu-gcc").as_deref(),
+            Some(
+                "`aarch64-linux-gnu-gcc` is provided by the `gcc-aarch64-linux-gnu` package on \
+                 Debian and Ubuntu, `gcc-aarch64-linux-gnu` on Fedora and \
+                 `aarch64-linux-gnu-gcc` on Arch Linux"
+            )
+        );
+        let hint = package_hint("riscv64-linux-gnu-g++-13").unwrap();
+        assert!(hint.contains("`g++-riscv64-linux-gnu`"), "{hint}");
+        assert!(hint.contains("`gcc-c++-riscv64-linux-gnu`"), "{hint}");
+        let hint = package_hint("x86_64-w64-mingw32-gcc").unwrap();
+        assert!(hint.contains("`gcc-mingw-w64-x86-64`"), "{hint}");
+        assert!(hint.contains("`mingw64-gcc`"), "{hint}");
+        let hint = package_hint("arm-none-eabi-gcc").unwrap();
+        assert!(hint.contains("`gcc-arm-none-eabi`"), "{hint}");
+        assert!(package_hint("clang").unwrap().contains("`clang` package"));
+        assert_eq!(package_hint("cc"), None);
+        assert_eq!(package_hint("x86_64-unknown-freebsd-gcc"), None);
+    }
+}
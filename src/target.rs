@@ -5,8 +5,11 @@ mod apple;
 mod generated;
 mod llvm;
 mod parser;
+mod spec;
+mod zig;
 
 pub(crate) use parser::TargetInfoParser;
+pub(crate) use spec::TargetSpec;
 
 /// Information specific to a `rustc` target.
 ///
@@ -39,3 +42,129 @@ pub(crate) struct TargetInfo<'a> {
     /// This is the same as the value of `cfg!(target_abi)`.
     pub abi: &'a str,
 }
+
+/// Information about the target that a [`Build`] compiles for, see
+/// [`Build::try_get_target`].
+///
+/// The fields match the `cfg` values that `rustc` uses for the target, so
+/// build scripts can branch on them without parsing the target name
+/// themselves.
+///
+/// [`Build`]: crate::Build
+/// [`Build::try_get_target`]: crate::Build::try_get_target
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Target {
+    /// The `rustc` target name, e.g. `aarch64-unknown-linux-gnu`.
+    pub rustc_target: String,
+    /// The full architecture, including the subarchitecture, e.g. `armv7`.
+    ///
+    /// This differs from [`arch`](Self::arch), which only specifies the
+    /// overall architecture.
+    pub full_arch: String,
+    /// The overall target architecture, the same as `cfg!(target_arch)`.
+    pub arch: String,
+    /// The target vendor, the same as `cfg!(target_vendor)`.
+    pub vendor: String,
+    /// The operating system, or `none` on bare-metal targets, the same as
+    /// `cfg!(target_os)`.
+    pub os: String,
+    /// The environment on top of the operating system, the same as
+    /// `cfg!(target_env)`.
+    pub env: String,
+    /// The ABI on top of the operating system, the same as `cfg!(target_abi)`.
+    pub abi: String,
+    /// The LLVM/Clang target triple, as passed to Clang's `--target`, e.g.
+    /// `arm64-apple-macosx` for `aarch64-apple-darwin`.
+    ///
+    /// See <https://clang.llvm.org/docs/CrossCompilation.html#target-triple>.
+    pub llvm_target: String,
+    /// Whether the target differs from the host.
+    pub is_cross_compile: bool,
+}
+
+impl Target {
+    pub(crate) fn new(
+        rustc_target: &str,
+        info: &TargetInfo<'_>,
+        llvm_target: String,
+        is_cross_compile: bool,
+    ) -> Self {
+        Self {
+            rustc_target: rustc_target.to_string(),
+            full_arch: info.full_arch.to_string(),
+            arch: info.arch.to_string(),
+            vendor: info.vendor.to_string(),
+            os: info.os.to_string(),
+            env: info.env.to_string(),
+            abi: info.abi.to_string(),
+            llvm_target,
+            is_cross_compile,
+        }
+    }
+
+    fn info(&self) -> TargetInfo<'_> {
+        TargetInfo {
+            full_arch: &self.full_arch,
+            arch: &self.arch,
+            vendor: &self.vendor,
+            os: &self.os,
+            env: &self.env,
+            abi: &self.abi,
+        }
+    }
+
+    fn is_apple_os(&self) -> bool {
+        self.vendor == "apple"
+            && matches!(&*self.os, "macos" | "ios" | "tvos" | "watchos" | "visionos")
+    }
+
+    /// The name of the Apple SDK to compile against, as understood by
+    /// `xcrun --sdk`, e.g. `iphonesimulator`.
+    ///
+    /// Returns `None` if this is not an Apple target.
+    pub fn apple_sdk_name(&self) -> Option<&'static str> {
+        self.is_apple_os().then(|| self.info().apple_sdk_name())
+    }
+
+    /// The compiler flag that sets the minimum OS version to `min_version`,
+    /// e.g. `-mmacosx-version-min=10.12`.
+    ///
+    /// Returns `None` if this is not an Apple target.
+    pub fn apple_version_flag(&self, min_version: &str) -> Option<String> {
+        self.is_apple_os()
+            .then(|| self.info().apple_version_flag(min_version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Target;
+
+    fn target(rustc_target: &str) -> Target {
+        let info = super::TargetInfo::from_rustc_target(rustc_target).unwrap();
+        let llvm_target = info.llvm_target(rustc_target, None).into_owned();
+        Target::new(rustc_target, &info, llvm_target, true)
+    }
+
+    #[test]
+    fn public_target() {
+        let linux = target("armv7-unknown-linux-gnueabihf");
+        assert_eq!(linux.full_arch, "armv7");
+        assert_eq!(linux.arch, "arm");
+        assert_eq!(linux.os, "linux");
+        assert_eq!(linux.env, "gnu");
+        assert_eq!(linux.abi, "eabihf");
+        assert_eq!(linux.llvm_target, "armv7-unknown-linux-gnueabihf");
+        assert_eq!(linux.apple_sdk_name(), None);
+        assert_eq!(linux.apple_version_flag("10.12"), None);
+
+        let ios_sim = target("aarch64-apple-ios-sim");
+        assert_eq!(ios_sim.llvm_target, "arm64-apple-ios-simulator");
+        assert_eq!(ios_sim.apple_sdk_name(), Some("iphonesimulator"));
+        assert_eq!(
+            ios_sim.apple_version_flag("14.0").as_deref(),
+            Some("-mios-simulator-version-min=14.0")
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Reading custom target JSON specs.
+//!
+//! See <https://doc.rust-lang.org/rustc/targets/custom.html>.
+
+use std::{
+    env,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use super::generated;
+use crate::json::Value;
+
+/// The fields of a custom target spec that affect how C code is compiled.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct TargetSpec {
+    /// The LLVM target triple, e.g. `x86_64-unknown-none-elf`.
+    pub llvm_target: Option<String>,
+    /// The CPU to generate code for, e.g. `cortex-m4`.
+    pub cpu: Option<String>,
+    /// The LLVM target features, e.g. `-mmx,-sse,+soft-float`.
+    pub features: Option<String>,
+    /// The LLVM data layout. The compiler derives it from the triple and CPU,
+    /// this is only kept for diagnostics.
+    pub data_layout: Option<String>,
+    /// The relocation model, e.g. `pic` or `static`.
+    pub relocation_model: Option<String>,
+}
+
+impl TargetSpec {
+    /// Find the JSON spec for `target`, which is either a path to the spec
+    /// itself, or the name of a spec in one of the `RUST_TARGET_PATH`
+    /// directories.
+    ///
+    /// Returns `None` for targets built into `rustc`, which take precedence
+    /// over `RUST_TARGET_PATH`.
+    pub(crate) fn find(target: &str, rust_target_path: Option<&OsStr>) -> Option<PathBuf> {
+        if target.ends_with(".json") {
+            return Some(PathBuf::from(target));
+        }
+        if generated::LLVM_TARGETS
+            .binary_search_by_key(&target, |(rustc_target, _)| rustc_target)
+            .is_ok()
+        {
+            return None;
+        }
+        env::split_paths(rust_target_path?)
+            .map(|dir| dir.join(format!("{target}.json")))
+            .find(|spec| spec.is_file())
+    }
+
+    /// The name of the target for a spec given as a path, e.g.
+    /// `x86_64-kernel` for `/path/to/x86_64-kernel.json`.
+    pub(crate) fn target_name(target: &str) -> &str {
+        match target.strip_suffix(".json") {
+            Some(path) => Path::new(path)
+                .file_name()
+                .and_then(OsStr::to_str)
+                .unwrap_or(path),
+            None => target,
+        }
+    }
+
+    pub(crate) fn parse(json: &str) -> Result<Self, String> {
+        let fields = match Value::parse(json)? {
+            Value::Object(fields) => fields,
+            _ => return Err("expected an object".to_string()),
+        };
+        let mut spec = Self::default();
+        for (key, value) in fields {
+            let field = match &*key {
+                "llvm-target" => &mut spec.llvm_target,
+                "cpu" => &mut spec.cpu,
+                "features" => &mut spec.features,
+                "data-layout" => &mut spec.data_layout,
+                "relocation-model" => &mut spec.relocation_model,
+                _ => continue,
+            };
+            if let Value::String(value) = value {
+                *field = Some(value);
+            }
+        }
+        Ok(spec)
+    }
+
+    /// Whether the relocation model implies position-independent code, or
+    /// `None` if the spec doesn't set one.
+    pub(crate) fn pic(&self) -> Option<bool> {
+        self.relocation_model
+            .as_deref()
+            .map(|model| matches!(model, "pic" | "pie"))
+    }
+
+    /// The enabled (`+feature`) and disabled (`-feature`) LLVM target features.
+    pub(crate) fn features(&self) -> impl Iterator<Item = &str> {
+        self.features
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|feature| feature.starts_with(['+', '-']) && feature.len() > 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::TargetSpec;
+
+    #[test]
+    fn parse_spec() {
+        let spec = TargetSpec::parse(
+            r#"{
+                "arch": "x86_64",
+                "cpu": "x86-64",
+                "data-layout": "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128",
+                "disable-redzone": true,
+                "features": "-mmx,-sse,+soft-float",
+                "llvm-target": "x86_64-unknown-none-elf",
+                "max-atomic-width": 64,
+                "pre-link-args": { "ld.lld": ["--script=linker.ld"] },
+                "relocation-model": "static",
+                "target-pointer-width": "64"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(spec.llvm_target.as_deref(), Some("x86_64-unknown-none-elf"));
+        assert_eq!(spec.cpu.as_deref(), Some("x86-64"));
+        assert_eq!(
+            spec.features().collect::<Vec<_>>(),
+            ["-mmx", "-sse", "+soft-float"]
+        );
+        assert_eq!(spec.pic(), Some(false));
+
+        assert_eq!(TargetSpec::parse("{}").unwrap(), TargetSpec::default());
+        assert!(TargetSpec::parse(r#"{"cpu": "#).is_err());
+        assert!(TargetSpec::parse("[]").is_err());
+    }
+
+    #[test]
+    fn find_spec() {
+        assert_eq!(
+            TargetSpec::find("/path/to/x86_64-kernel.json", None).as_deref(),
+            Some(Path::new("/path/to/x86_64-kernel.json"))
+        );
+        assert_eq!(TargetSpec::find("x86_64-unknown-linux-gnu", None), None);
+        assert_eq!(TargetSpec::find("x86_64-kernel", None), None);
+        assert_eq!(
+            TargetSpec::target_name("/path/to/x86_64-kernel.json"),
+            "x86_64-kernel"
+        );
+        assert_eq!(TargetSpec::target_name("x86_64-kernel"), "x86_64-kernel");
+    }
+}
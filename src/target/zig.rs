@@ -0,0 +1,155 @@
+use super::TargetInfo;
+
+impl TargetInfo<'_> {
+    /// The target triple in the format expected by `zig cc -target`.
+    ///
+    /// See <https://ziglang.org/documentation/master/#Targets>.
+    ///
+    /// `glibc_version` is appended to GNU/Linux targets (e.g.
+    /// `x86_64-linux-gnu.2.17`). If it is `None`, the minimum glibc version
+    /// supported by `rustc` for the target is used, so that the compiled
+    /// code doesn't require a newer glibc than the Rust code it is linked with.
+    pub(crate) fn zig_target(&self, glibc_version: Option<&str>) -> String {
+        let arch = match self.arch {
+            "x86" => "x86",
+            "arm" if self.full_arch.starts_with("thumb") => "thumb",
+            "arm" if self.full_arch.starts_with("armeb") => "armeb",
+            "arm" => "arm",
+            "aarch64" if self.full_arch == "aarch64_be" => "aarch64_be",
+            "aarch64" => "aarch64",
+            "powerpc64" if self.full_arch == "powerpc64le" => "powerpc64le",
+            "mips" if self.full_arch.ends_with("el") => "mipsel",
+            "mips64" if self.full_arch.ends_with("el") => "mips64el",
+            "mips32r6" | "mips64r6" if self.full_arch.ends_with("el") => {
+                if self.arch == "mips32r6" {
+                    "mipsel"
+                } else {
+                    "mips64el"
+                }
+            }
+            "mips32r6" => "mips",
+            "mips64r6" => "mips64",
+            arch => arch,
+        };
+
+        let os = match self.os {
+            "android" => "linux",
+            "none" => "freestanding",
+            os => os,
+        };
+
+        let eabi = match self.abi {
+            "eabi" => "eabi",
+            "eabihf" => "eabihf",
+            _ => "",
+        };
+        let abi = match (self.os, self.env) {
+            ("android", _) if arch == "arm" || arch == "thumb" => "androideabi".to_string(),
+            ("android", _) => "android".to_string(),
+            ("linux", "gnu") => {
+                let abi = match self.abi {
+                    "x32" => "x32",
+                    "ilp32" => "ilp32",
+                    "abi64" => "abi64",
+                    _ => eabi,
+                };
+                let glibc_version = glibc_version.unwrap_or(match arch {
+                    "riscv64" => "2.27",
+                    "loongarch64" => "2.36",
+                    _ => "2.17",
+                });
+                format!("gnu{abi}.{glibc_version}")
+            }
+            ("linux", "musl") => format!("musl{eabi}"),
+            ("linux", "ohos") => "ohos".to_string(),
+            ("windows", "msvc") => "msvc".to_string(),
+            ("windows", _) => "gnu".to_string(),
+            ("none", _) if eabi.is_empty() => "none".to_string(),
+            ("none", _) => eabi.to_string(),
+            (_, "sim") => "simulator".to_string(),
+            (_, "macabi") => "macabi".to_string(),
+            // Let Zig pick the default ABI for the OS.
+            _ => String::new(),
+        };
+
+        if abi.is_empty() {
+            format!("{arch}-{os}")
+        } else {
+            format!("{arch}-{os}-{abi}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TargetInfo;
+
+    #[track_caller]
+    fn zig(rustc_target: &str, glibc_version: Option<&str>) -> String {
+        TargetInfo::from_rustc_target(rustc_target)
+            .unwrap()
+            .zig_target(glibc_version)
+    }
+
+    #[test]
+    fn linux() {
+        assert_eq!(
+            zig("x86_64-unknown-linux-gnu", None),
+            "x86_64-linux-gnu.2.17"
+        );
+        assert_eq!(
+            zig("aarch64-unknown-linux-gnu", Some("2.31")),
+            "aarch64-linux-gnu.2.31"
+        );
+        assert_eq!(
+            zig("armv7-unknown-linux-gnueabihf", None),
+            "arm-linux-gnueabihf.2.17"
+        );
+        assert_eq!(
+            zig("riscv64gc-unknown-linux-gnu", None),
+            "riscv64-linux-gnu.2.27"
+        );
+        assert_eq!(zig("i686-unknown-linux-gnu", None), "x86-linux-gnu.2.17");
+        assert_eq!(
+            zig("x86_64-unknown-linux-gnux32", None),
+            "x86_64-linux-gnux32.2.17"
+        );
+        assert_eq!(
+            zig("powerpc64le-unknown-linux-gnu", None),
+            "powerpc64le-linux-gnu.2.17"
+        );
+        assert_eq!(zig("x86_64-unknown-linux-musl", None), "x86_64-linux-musl");
+        assert_eq!(
+            zig("arm-unknown-linux-musleabihf", None),
+            "arm-linux-musleabihf"
+        );
+        assert_eq!(zig("mipsel-unknown-linux-musl", None), "mipsel-linux-musl");
+    }
+
+    #[test]
+    fn android() {
+        assert_eq!(zig("aarch64-linux-android", None), "aarch64-linux-android");
+        assert_eq!(
+            zig("armv7-linux-androideabi", None),
+            "arm-linux-androideabi"
+        );
+    }
+
+    #[test]
+    fn other_os() {
+        assert_eq!(zig("x86_64-pc-windows-gnu", None), "x86_64-windows-gnu");
+        assert_eq!(zig("aarch64-pc-windows-msvc", None), "aarch64-windows-msvc");
+        assert_eq!(zig("aarch64-apple-darwin", None), "aarch64-macos");
+        assert_eq!(zig("aarch64-apple-ios-sim", None), "aarch64-ios-simulator");
+        assert_eq!(zig("x86_64-unknown-freebsd", None), "x86_64-freebsd");
+        assert_eq!(zig("wasm32-wasip1", None), "wasm32-wasi");
+        assert_eq!(
+            zig("thumbv7em-none-eabihf", None),
+            "thumb-freestanding-eabihf"
+        );
+        assert_eq!(
+            zig("riscv32imac-unknown-none-elf", None),
+            "riscv32-freestanding-none"
+        );
+    }
+}
@@ -9,13 +9,14 @@ use std::{
     collections::HashMap,
     env,
     ffi::{OsStr, OsString},
+    fmt,
     io::Write,
     path::{Path, PathBuf},
     process::{Command, Output, Stdio},
     sync::RwLock,
 };
 
-pub(crate) type CompilerFamilyLookupCache = HashMap<Box<[Box<OsStr>]>, ToolFamily>;
+pub(crate) type CompilerFamilyLookupCache = HashMap<Box<[Box<OsStr>]>, (ToolFamily, CompilerInfo)>;
 
 /// Configuration used to represent an invocation of a C compiler.
 ///
@@ -33,6 +34,7 @@ pub struct Tool {
     pub(crate) args: Vec<OsString>,
     pub(crate) env: Vec<(OsString, OsString)>,
     pub(crate) family: ToolFamily,
+    pub(crate) info: CompilerInfo,
     pub(crate) cuda: bool,
     pub(crate) removed_args: Vec<OsString>,
     pub(crate) has_internal_target_arg: bool,
@@ -98,6 +100,7 @@ impl Tool {
             args: Vec::new(),
             env: Vec::new(),
             family,
+            info: CompilerInfo::default(),
             cuda: false,
             removed_args: Vec::new(),
             has_internal_target_arg: false,
@@ -150,6 +153,15 @@ impl Tool {
             )
             .is_ok();
 
+            // Both of these may also define `__GNUC__`, but don't accept all
+            // of the GNU flags.
+            if stdout.contains(r#""TI""#) {
+                return Ok(ToolFamily::Ti);
+            }
+            if stdout.contains(r#""nvhpc""#) {
+                return Ok(ToolFamily::NvHpc);
+            }
+
             let clang = stdout.contains(r#""clang""#);
             let gcc = stdout.contains(r#""gcc""#);
             let emscripten = stdout.contains(r#""emscripten""#);
@@ -176,7 +188,7 @@ impl Tool {
             args: &[String],
             cargo_output: &CargoOutput,
             out_dir: Option<&Path>,
-        ) -> Result<ToolFamily, Error> {
+        ) -> Result<(ToolFamily, CompilerInfo), Error> {
             let out_dir = out_dir
                 .map(Cow::Borrowed)
                 .unwrap_or_else(|| Cow::Owned(env::temp_dir()));
@@ -250,33 +262,54 @@ impl Tool {
             };
 
             let stdout = String::from_utf8_lossy(&stdout);
-            guess_family_from_stdout(&stdout, path, args, cargo_output)
+            let family = guess_family_from_stdout(&stdout, path, args, cargo_output)?;
+
+            Ok((family, CompilerInfo::from_preprocessed(&stdout)))
         }
-        let detect_family = |path: &Path, args: &[String]| -> Result<ToolFamily, Error> {
-            let cache_key = [path.as_os_str()]
-                .iter()
-                .cloned()
-                .chain(args.iter().map(OsStr::new))
-                .map(Into::into)
-                .collect();
-            if let Some(family) = cached_compiler_family.read().unwrap().get(&cache_key) {
-                return Ok(*family);
-            }
+        let detect_family =
+            |path: &Path, args: &[String]| -> Result<(ToolFamily, CompilerInfo), Error> {
+                let cache_key = [path.as_os_str()]
+                    .iter()
+                    .cloned()
+                    .chain(args.iter().map(OsStr::new))
+                    .map(Into::into)
+                    .collect();
+                if let Some(detected) = cached_compiler_family.read().unwrap().get(&cache_key) {
+                    return Ok(detected.clone());
+                }
 
-            let family = detect_family_inner(path, args, cargo_output, out_dir)?;
-            cached_compiler_family
-                .write()
-                .unwrap()
-                .insert(cache_key, family);
-            Ok(family)
-        };
+                let detected = detect_family_inner(path, args, cargo_output, out_dir)?;
+                cached_compiler_family
+                    .write()
+                    .unwrap()
+                    .insert(cache_key, detected.clone());
+                Ok(detected)
+            };
 
-        let family = detect_family(&path, &args).unwrap_or_else(|e| {
+        let (family, info) = detect_family(&path, &args).unwrap_or_else(|e| {
+            let fname = path.file_name().map(OsStr::to_string_lossy);
+            let stem = fname
+                .as_deref()
+                .map(|fname| fname.trim_end_matches(".exe"))
+                .unwrap_or_default();
+            // The TI compilers can't preprocess to stdout, so detection always
+            // fails for them, don't warn about that.
+            if is_ti_compiler(stem) {
+                let info = CompilerInfo {
+                    vendor: CompilerVendor::Ti,
+                    ..CompilerInfo::default()
+                };
+                return (ToolFamily::Ti, info);
+            }
             cargo_output.print_warning(&format_args!(
                 "Compiler family detection failed due to error: {e}"
             ));
-            match path.file_name().map(OsStr::to_string_lossy) {
-                Some(fname) if fname.contains("clang-cl") => ToolFamily::Msvc { clang_cl: true },
+            let family = match fname.as_deref() {
+                _ if matches!(stem, "nvc" | "nvc++" | "pgcc" | "pgc++") => ToolFamily::NvHpc,
+                _ if matches!(stem, "icx" | "icpx") => ToolFamily::Clang { zig_cc: false },
+                Some(fname) if fname.contains("clang-cl") || fname.starts_with("icx-cl") => {
+                    ToolFamily::Msvc { clang_cl: true }
+                }
                 Some(fname) if fname.ends_with("cl") || fname == "cl.exe" => {
                     ToolFamily::Msvc { clang_cl: false }
                 }
@@ -294,7 +327,8 @@ impl Tool {
                 }
                 Some(fname) if fname.contains("zig") => ToolFamily::Clang { zig_cc: true },
                 _ => ToolFamily::Gnu,
-            }
+            };
+            (family, CompilerInfo::default())
         });
 
         Tool {
@@ -304,6 +338,7 @@ impl Tool {
             args: Vec::new(),
             env: Vec::new(),
             family,
+            info,
             cuda,
             removed_args: Vec::new(),
             has_internal_target_arg: false,
@@ -342,7 +377,7 @@ impl Tool {
             if chars.next() != Some('/') {
                 return false;
             }
-        } else if (self.is_like_gnu() || self.is_like_clang()) && chars.next() != Some('-') {
+        } else if chars.next() != Some('-') {
             return false;
         }
 
@@ -479,6 +514,18 @@ impl Tool {
         matches!(self.family, ToolFamily::Msvc { clang_cl: true })
     }
 
+    /// The vendor, version and default target of the compiler.
+    ///
+    /// This is detected on a best-effort basis from the compiler's predefined
+    /// macros, so all fields may be unknown, e.g. when `CC_FORCE_DISABLE` is
+    /// set or when the compiler was configured via [`Build::compiler`] with
+    /// a non-existent path.
+    ///
+    /// [`Build::compiler`]: crate::Build::compiler
+    pub fn info(&self) -> &CompilerInfo {
+        &self.info
+    }
+
     /// Supports using `--` delimiter to separate arguments and path to source files.
     pub(crate) fn supports_path_delimiter(&self) -> bool {
         // homebrew clang and zig-cc does not support this while stock version does
@@ -486,6 +533,246 @@ impl Tool {
     }
 }
 
+/// The vendor of a compiler, see [`CompilerInfo`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CompilerVendor {
+    /// GNU Compiler Collection.
+    Gnu,
+    /// LLVM Clang, including `clang-cl`.
+    Clang,
+    /// Apple's fork of Clang shipped with Xcode, which uses its own version numbers.
+    AppleClang,
+    /// Microsoft Visual C++.
+    Msvc,
+    /// Intel's LLVM-based oneAPI DPC++/C++ compiler (`icx`/`icpx`).
+    IntelLlvm,
+    /// Arm Compiler for Embedded (`armclang`), which is based on Clang.
+    ArmClang,
+    /// The NVIDIA HPC SDK compilers (`nvc`/`nvc++`), formerly PGI.
+    NvidiaHpc,
+    /// The Texas Instruments compilers, e.g. `cl430` or `armcl`.
+    Ti,
+    /// The vendor could not be detected.
+    #[default]
+    Unknown,
+}
+
+impl fmt::Display for CompilerVendor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CompilerVendor::Gnu => "GCC",
+            CompilerVendor::Clang => "Clang",
+            CompilerVendor::AppleClang => "Apple Clang",
+            CompilerVendor::Msvc => "MSVC",
+            CompilerVendor::IntelLlvm => "Intel oneAPI",
+            CompilerVendor::ArmClang => "Arm Compiler",
+            CompilerVendor::NvidiaHpc => "NVIDIA HPC",
+            CompilerVendor::Ti => "TI",
+            CompilerVendor::Unknown => "unknown compiler",
+        })
+    }
+}
+
+/// The version of a compiler, see [`CompilerInfo`].
+///
+/// For MSVC, this is the version of `cl.exe` (e.g. 19.29.30133), not the
+/// version of Visual Studio.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CompilerVersion {
+    /// The major version.
+    pub major: u32,
+    /// The minor version.
+    pub minor: u32,
+    /// The patch version.
+    pub patch: u32,
+}
+
+impl CompilerVersion {
+    /// Create a new version.
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl fmt::Display for CompilerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Information about a compiler, as returned by [`Tool::info`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CompilerInfo {
+    /// The vendor of the compiler.
+    pub vendor: CompilerVendor,
+    /// The version of the compiler, if known.
+    pub version: Option<CompilerVersion>,
+    /// The target triple the compiler generates code for by default (i.e.
+    /// without any `--target` or similar flag), if known.
+    ///
+    /// This is the compiler's own notion of a triple in the style of
+    /// `-dumpmachine`, such as `x86_64-linux-gnu`, not a Rust target name. It
+    /// is derived from the compiler's predefined macros, so the vendor part
+    /// may differ from what the compiler itself reports.
+    pub target: Option<String>,
+}
+
+impl CompilerInfo {
+    /// Parse the output of preprocessing `detect_compiler_family.c`.
+    pub(crate) fn from_preprocessed(stdout: &str) -> Self {
+        let mut clang = None;
+        let mut apple = false;
+        let mut gnuc = None;
+        let mut msc = None;
+        let mut msvc_arch = None;
+        let mut intel_llvm = None;
+        let mut armcc = None;
+        let mut nvcompiler = None;
+        let mut ti = None;
+        let mut arch = None;
+        let mut os = None;
+        let mut arm_hard_float = false;
+
+        let parse_number = |rest: &str| rest.trim().parse::<u32>().ok();
+        let parse_version = |rest: &str| {
+            let mut parts = rest.split_whitespace().map(|n| n.parse::<u32>().ok());
+            Some(CompilerVersion::new(
+                parts.next()??,
+                parts.next()??,
+                parts.next()??,
+            ))
+        };
+
+        for line in stdout.lines() {
+            let line = line.trim();
+            if let Some(rest) = line.strip_prefix("cc_rs_clang_version ") {
+                clang = parse_version(rest);
+            } else if line == "cc_rs_apple_clang" {
+                apple = true;
+            } else if let Some(rest) = line.strip_prefix("cc_rs_gnuc_version ") {
+                gnuc = parse_version(rest);
+            } else if let Some(rest) = line.strip_prefix("cc_rs_msc_full_ver ") {
+                msc = parse_number(rest).map(msc_full_ver_to_version);
+            } else if let Some(arch) = line.strip_prefix("cc_rs_msvc_arch_") {
+                msvc_arch = Some(arch.to_string());
+            } else if let Some(rest) = line.strip_prefix("cc_rs_intel_llvm_compiler ") {
+                intel_llvm = parse_number(rest).map(intel_llvm_compiler_to_version);
+            } else if let Some(rest) = line.strip_prefix("cc_rs_armcc_version ") {
+                armcc = parse_number(rest).map(armcc_version_to_version);
+            } else if let Some(rest) = line.strip_prefix("cc_rs_nvcompiler_version ") {
+                nvcompiler = parse_version(rest);
+            } else if let Some(rest) = line.strip_prefix("cc_rs_ti_compiler_version ") {
+                ti = parse_number(rest).map(ti_compiler_version_to_version);
+            } else if let Some(rest) = line.strip_prefix("cc_rs_arch_") {
+                arch = Some(rest);
+            } else if let Some(rest) = line.strip_prefix("cc_rs_os_") {
+                os = Some(rest);
+            } else if line == "cc_rs_arm_hard_float" {
+                arm_hard_float = true;
+            }
+        }
+
+        // The compilers derived from Clang or mimicking GCC define their
+        // macros too, so check for them first. Clang also defines `__GNUC__`
+        // and (in MSVC mode) `_MSC_FULL_VER` for compatibility.
+        let (vendor, version) = if intel_llvm.is_some() {
+            (CompilerVendor::IntelLlvm, intel_llvm)
+        } else if armcc.is_some() {
+            (CompilerVendor::ArmClang, armcc)
+        } else if nvcompiler.is_some() {
+            (CompilerVendor::NvidiaHpc, nvcompiler)
+        } else if ti.is_some() {
+            (CompilerVendor::Ti, ti)
+        } else if clang.is_some() {
+            let vendor = if apple {
+                CompilerVendor::AppleClang
+            } else {
+                CompilerVendor::Clang
+            };
+            (vendor, clang)
+        } else if msc.is_some() {
+            (CompilerVendor::Msvc, msc)
+        } else if gnuc.is_some() {
+            (CompilerVendor::Gnu, gnuc)
+        } else {
+            (CompilerVendor::Unknown, None)
+        };
+
+        let target = if msc.is_some() {
+            msvc_arch.map(|arch| format!("{arch}-pc-windows-msvc"))
+        } else {
+            match (arch, os) {
+                (Some(arch), Some(os)) => Some(default_target(arch, os, arm_hard_float)),
+                _ => None,
+            }
+        };
+
+        Self {
+            vendor,
+            version,
+            target,
+        }
+    }
+}
+
+/// Assemble the `-dumpmachine` style triple of the `cc_rs_arch_*` and
+/// `cc_rs_os_*` lines in `detect_compiler_family.c`.
+fn default_target(arch: &str, os: &str, arm_hard_float: bool) -> String {
+    let eabi = if arm_hard_float { "eabihf" } else { "eabi" };
+    match (arch, os) {
+        ("arm", "linux_gnu") => format!("arm-linux-gnu{eabi}"),
+        ("arm", "linux_musl") => format!("arm-linux-musl{eabi}"),
+        ("arm", "linux_android") => "arm-linux-androideabi".to_string(),
+        ("arm", "none_elf") => format!("arm-none-{eabi}"),
+        (arch, os) => format!("{arch}-{}", os.replace('_', "-")),
+    }
+}
+
+/// Convert `_MSC_FULL_VER` (e.g. `192930133`) to a version (e.g. 19.29.30133).
+fn msc_full_ver_to_version(full_ver: u32) -> CompilerVersion {
+    CompilerVersion::new(
+        full_ver / 10_000_000,
+        (full_ver / 100_000) % 100,
+        full_ver % 100_000,
+    )
+}
+
+/// Convert `__INTEL_LLVM_COMPILER` to a version. It is `YYYYMP` (e.g. `202110`
+/// for 2021.1.0) before 2024, and `YYYYMMPP` (e.g. `20240102` for 2024.1.2)
+/// since.
+fn intel_llvm_compiler_to_version(ver: u32) -> CompilerVersion {
+    if ver >= 10_000_000 {
+        CompilerVersion::new(ver / 10_000, (ver / 100) % 100, ver % 100)
+    } else {
+        CompilerVersion::new(ver / 100, (ver / 10) % 10, ver % 10)
+    }
+}
+
+/// Convert `__ARMCC_VERSION` (`Mmmuuxx`, e.g. `6210000`) to a version (e.g. 6.21.0).
+fn armcc_version_to_version(ver: u32) -> CompilerVersion {
+    CompilerVersion::new(ver / 1_000_000, (ver / 10_000) % 100, (ver / 100) % 100)
+}
+
+/// Convert `__TI_COMPILER_VERSION__` (`VVVRRRPPP`, e.g. `21006001`) to a
+/// version (e.g. 21.6.1).
+fn ti_compiler_version_to_version(ver: u32) -> CompilerVersion {
+    CompilerVersion::new(ver / 1_000_000, (ver / 1_000) % 1_000, ver % 1_000)
+}
+
+/// Whether `stem` is the name of one of the TI compiler drivers.
+fn is_ti_compiler(stem: &str) -> bool {
+    matches!(
+        stem,
+        "cl430" | "cl2000" | "cl6x" | "cl7x" | "clpru" | "armcl"
+    )
+}
+
 /// Represents the family of tools this tool belongs to.
 ///
 /// Each family of tools differs in how and what arguments they accept.
@@ -500,6 +787,11 @@ pub enum ToolFamily {
     Clang { zig_cc: bool },
     /// Tool is the MSVC cl.exe.
     Msvc { clang_cl: bool },
+    /// Tool is the NVIDIA HPC SDK's `nvc`/`nvc++`. It mostly accepts GNU-style flags, but errors
+    /// out on those it doesn't know.
+    NvHpc,
+    /// Tool is one of the Texas Instruments compilers, which use their own flag syntax.
+    Ti,
 }
 
 impl ToolFamily {
@@ -514,6 +806,16 @@ impl ToolFamily {
             ToolFamily::Msvc { .. } => {
                 cmd.push_cc_arg("-Z7".into());
             }
+            // Neither supports limiting the amount of debug info.
+            ToolFamily::NvHpc => {
+                cmd.push_cc_arg("-g".into());
+            }
+            ToolFamily::Ti => {
+                cmd.push_cc_arg("--symdebug:dwarf".into());
+                if let Some(v) = dwarf_version {
+                    cmd.push_cc_arg(format!("--symdebug:dwarf_version={v}").into());
+                }
+            }
             ToolFamily::Gnu | ToolFamily::Clang { .. } => {
                 match debug_opt {
                     // From https://doc.rust-lang.org/cargo/reference/profiles.html#debug
@@ -548,25 +850,42 @@ impl ToolFamily {
         }
     }
 
+    /// The flag to add the directory following it to the include path.
+    ///
+    /// All families accept the GNU spelling, including `cl.exe`, `nvcc` and
+    /// the TI compilers.
+    pub(crate) fn include_flag(&self) -> &'static str {
+        match *self {
+            ToolFamily::Msvc { .. }
+            | ToolFamily::Gnu
+            | ToolFamily::Clang { .. }
+            | ToolFamily::NvHpc
+            | ToolFamily::Ti => "-I",
+        }
+    }
+
     /// What the flags to enable all warnings
     pub(crate) fn warnings_flags(&self) -> &'static str {
         match *self {
             ToolFamily::Msvc { .. } => "-W4",
             ToolFamily::Gnu | ToolFamily::Clang { .. } => "-Wall",
+            ToolFamily::NvHpc => "-Minform=inform",
+            ToolFamily::Ti => "--issue_remarks",
         }
     }
 
     pub(crate) fn warnings_suppression_flags(&self) -> &'static str {
         match *self {
             ToolFamily::Msvc { .. } => "-W0",
-            ToolFamily::Gnu | ToolFamily::Clang { .. } => "-w",
+            ToolFamily::Gnu | ToolFamily::Clang { .. } | ToolFamily::NvHpc => "-w",
+            ToolFamily::Ti => "--no_warnings",
         }
     }
 
     /// What the flags to enable extra warnings
     pub(crate) fn extra_warnings_flags(&self) -> Option<&'static str> {
         match *self {
-            ToolFamily::Msvc { .. } => None,
+            ToolFamily::Msvc { .. } | ToolFamily::NvHpc | ToolFamily::Ti => None,
             ToolFamily::Gnu | ToolFamily::Clang { .. } => Some("-Wextra"),
         }
     }
@@ -575,7 +894,19 @@ impl ToolFamily {
     pub(crate) fn warnings_to_errors_flag(&self) -> &'static str {
         match *self {
             ToolFamily::Msvc { .. } => "-WX",
-            ToolFamily::Gnu | ToolFamily::Clang { .. } => "-Werror",
+            ToolFamily::Gnu | ToolFamily::Clang { .. } | ToolFamily::NvHpc => "-Werror",
+            ToolFamily::Ti => "--emit_warnings_as_errors",
+        }
+    }
+
+    /// What the flag to select the language standard `std` (e.g. `c11`) looks like
+    pub(crate) fn std_flag(&self, std: &str) -> String {
+        match *self {
+            ToolFamily::Msvc { .. } => format!("-std:{std}"),
+            ToolFamily::Gnu | ToolFamily::Clang { .. } | ToolFamily::NvHpc => format!("-std={std}"),
+            // The TI compilers always enable the GNU extensions, so e.g. `gnu11` is the same as
+            // `c11`.
+            ToolFamily::Ti => format!("--{}", std.replacen("gnu", "c", 1)),
         }
     }
 
@@ -583,3 +914,114 @@ impl ToolFamily {
         matches!(*self, ToolFamily::Clang { .. })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compiler_info_gcc() {
+        let info = CompilerInfo::from_preprocessed(
+            "# 1 \"detect.c\"\n#pragma message \"gcc\"\n# 14 \"detect.c\"\ncc_rs_gnuc_version 12 2 0\n",
+        );
+        assert_eq!(info.vendor, CompilerVendor::Gnu);
+        assert_eq!(info.version, Some(CompilerVersion::new(12, 2, 0)));
+        assert_eq!(info.target, None);
+
+        let info = CompilerInfo::from_preprocessed(
+            "cc_rs_gnuc_version 13 1 0\ncc_rs_arch_x86_64\ncc_rs_os_linux_gnu\n",
+        );
+        assert_eq!(info.target.as_deref(), Some("x86_64-linux-gnu"));
+        let info = CompilerInfo::from_preprocessed(
+            "cc_rs_gnuc_version 13 1 0\ncc_rs_arch_arm\ncc_rs_arm_hard_float\ncc_rs_os_none_elf\n",
+        );
+        assert_eq!(info.target.as_deref(), Some("arm-none-eabihf"));
+    }
+
+    #[test]
+    fn compiler_info_clang() {
+        let stdout = "cc_rs_clang_version 17 0 6\ncc_rs_gnuc_version 4 2 1\n";
+        let info = CompilerInfo::from_preprocessed(stdout);
+        assert_eq!(info.vendor, CompilerVendor::Clang);
+        assert_eq!(info.version, Some(CompilerVersion::new(17, 0, 6)));
+
+        let stdout = "cc_rs_clang_version 15 0 0\ncc_rs_apple_clang\ncc_rs_gnuc_version 4 2 1\n";
+        let info = CompilerInfo::from_preprocessed(stdout);
+        assert_eq!(info.vendor, CompilerVendor::AppleClang);
+        assert_eq!(info.version, Some(CompilerVersion::new(15, 0, 0)));
+    }
+
+    #[test]
+    fn compiler_info_msvc() {
+        let info = CompilerInfo::from_preprocessed(
+            "#line 1 \"detect.c\"\ncc_rs_msc_full_ver 192930133\ncc_rs_msvc_arch_x86_64\n",
+        );
+        assert_eq!(info.vendor, CompilerVendor::Msvc);
+        assert_eq!(info.version, Some(CompilerVersion::new(19, 29, 30133)));
+        assert_eq!(info.target.as_deref(), Some("x86_64-pc-windows-msvc"));
+
+        // clang-cl
+        let info = CompilerInfo::from_preprocessed(
+            "cc_rs_clang_version 18 1 8\ncc_rs_msc_full_ver 193933523\ncc_rs_msvc_arch_aarch64\n",
+        );
+        assert_eq!(info.vendor, CompilerVendor::Clang);
+        assert_eq!(info.version, Some(CompilerVersion::new(18, 1, 8)));
+        assert_eq!(info.target.as_deref(), Some("aarch64-pc-windows-msvc"));
+    }
+
+    #[test]
+    fn compiler_info_vendor_variants() {
+        // icx
+        let stdout = "cc_rs_clang_version 18 0 0\ncc_rs_intel_llvm_compiler 20240102\ncc_rs_gnuc_version 4 2 1\n";
+        let info = CompilerInfo::from_preprocessed(stdout);
+        assert_eq!(info.vendor, CompilerVendor::IntelLlvm);
+        assert_eq!(info.version, Some(CompilerVersion::new(2024, 1, 2)));
+        let info = CompilerInfo::from_preprocessed("cc_rs_intel_llvm_compiler 202110\n");
+        assert_eq!(info.version, Some(CompilerVersion::new(2021, 1, 0)));
+
+        // armclang
+        let stdout = "cc_rs_clang_version 17 0 0\ncc_rs_armcc_version 6210000\n";
+        let info = CompilerInfo::from_preprocessed(stdout);
+        assert_eq!(info.vendor, CompilerVendor::ArmClang);
+        assert_eq!(info.version, Some(CompilerVersion::new(6, 21, 0)));
+
+        // nvc
+        let stdout = "cc_rs_nvcompiler_version 24 3 0\ncc_rs_gnuc_version 4 8 0\n";
+        let info = CompilerInfo::from_preprocessed(stdout);
+        assert_eq!(info.vendor, CompilerVendor::NvidiaHpc);
+        assert_eq!(info.version, Some(CompilerVersion::new(24, 3, 0)));
+
+        // cl430
+        let info = CompilerInfo::from_preprocessed("cc_rs_ti_compiler_version 21006001\n");
+        assert_eq!(info.vendor, CompilerVendor::Ti);
+        assert_eq!(info.version, Some(CompilerVersion::new(21, 6, 1)));
+    }
+
+    #[test]
+    fn std_flags() {
+        assert_eq!(ToolFamily::Gnu.std_flag("c11"), "-std=c11");
+        assert_eq!(ToolFamily::NvHpc.std_flag("c++17"), "-std=c++17");
+        assert_eq!(
+            ToolFamily::Msvc { clang_cl: false }.std_flag("c++latest"),
+            "-std:c++latest"
+        );
+        assert_eq!(ToolFamily::Ti.std_flag("gnu99"), "--c99");
+        assert_eq!(ToolFamily::Ti.std_flag("c++14"), "--c++14");
+    }
+
+    #[test]
+    fn compiler_info_unknown() {
+        let info = CompilerInfo::from_preprocessed("cc_rs_gnuc_version __GNUC__ __GNUC_MINOR__\n");
+        assert_eq!(info, CompilerInfo::default());
+    }
+
+    #[test]
+    fn compiler_version_ordering() {
+        assert!(CompilerVersion::new(10, 0, 0) > CompilerVersion::new(9, 4, 0));
+        assert!(CompilerVersion::new(19, 29, 0) < CompilerVersion::new(19, 29, 30133));
+        assert_eq!(
+            CompilerVersion::new(19, 29, 30133).to_string(),
+            "19.29.30133"
+        );
+    }
+}
@@ -0,0 +1,495 @@
+//! Parsing of declarative toolchain files, see [`Build::toolchain_file`].
+//!
+//! The format is the subset of [Meson's cross/native files][meson] that maps
+//! onto the environment variables `cc` already understands, so existing Meson
+//! cross files can be used as-is.
+//!
+//! [`Build::toolchain_file`]: crate::Build::toolchain_file
+//! [meson]: https://mesonbuild.com/Machine-files.html
+
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{Error, ErrorKind};
+
+/// A value in a toolchain file.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    /// A single string, either quoted or bare.
+    String(String),
+    /// An array of strings, such as `['ccache', 'gcc']`.
+    Array(Vec<String>),
+}
+
+impl Value {
+    /// Convert the value into the format of the equivalent environment
+    /// variable, i.e. with array elements separated by spaces.
+    pub(crate) fn into_os_string(self) -> OsString {
+        match self {
+            Value::String(s) => s.into(),
+            Value::Array(a) => a.join(" ").into(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    section: Box<str>,
+    key: Box<str>,
+    value: String,
+    line: usize,
+}
+
+/// A parsed toolchain file.
+#[derive(Debug)]
+pub(crate) struct ToolchainFile {
+    path: Box<Path>,
+    entries: Vec<Entry>,
+}
+
+impl ToolchainFile {
+    pub(crate) fn load(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            Error::new(
+                ErrorKind::IOError,
+                format!("failed to read toolchain file {}: {e}", path.display()),
+            )
+        })?;
+        Self::parse(path, &contents)
+    }
+
+    pub(crate) fn parse(path: &Path, contents: &str) -> Result<Self, Error> {
+        let mut section: Option<Box<str>> = None;
+        let mut entries: Vec<Entry> = Vec::new();
+
+        for (i, line) in contents.lines().enumerate() {
+            let line_no = i + 1;
+            let stripped = strip_comment(line);
+            if stripped.trim().is_empty() {
+                continue;
+            }
+
+            // Indented lines continue the previous value, like in Python's
+            // `configparser` which Meson uses.
+            if stripped.starts_with(char::is_whitespace) {
+                if let Some(entry) = entries.last_mut() {
+                    if Some(&entry.section) == section.as_ref() {
+                        entry.value.push(' ');
+                        entry.value.push_str(stripped.trim());
+                        continue;
+                    }
+                }
+            }
+
+            let stripped = stripped.trim();
+            if let Some(name) = stripped.strip_prefix('[') {
+                let name = name
+                    .strip_suffix(']')
+                    .ok_or_else(|| syntax_error(path, line_no, "unterminated section header"))?;
+                section = Some(name.trim().into());
+                continue;
+            }
+
+            let (key, value) = stripped
+                .split_once('=')
+                .ok_or_else(|| syntax_error(path, line_no, "expected `key = value`"))?;
+            let section = section.clone().ok_or_else(|| {
+                syntax_error(path, line_no, "key/value pair outside of a section")
+            })?;
+            entries.push(Entry {
+                section,
+                key: key.trim().into(),
+                value: value.trim().to_string(),
+                line: line_no,
+            });
+        }
+
+        Ok(Self {
+            path: path.into(),
+            entries,
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Look up the value that corresponds to the environment variable `env`.
+    ///
+    /// Returns `Ok(None)` if the variable has no equivalent in toolchain
+    /// files, or if the file doesn't set it.
+    pub(crate) fn lookup(&self, env: &str) -> Result<Option<Value>, Error> {
+        let keys: &[(&str, &str)] = match env {
+            "CC" => &[("binaries", "c")],
+            "CXX" => &[("binaries", "cpp")],
+            "AR" => &[("binaries", "ar")],
+            "RANLIB" => &[("binaries", "ranlib")],
+            "NVCC" => &[("binaries", "cuda")],
+            // Meson moved these from `[properties]` to `[built-in options]`,
+            // accept both.
+            "CFLAGS" => &[("built-in options", "c_args"), ("properties", "c_args")],
+            "CXXFLAGS" => &[("built-in options", "cpp_args"), ("properties", "cpp_args")],
+            _ => return Ok(None),
+        };
+        for (section, key) in keys {
+            if let Some(entry) = self.find(section, key) {
+                return self.eval_entry(entry).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Look up a path-valued property, such as `sys_root`.
+    pub(crate) fn property_path(&self, key: &str) -> Result<Option<PathBuf>, Error> {
+        match self.find("properties", key) {
+            Some(entry) => match self.eval_entry(entry)? {
+                Value::String(s) => Ok(Some(s.into())),
+                Value::Array(_) => Err(self.error(entry.line, format!("`{key}` must be a string"))),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn find(&self, section: &str, key: &str) -> Option<&Entry> {
+        // Later entries override earlier ones.
+        self.entries
+            .iter()
+            .rev()
+            .find(|e| &*e.section == section && &*e.key == key)
+    }
+
+    fn eval_entry(&self, entry: &Entry) -> Result<Value, Error> {
+        // Values without any quotes or brackets are taken verbatim, which
+        // allows writing e.g. `c = aarch64-linux-gnu-gcc`.
+        if !entry.value.contains(['\'', '"', '[']) {
+            return Ok(Value::String(entry.value.clone()));
+        }
+        let mut parser = ExprParser {
+            file: self,
+            entry,
+            tokens: tokenize(&entry.value).map_err(|msg| self.error(entry.line, msg))?,
+            pos: 0,
+        };
+        let value = parser.expr()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(self.error(entry.line, "unexpected trailing tokens"));
+        }
+        Ok(value)
+    }
+
+    fn constant(&self, name: &str, before_line: usize) -> Option<&Entry> {
+        // Constants may only refer to constants defined before them.
+        self.entries
+            .iter()
+            .rev()
+            .find(|e| &*e.section == "constants" && &*e.key == name && e.line < before_line)
+    }
+
+    fn error(&self, line: usize, msg: impl std::fmt::Display) -> Error {
+        syntax_error(&self.path, line, msg)
+    }
+}
+
+fn syntax_error(path: &Path, line: usize, msg: impl std::fmt::Display) -> Error {
+    Error::new(
+        ErrorKind::InvalidArgument,
+        format!("invalid toolchain file {}:{line}: {msg}", path.display()),
+    )
+}
+
+/// Remove a trailing `#` comment, unless it's inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some(_), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '#') => return &line[..i],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Str(String),
+    Ident(String),
+    LBracket,
+    RBracket,
+    Comma,
+    Plus,
+    Slash,
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '[' => tokens.push(Token::LBracket),
+            ']' => tokens.push(Token::RBracket),
+            ',' => tokens.push(Token::Comma),
+            '+' => tokens.push(Token::Plus),
+            '/' => tokens.push(Token::Slash),
+            '\'' | '"' => {
+                let quote = c;
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => match chars.next() {
+                            Some('n') => value.push('\n'),
+                            Some('t') => value.push('\t'),
+                            Some(c) => value.push(c),
+                            None => return Err("unterminated string".to_string()),
+                        },
+                        Some(c) if c == quote => break,
+                        Some(c) => value.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut ident = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' {
+                        ident.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            c => return Err(format!("unexpected character `{c}`")),
+        }
+    }
+    Ok(tokens)
+}
+
+/// A parser for the small expression language of Meson machine files:
+/// strings, arrays, references to `[constants]`, `+` and `/`.
+struct ExprParser<'a> {
+    file: &'a ToolchainFile,
+    entry: &'a Entry,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl ExprParser<'_> {
+    fn error(&self, msg: impl std::fmt::Display) -> Error {
+        self.file.error(self.entry.line, msg)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn expr(&mut self) -> Result<Value, Error> {
+        let mut lhs = self.term()?;
+        loop {
+            match self.peek() {
+                Some(Token::Plus) => {
+                    self.pos += 1;
+                    let rhs = self.term()?;
+                    lhs = match (lhs, rhs) {
+                        (Value::String(a), Value::String(b)) => Value::String(a + &b),
+                        (Value::Array(mut a), Value::Array(b)) => {
+                            a.extend(b);
+                            Value::Array(a)
+                        }
+                        (Value::Array(mut a), Value::String(b)) => {
+                            a.push(b);
+                            Value::Array(a)
+                        }
+                        (Value::String(_), Value::Array(_)) => {
+                            return Err(self.error("cannot add an array to a string"))
+                        }
+                    };
+                }
+                Some(Token::Slash) => {
+                    self.pos += 1;
+                    let rhs = self.term()?;
+                    lhs = match (lhs, rhs) {
+                        (Value::String(a), Value::String(b)) => {
+                            if b.starts_with('/') {
+                                Value::String(b)
+                            } else {
+                                Value::String(format!("{}/{b}", a.trim_end_matches('/')))
+                            }
+                        }
+                        _ => return Err(self.error("`/` is only supported between strings")),
+                    };
+                }
+                _ => return Ok(lhs),
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Value, Error> {
+        match self.next() {
+            Some(Token::Str(s)) => Ok(Value::String(s.clone())),
+            Some(Token::Ident(name)) => {
+                let name = name.clone();
+                let constant = self
+                    .file
+                    .constant(&name, self.entry.line)
+                    .ok_or_else(|| self.error(format_args!("unknown constant `{name}`")))?;
+                self.file.eval_entry(constant)
+            }
+            Some(Token::LBracket) => {
+                let mut items = Vec::new();
+                loop {
+                    if self.peek() == Some(&Token::RBracket) {
+                        self.pos += 1;
+                        break;
+                    }
+                    match self.expr()? {
+                        Value::String(s) => items.push(s),
+                        Value::Array(a) => items.extend(a),
+                    }
+                    match self.next() {
+                        Some(Token::Comma) => {}
+                        Some(Token::RBracket) => break,
+                        _ => return Err(self.error("expected `,` or `]` in array")),
+                    }
+                }
+                Ok(Value::Array(items))
+            }
+            _ => Err(self.error("expected a string, array or constant")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> ToolchainFile {
+        ToolchainFile::parse(Path::new("test.ini"), contents).unwrap()
+    }
+
+    #[test]
+    fn bare_values() {
+        let file = parse(
+            "[binaries]\n\
+             c = aarch64-linux-gnu-gcc\n\
+             ar = /opt/my toolchain/bin/ar # comment\n\
+             [built-in options]\n\
+             c_args = -O2 -march=armv8-a\n",
+        );
+        assert_eq!(
+            file.lookup("CC").unwrap(),
+            Some(Value::String("aarch64-linux-gnu-gcc".into()))
+        );
+        assert_eq!(
+            file.lookup("AR").unwrap(),
+            Some(Value::String("/opt/my toolchain/bin/ar".into()))
+        );
+        assert_eq!(
+            file.lookup("CFLAGS").unwrap(),
+            Some(Value::String("-O2 -march=armv8-a".into()))
+        );
+        assert_eq!(file.lookup("CXX").unwrap(), None);
+        assert_eq!(file.lookup("UNRELATED").unwrap(), None);
+    }
+
+    #[test]
+    fn meson_cross_file() {
+        let file = parse(
+            "[constants]\n\
+             toolchain = '/opt/gcc-arm'\n\
+             prefix = toolchain / 'bin/arm-none-eabi-'\n\
+             common_args = ['-mthumb', '-mcpu=cortex-m4']\n\
+             \n\
+             [binaries]\n\
+             c = ['ccache', prefix + 'gcc']\n\
+             cpp = prefix + 'g++'\n\
+             ar = prefix + 'ar'\n\
+             strip = prefix + 'strip'\n\
+             \n\
+             [built-in options]\n\
+             c_args = common_args + ['-DFOO=\"bar baz\"',\n\
+             \x20   '-O2']\n\
+             cpp_args = common_args\n\
+             \n\
+             [properties]\n\
+             sys_root = toolchain / 'sysroot'\n\
+             needs_exe_wrapper = true\n\
+             \n\
+             [host_machine]\n\
+             system = 'none'\n",
+        );
+        assert_eq!(
+            file.lookup("CC").unwrap(),
+            Some(Value::Array(vec![
+                "ccache".into(),
+                "/opt/gcc-arm/bin/arm-none-eabi-gcc".into()
+            ]))
+        );
+        assert_eq!(
+            file.lookup("CXX").unwrap(),
+            Some(Value::String("/opt/gcc-arm/bin/arm-none-eabi-g++".into()))
+        );
+        assert_eq!(
+            file.lookup("CFLAGS").unwrap(),
+            Some(Value::Array(vec![
+                "-mthumb".into(),
+                "-mcpu=cortex-m4".into(),
+                "-DFOO=\"bar baz\"".into(),
+                "-O2".into(),
+            ]))
+        );
+        assert_eq!(
+            file.property_path("sys_root").unwrap(),
+            Some(PathBuf::from("/opt/gcc-arm/sysroot"))
+        );
+        assert_eq!(
+            file.lookup("CC").unwrap().unwrap().into_os_string(),
+            "ccache /opt/gcc-arm/bin/arm-none-eabi-gcc"
+        );
+    }
+
+    #[test]
+    fn legacy_properties_args() {
+        let file = parse("[properties]\nc_args = ['-g']\n");
+        assert_eq!(
+            file.lookup("CFLAGS").unwrap(),
+            Some(Value::Array(vec!["-g".into()]))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = ToolchainFile::parse(Path::new("t.ini"), "c = gcc\n").unwrap_err();
+        assert!(err.message.contains("t.ini:1"), "{}", err.message);
+
+        let err = ToolchainFile::parse(Path::new("t.ini"), "[binaries\n").unwrap_err();
+        assert!(err.message.contains("unterminated section"));
+
+        let file = parse("[binaries]\n\nc = missing + 'gcc'\n");
+        let err = file.lookup("CC").unwrap_err();
+        assert!(err.message.contains("test.ini:3"), "{}", err.message);
+        assert!(err.message.contains("unknown constant `missing`"));
+
+        let file = parse("[binaries]\nc = 'gcc\n");
+        assert!(file.lookup("CC").is_err());
+    }
+}
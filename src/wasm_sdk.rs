@@ -0,0 +1,65 @@
+//! Discovery of WebAssembly toolchains: the [WASI SDK] and [Emscripten].
+//!
+//! [WASI SDK]: https://github.com/WebAssembly/wasi-sdk
+//! [Emscripten]: https://emscripten.org
+
+use std::path::{Path, PathBuf};
+
+use crate::check_exe;
+
+/// A WASI SDK installation, as pointed to by `WASI_SDK_PATH`.
+#[derive(Debug)]
+pub(crate) struct WasiSdk {
+    root: PathBuf,
+}
+
+impl WasiSdk {
+    pub(crate) fn from_root(root: &Path) -> Option<Self> {
+        root.join("bin").is_dir().then(|| Self {
+            root: root.to_path_buf(),
+        })
+    }
+
+    /// The compiler to use for the given Rust target, preferring the
+    /// target-prefixed wrappers such as `wasm32-wasip1-clang`, which already
+    /// know about the target and sysroot.
+    pub(crate) fn compiler(&self, raw_target: &str, clang: &str) -> Option<PathBuf> {
+        self.tool(&format!("{raw_target}-{clang}"))
+            .or_else(|| self.tool(clang))
+    }
+
+    /// The `share/wasi-sysroot` directory shipped with the SDK.
+    pub(crate) fn sysroot(&self) -> Option<PathBuf> {
+        let sysroot = self.root.join("share").join("wasi-sysroot");
+        sysroot.is_dir().then_some(sysroot)
+    }
+
+    pub(crate) fn tool(&self, name: &str) -> Option<PathBuf> {
+        check_exe(self.root.join("bin").join(name))
+    }
+}
+
+/// An Emscripten installation, as pointed to by `EMSDK`.
+#[derive(Debug)]
+pub(crate) struct Emsdk {
+    /// The `upstream/emscripten` directory containing `emcc` and friends.
+    emscripten: PathBuf,
+}
+
+impl Emsdk {
+    pub(crate) fn from_root(root: &Path) -> Option<Self> {
+        let emscripten = root.join("upstream").join("emscripten");
+        emscripten.is_dir().then_some(Self { emscripten })
+    }
+
+    /// The path to `emcc`, `em++`, `emar` or `emranlib`. On Windows these are
+    /// batch files, which have to be run through `cmd /c`.
+    pub(crate) fn tool(&self, name: &str) -> Option<PathBuf> {
+        let path = if cfg!(windows) {
+            self.emscripten.join(format!("{name}.bat"))
+        } else {
+            self.emscripten.join(name)
+        };
+        path.exists().then_some(path)
+    }
+}
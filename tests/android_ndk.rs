@@ -0,0 +1,90 @@
+use std::env::consts::EXE_SUFFIX;
+use std::fs;
+use std::path::PathBuf;
+
+mod support;
+use crate::support::Test;
+
+/// Create a minimal fake NDK with the shim as its clang and llvm-ar.
+fn fake_ndk(test: &mut Test) -> PathBuf {
+    let root = test.fake_sdk(
+        "ndk",
+        "toolchains/llvm/prebuilt/test-host/bin",
+        &["clang", "clang++", "llvm-ar", "llvm-ranlib"],
+    );
+    fs::create_dir_all(root.join("meta")).unwrap();
+    fs::write(
+        root.join("source.properties"),
+        "Pkg.Desc = Android NDK\nPkg.Revision = 26.1.10909125\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("meta/platforms.json"),
+        "{\n  \"min\": 19,\n  \"max\": 34\n}\n",
+    )
+    .unwrap();
+
+    test.env.set("ANDROID_NDK_HOME", &root);
+    test.env.remove("ANDROID_NDK_ROOT");
+    test.env.remove("ANDROID_API_LEVEL");
+    root
+}
+
+#[test]
+fn android_ndk_home() {
+    let mut test = Test::new();
+    let root = fake_ndk(&mut test);
+    let bin = root.join("toolchains/llvm/prebuilt/test-host/bin");
+
+    let mut build = test.gcc();
+    build.target("aarch64-linux-android").file("foo.c");
+    let compiler = build.get_compiler();
+    assert_eq!(compiler.path(), bin.join(format!("clang{EXE_SUFFIX}")));
+    assert_eq!(
+        build.get_archiver().get_program(),
+        bin.join(format!("llvm-ar{EXE_SUFFIX}"))
+    );
+
+    build.compile("foo");
+    // The NDK supports API level 19, but 64-bit targets require 21.
+    test.cmd(0)
+        .must_have("--target=aarch64-linux-android21")
+        .must_not_have("--target=aarch64-linux-android");
+}
+
+#[test]
+fn android_api_level() {
+    let mut test = Test::new();
+    fake_ndk(&mut test);
+
+    test.gcc()
+        .target("armv7-linux-androideabi")
+        .file("foo.c")
+        .compile("foo");
+    test.cmd(0).must_have("--target=armv7a-linux-androideabi19");
+
+    test.env.set("ANDROID_API_LEVEL", "26");
+    let compiler = test.gcc().target("armv7-linux-androideabi").get_compiler();
+    assert!(compiler
+        .args()
+        .iter()
+        .any(|arg| arg == "--target=armv7a-linux-androideabi26"));
+
+    let compiler = test
+        .gcc()
+        .target("armv7-linux-androideabi")
+        .android_api_level(30)
+        .get_compiler();
+    assert!(compiler
+        .args()
+        .iter()
+        .any(|arg| arg == "--target=armv7a-linux-androideabi30"));
+
+    let err = test
+        .gcc()
+        .target("aarch64-linux-android")
+        .android_api_level(19)
+        .try_get_compiler()
+        .unwrap_err();
+    assert!(err.to_string().contains("the minimum is 21"), "{err}");
+}
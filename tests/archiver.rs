@@ -20,6 +20,10 @@ fn ar_env() {
 fn various() {
     let mut env = support::GlobalEnv::lock();
     env.remove("AR");
+    // Don't pick up the `llvm-ar` of an installed Android NDK.
+    env.remove("ANDROID_NDK_HOME");
+    env.remove("ANDROID_NDK_ROOT");
+    env.remove("ANDROID_HOME");
 
     let ar = get_ar_for_target("x86_64-unknown-linux-gnu");
     assert_eq!(ar, "ar");
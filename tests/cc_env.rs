@@ -159,3 +159,62 @@ fn env_var_alternatives_override() {
     let compiler = test.gcc().target("thumbv8m.main-none-eabi").get_compiler();
     assert_eq!(compiler.path(), Path::new(&compiler5));
 }
+
+#[test]
+fn compiler_from_linker() {
+    let mut test = Test::new();
+    test.shim("aarch64-none-linux-gnu-gcc")
+        .shim("aarch64-none-linux-gnu-g++")
+        .shim("aarch64-none-linux-gnu-ar");
+    let gcc = test.td.path().join("aarch64-none-linux-gnu-gcc");
+
+    test.env
+        .set("CARGO_TARGET_AARCH64_UNKNOWN_LINUX_GNU_LINKER", &gcc);
+    let compiler = test
+        .gcc()
+        .target("aarch64-unknown-linux-gnu")
+        .get_compiler();
+    assert_eq!(compiler.path(), gcc);
+    let compiler = test
+        .gcc()
+        .target("aarch64-unknown-linux-gnu")
+        .cpp(true)
+        .get_compiler();
+    assert_eq!(
+        compiler.path(),
+        test.td.path().join("aarch64-none-linux-gnu-g++")
+    );
+    let archiver = test
+        .gcc()
+        .target("aarch64-unknown-linux-gnu")
+        .get_archiver();
+    assert_eq!(
+        archiver.get_program(),
+        test.td.path().join("aarch64-none-linux-gnu-ar")
+    );
+
+    // `RUSTC_LINKER` only applies to `TARGET`.
+    test.env
+        .remove("CARGO_TARGET_AARCH64_UNKNOWN_LINUX_GNU_LINKER");
+    test.env.set("RUSTC_LINKER", &gcc);
+    test.env.set("TARGET", "aarch64-unknown-linux-gnu");
+    let compiler = test
+        .gcc()
+        .target("aarch64-unknown-linux-gnu")
+        .get_compiler();
+    assert_eq!(compiler.path(), gcc);
+    let compiler = test
+        .gcc()
+        .target("x86_64-unknown-linux-gnu")
+        .host("x86_64-unknown-linux-gnu")
+        .get_compiler();
+    assert_ne!(compiler.path(), gcc);
+
+    // `CC` takes precedence.
+    test.env.set("CC", "cc");
+    let compiler = test
+        .gcc()
+        .target("aarch64-unknown-linux-gnu")
+        .get_compiler();
+    assert_eq!(compiler.path(), Path::new("cc"));
+}
@@ -55,3 +55,48 @@ fn cflags_order() {
         .must_have_in_order("-Larbitrary2", "-Larbitrary3")
         .must_have_in_order("-Larbitrary3", "-Larbitrary4");
 }
+
+#[test]
+fn for_host_while_cross_compiling() {
+    let mut test = Test::gnu();
+    test.env.set("CC", "aarch64-linux-gnu-gcc");
+    test.env.set("CFLAGS", "-Ltarget-only");
+    test.env.set("HOST_CFLAGS", "-Lhost1");
+    test.env.set("CFLAGS_x86_64-unknown-linux-gnu", "-Lhost2");
+    test.env
+        .set("CARGO_ENCODED_RUSTFLAGS", "-Cforce-frame-pointers=true");
+    test.env.set("CARGO_CFG_TARGET_FEATURE", "crt-static");
+
+    test.gcc()
+        .target("aarch64-unknown-linux-gnu")
+        .host("x86_64-unknown-linux-gnu")
+        .for_host(true)
+        .file("foo.c")
+        .compile("foo");
+
+    test.cmd(0)
+        .must_have("-m64")
+        .must_have_in_order("-Lhost1", "-Lhost2")
+        .must_not_have("-Ltarget-only")
+        .must_not_have("-fno-omit-frame-pointer")
+        .must_not_have("-static");
+}
+
+#[test]
+fn for_host_target_applies_to_host() {
+    let mut test = Test::gnu();
+    test.env.set("CFLAGS", "-Lplain");
+    test.env
+        .set("CARGO_ENCODED_RUSTFLAGS", "-Cforce-frame-pointers=true");
+
+    test.gcc().for_host(true).file("foo.c").compile("foo");
+    test.cmd(0)
+        .must_have("-Lplain")
+        .must_have("-fno-omit-frame-pointer");
+
+    test.env.set("CARGO_TARGET_APPLIES_TO_HOST", "false");
+    test.gcc().for_host(true).file("foo.c").compile("foo");
+    test.cmd(2)
+        .must_not_have("-Lplain")
+        .must_not_have("-fno-omit-frame-pointer");
+}
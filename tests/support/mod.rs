@@ -47,6 +47,20 @@ impl Test {
         env.remove("CC");
         env.remove("CXX");
         env.remove("AR");
+        // Nor the compiler derived from the linker Cargo was configured with.
+        env.remove("RUSTC_LINKER");
+
+        // Tests don't expect an installed Android NDK, WASI SDK or Emscripten
+        // to be used.
+        env.remove("ANDROID_NDK_HOME");
+        env.remove("ANDROID_NDK_ROOT");
+        env.remove("ANDROID_HOME");
+        env.remove("WASI_SDK_PATH");
+        env.remove("WASI_SYSROOT");
+        env.remove("EMSDK");
+
+        // The shims don't produce real object files.
+        env.remove("CC_SANITY_CHECK");
 
         // Some tests check that a flag is *not* present.  These tests might fail if the flag is set in the
         // CFLAGS or CXXFLAGS environment variables.  This clears the CFLAGS and CXXFLAGS
@@ -109,6 +123,23 @@ impl Test {
         self
     }
 
+    /// Create a fake SDK such as the Android NDK at `root` in the test
+    /// directory, with shims for `tools` in its `bin` subdirectory, and
+    /// return the path of the SDK.
+    pub fn fake_sdk(&self, root: &str, bin: &str, tools: &[&str]) -> PathBuf {
+        let root = self.td.path().join(root);
+        let bin = root.join(bin);
+        fs::create_dir_all(&bin).unwrap();
+        for tool in tools {
+            fs::copy(
+                &self.gcc,
+                bin.join(format!("{tool}{}", env::consts::EXE_SUFFIX)),
+            )
+            .unwrap();
+        }
+        root
+    }
+
     pub fn gcc(&self) -> cc::Build {
         let mut cfg = cc::Build::new();
         let target = if self.msvc || self.msvc_autodetect {
@@ -0,0 +1,99 @@
+mod support;
+use crate::support::Test;
+
+// Apple targets use `-isysroot` instead.
+#[cfg(not(target_vendor = "apple"))]
+#[test]
+fn sysroot_builder() {
+    let test = Test::gnu();
+    test.gcc()
+        .sysroot("/opt/sysroot")
+        .file("foo.c")
+        .compile("foo");
+
+    test.cmd(0).must_have("--sysroot=/opt/sysroot");
+}
+
+#[cfg(not(target_vendor = "apple"))]
+#[test]
+fn sysroot_env() {
+    let mut test = Test::gnu();
+    test.env
+        .set("SYSROOT_x86_64-unknown-linux-gnu", "/opt/target");
+    test.gcc().file("foo.c").compile("foo");
+    test.cmd(0).must_have("--sysroot=/opt/target");
+
+    let compiler = test.gcc().sysroot("/opt/builder").get_compiler();
+    assert!(compiler
+        .cflags_env()
+        .into_string()
+        .unwrap()
+        .contains("--sysroot=/opt/builder"));
+}
+
+#[cfg(not(target_vendor = "apple"))]
+#[test]
+fn sysroot_plain_env_ignored() {
+    let mut test = Test::gnu();
+    test.env.set("SYSROOT", "/opt/unrelated");
+    let compiler = test.gcc().get_compiler();
+    assert!(!compiler
+        .cflags_env()
+        .into_string()
+        .unwrap()
+        .contains("--sysroot"));
+}
+
+#[cfg(not(any(windows, target_vendor = "apple")))]
+#[test]
+fn sysroot_flag_probe() {
+    let test = Test::gnu();
+    test.gcc()
+        .compiler(test.td.path().join("cc"))
+        .sysroot("/opt/sysroot")
+        .flag_if_supported("-Wall")
+        .file("foo.c")
+        .compile("foo");
+
+    // The flag probe runs first, then the actual compile.
+    test.cmd(0)
+        .must_have("--sysroot=/opt/sysroot")
+        .must_have("-Wall");
+    test.cmd(1)
+        .must_have("--sysroot=/opt/sysroot")
+        .must_have("-Wall");
+}
+
+#[cfg(not(target_vendor = "apple"))]
+#[test]
+fn sysroot_expand() {
+    let test = Test::gnu();
+    test.gcc().sysroot("/opt/sysroot").file("foo.c").expand();
+
+    test.cmd(0)
+        .must_have("-E")
+        .must_have("--sysroot=/opt/sysroot");
+}
+
+#[test]
+fn sysroot_wasm_linux() {
+    let test = Test::clang();
+    test.gcc()
+        .target("wasm32-wali-linux-musl")
+        .compiler("clang")
+        .sysroot("/opt/sysroot")
+        .file("foo.c")
+        .compile("foo");
+
+    let cmd = test.cmd(0);
+    cmd.must_have("--sysroot=/opt/sysroot");
+    assert_eq!(
+        cmd.args
+            .iter()
+            .filter(|arg| arg.starts_with("--sysroot="))
+            .count(),
+        1,
+        "{:?}",
+        cmd.args
+    );
+}
@@ -1132,3 +1132,634 @@ fn gnu_ar_probe_failure_no_warning() {
         stdout
     );
 }
+
+#[test]
+fn ti_flags() {
+    let test = Test::gnu();
+    test.shim("cl430")
+        .gcc()
+        .compiler("cl430")
+        .target("msp430-none-elf")
+        .std("gnu11")
+        .debug(true)
+        .warnings(true)
+        .warnings_into_errors(true)
+        .file("foo.c")
+        .compile("foo");
+
+    let cmd = test.cmd(0);
+    cmd.must_have("-O2")
+        .must_have("--symdebug:dwarf")
+        .must_have("--c11")
+        .must_have("--issue_remarks")
+        .must_have("--emit_warnings_as_errors")
+        .must_have("-c")
+        .must_not_have("-o")
+        .must_not_have("-g")
+        .must_not_have("-Wall")
+        .must_not_have("-ffunction-sections");
+    assert!(cmd.args.iter().any(|a| a.starts_with("--output_file=")));
+}
+
+#[test]
+fn nvhpc_flags() {
+    let test = Test::gnu();
+    test.shim("nvc")
+        .gcc()
+        .compiler("nvc")
+        .opt_level_str("s")
+        .warnings(true)
+        .extra_warnings(true)
+        .file("foo.c")
+        .compile("foo");
+
+    test.cmd(0)
+        .must_have("-O1")
+        .must_have("-fPIC")
+        .must_have("-Minform=inform")
+        .must_have("-o")
+        .must_not_have("-Os")
+        .must_not_have("-Wall")
+        .must_not_have("-Wextra")
+        .must_not_have("-ffunction-sections");
+}
+
+#[test]
+fn sanity_check_missing_cross_compiler() {
+    let test = Test::gnu();
+    for sanity_check in [true, false] {
+        // Don't pick up a cross compiler that happens to be installed.
+        let err = cc::Build::new()
+            .target("aarch64-unknown-linux-gnu")
+            .host("x86_64-unknown-linux-gnu")
+            .opt_level(2)
+            .debug(false)
+            .out_dir(test.td.path())
+            .env("PATH", test.td.path())
+            .sanity_check(sanity_check)
+            .file("foo.c")
+            .try_compile("foo")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("failed to find tool"), "{err}");
+        assert!(err.contains("target: aarch64-unknown-linux-gnu"), "{err}");
+        assert!(err.contains("compiler: aarch64-linux-gnu-gcc"), "{err}");
+        assert!(err.contains("`CC_aarch64-unknown-linux-gnu`"), "{err}");
+        if cfg!(target_os = "linux") {
+            assert!(err.contains("`gcc-aarch64-linux-gnu` package"), "{err}");
+        }
+    }
+}
+
+#[test]
+fn sanity_check_no_object() {
+    let test = Test::gnu();
+    let err = test
+        .gcc()
+        .sanity_check(true)
+        .file("foo.c")
+        .try_compile("foo")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("did not produce an object file"), "{err}");
+    assert!(err.contains("target: x86_64-unknown-linux-gnu"), "{err}");
+
+    test.cmd(0).must_have("-c").must_not_have("foo.c");
+    assert!(test
+        .cmd(0)
+        .args
+        .iter()
+        .any(|a| a.ends_with("sanity_check.c")));
+}
+
+#[test]
+fn target_info() {
+    let test = Test::gnu();
+
+    let target = test
+        .gcc()
+        .target("aarch64-unknown-linux-gnu")
+        .host("x86_64-unknown-linux-gnu")
+        .try_get_target()
+        .unwrap();
+    assert_eq!(target.rustc_target, "aarch64-unknown-linux-gnu");
+    assert_eq!(target.arch, "aarch64");
+    assert_eq!(target.llvm_target, "aarch64-unknown-linux-gnu");
+    assert!(target.is_cross_compile);
+
+    let host = test
+        .gcc()
+        .target("aarch64-unknown-linux-gnu")
+        .host("x86_64-unknown-linux-gnu")
+        .for_host(true)
+        .try_get_target()
+        .unwrap();
+    assert_eq!(host.rustc_target, "x86_64-unknown-linux-gnu");
+    assert!(!host.is_cross_compile);
+}
+
+#[test]
+fn custom_target_spec() {
+    let test = Test::gnu();
+    test.shim("clang");
+    let spec = test.td.path().join("x86_64-unknown-kernel.json");
+    std::fs::write(
+        &spec,
+        r#"{
+            "arch": "x86_64",
+            "cpu": "x86-64-v2",
+            "features": "-mmx,-sse,+soft-float",
+            "llvm-target": "x86_64-unknown-none-elf",
+            "relocation-model": "static",
+            "target-pointer-width": "64"
+        }"#,
+    )
+    .unwrap();
+    let target = spec.to_str().unwrap();
+
+    test.gcc()
+        .target(target)
+        .compiler("clang")
+        .file("foo.c")
+        .compile("foo");
+    test.cmd(0)
+        .must_have("--target=x86_64-unknown-none-elf")
+        .must_have("-march=x86-64-v2")
+        .must_have("-target-feature")
+        .must_have("-mmx")
+        .must_have("+soft-float")
+        .must_not_have("-fPIC");
+
+    test.gcc()
+        .target(target)
+        .compiler("cc")
+        .file("foo.c")
+        .compile("foo");
+    test.cmd(3)
+        .must_have("-march=x86-64-v2")
+        .must_have("-mno-mmx")
+        .must_have("-mno-sse")
+        .must_have("-msoft-float")
+        .must_not_have("-fPIC");
+}
+
+#[test]
+fn gnu_freestanding() {
+    let test = Test::gnu();
+    test.gcc().freestanding(true).file("foo.c").compile("foo");
+    test.cmd(0)
+        .must_have("-ffreestanding")
+        .must_have("-fno-builtin")
+        .must_have("-fno-stack-protector")
+        .must_have("-nostdlib")
+        .must_not_have("-fPIC");
+
+    test.gcc()
+        .freestanding(true)
+        .c_library(cc::CLibrary::Picolibc)
+        .pic(true)
+        .file("foo.c")
+        .compile("foo");
+    test.cmd(3)
+        .must_have("--specs=picolibc.specs")
+        .must_have("-fPIC")
+        .must_not_have("-nostdlib");
+}
+
+#[test]
+fn clang_freestanding_float_abi() {
+    let test = Test::clang();
+    test.gcc()
+        .target("thumbv7em-none-eabihf")
+        .compiler("clang")
+        .freestanding(true)
+        .file("foo.c")
+        .compile("foo");
+    test.cmd(0)
+        .must_have("--target=thumbv7em-none-eabihf")
+        .must_have("-ffreestanding")
+        .must_have("-mfloat-abi=hard")
+        .must_have("-mfpu=fpv4-sp-d16")
+        .must_have("-nostdlibinc");
+}
+
+#[test]
+fn gnu_mixed_languages() {
+    let mut test = Test::gnu();
+    test.env.set("CFLAGS", "-cflag");
+    test.env.set("CXXFLAGS", "-cxxflag");
+    test.gcc()
+        .mixed_languages(true)
+        .file("foo.c")
+        .file("bar.cpp")
+        .std("c11")
+        .language_std(cc::Language::Cpp, "c++17")
+        .language_flag(cc::Language::Cpp, "-fno-exceptions")
+        .compile("foo");
+
+    test.cmd(0)
+        .must_have("foo.c")
+        .must_have("-std=c11")
+        .must_have("-cflag")
+        .must_not_have("-cxxflag")
+        .must_not_have("-fno-exceptions");
+    test.cmd(1)
+        .must_have("bar.cpp")
+        .must_have("-std=c++17")
+        .must_have("-cxxflag")
+        .must_have("-fno-exceptions")
+        .must_not_have("-cflag");
+}
+
+#[test]
+fn gnu_file_with_options() {
+    let test = Test::gnu();
+    let mut avx2 = cc::FileOptions::new();
+    avx2.flag("-mavx2").define("KERNEL", "avx2").include("avx2");
+    let mut hot = cc::FileOptions::new();
+    hot.flag("-O3").remove_flag("-O2");
+    test.gcc()
+        .file("foo.c")
+        .file_with_options("kernel.c", &avx2)
+        .file_with_options("kernel.c", &hot)
+        .compile("foo");
+
+    test.cmd(0)
+        .must_have("-O2")
+        .must_not_have("-mavx2")
+        .must_not_have("-O3");
+    test.cmd(1)
+        .must_have("-mavx2")
+        .must_have("-DKERNEL=avx2")
+        .must_have_in_order("-I", "avx2")
+        .must_not_have("-O3");
+    test.cmd(2).must_have("-O3").must_not_have("-O2");
+
+    // Each variant of `kernel.c` gets its own object.
+    let objects = [test.cmd(1), test.cmd(2)].map(|cmd| {
+        let output = cmd.args.iter().position(|arg| arg == "-o").unwrap();
+        cmd.args[output + 1].clone()
+    });
+    assert_ne!(objects[0], objects[1]);
+}
+
+#[test]
+fn msvc_asm_file_with_options() {
+    let test = Test::msvc();
+    let mut options = cc::FileOptions::new();
+    options
+        .include("avx2")
+        .define("USE_AVX2", None)
+        .flag("-Xfile")
+        .remove_flag("-Xmsvc");
+    test.shim("ml64.exe")
+        .gcc()
+        .asm_flag("-Xmsvc")
+        .asm_flag("-Xboth")
+        .file_with_options("x86_64.asm", &options)
+        .compile("foo");
+
+    test.cmd(0)
+        .must_have("-nologo")
+        .must_have_in_order("-Xboth", "-Xfile")
+        .must_have_in_order("-I", "avx2")
+        .must_have("-DUSE_AVX2")
+        .must_not_have("-Xmsvc");
+}
+
+#[test]
+fn gnu_multiversion() {
+    let test = Test::gnu();
+    let mut kernel = cc::Multiversion::new("kernel.c");
+    kernel
+        .variant("avx2", Some("avx2"), &["-mavx2"])
+        .variant("sse42", None, &["-msse4.2"])
+        .function("int sum(const int *data, size_t len)")
+        .dispatcher(true);
+    test.gcc().multiversion(&kernel).compile("kernel");
+
+    test.cmd(0)
+        .must_have("kernel.c")
+        .must_have("-mavx2")
+        .must_have("-Dsum=sum_avx2");
+    test.cmd(1)
+        .must_have("kernel.c")
+        .must_have("-msse4.2")
+        .must_have("-Dsum=sum_sse42");
+    let dispatcher = std::fs::read_dir(test.td.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.to_str().unwrap().ends_with("-kernel_dispatch.c"))
+        .unwrap();
+    test.cmd(2).must_have(&dispatcher).must_not_have("-mavx2");
+
+    let dispatcher = std::fs::read_to_string(dispatcher).unwrap();
+    assert!(dispatcher.contains("return sum_avx2(data, len);"));
+}
+
+fn dispatched_kernel() -> cc::Multiversion {
+    let mut kernel = cc::Multiversion::new("kernel.c");
+    kernel
+        .variant("avx2", Some("avx2"), &["-mavx2"])
+        .function("int sum(const int *data, size_t len)")
+        .dispatcher(true);
+    kernel
+}
+
+#[test]
+fn msvc_multiversion_dispatcher_unsupported() {
+    let test = Test::msvc();
+    let err = test
+        .gcc()
+        .multiversion(&dispatched_kernel())
+        .try_compile("kernel")
+        .unwrap_err();
+    assert!(err.to_string().contains("needs GCC or Clang"), "{err}");
+}
+
+#[test]
+fn gnu_multiversion_dispatcher_non_x86() {
+    let kernel = dispatched_kernel();
+    let test = Test::gnu();
+    let err = test
+        .gcc()
+        .target("aarch64-unknown-linux-gnu")
+        .multiversion(&kernel)
+        .try_compile("kernel")
+        .unwrap_err();
+    assert!(err.to_string().contains("needs GCC or Clang"), "{err}");
+}
+
+#[test]
+fn gnu_precompiled_header() {
+    let test = Test::gnu();
+    test.gcc()
+        .cpp(true)
+        .file("foo.cpp")
+        .file("bar.S")
+        .file_with_options("avx2.cpp", cc::FileOptions::new().flag("-mavx2"))
+        .precompiled_header("big.h")
+        .compile("foo");
+
+    test.cmd(0)
+        .must_have_in_order("-x", "c++-header")
+        .must_have("-o");
+    let gch = test.cmd(0).args.last().unwrap().clone();
+    assert!(gch.ends_with("-big.h.gch"), "{gch}");
+    test.cmd(1)
+        .must_have("foo.cpp")
+        .must_have_in_order("-include", gch.strip_suffix(".gch").unwrap());
+    test.cmd(2).must_have("bar.S").must_not_have("-include");
+    // The precompiled header isn't valid with different flags.
+    let cmd = test.cmd(3);
+    cmd.must_have("avx2.cpp")
+        .must_not_have(gch.strip_suffix(".gch").unwrap());
+    let include = cmd.args.iter().position(|arg| arg == "-include").unwrap();
+    assert!(
+        std::path::Path::new(&cmd.args[include + 1]).ends_with("big.h"),
+        "{:?}",
+        cmd.args
+    );
+}
+
+#[test]
+fn msvc_precompiled_header() {
+    let test = Test::msvc();
+    test.gcc()
+        .file("foo.c")
+        .precompiled_header("big.h")
+        .compile("foo");
+
+    let cmd = test.cmd(0);
+    let create = cmd.args.iter().find(|arg| arg.starts_with("-Yc")).unwrap();
+    assert!(create.ends_with("-big.h"), "{create}");
+    test.cmd(1)
+        .must_have(format!("-Yu{}", &create[3..]))
+        .must_have(format!("-FI{}", &create[3..]));
+    // The object compiled along with the precompiled header is archived too.
+    assert!(test.cmd(2).args.iter().any(|arg| arg.ends_with("-big.obj")));
+}
+
+#[test]
+fn gnu_cpp_modules() {
+    let test = Test::gnu();
+    let main = test.td.path().join("main.cpp");
+    let math = test.td.path().join("math.cppm");
+    std::fs::write(&main, "import math;\nint main() {}\n").unwrap();
+    std::fs::write(&math, "export module math;\n").unwrap();
+    test.gcc()
+        .cpp(true)
+        .std("c++20")
+        .cpp_modules(true)
+        .file(&main)
+        .file(&math)
+        .compile("foo");
+
+    // Both files are scanned, then the module is compiled first.
+    test.cmd(0)
+        .must_have("-fdeps-format=p1689r5")
+        .must_have(&main);
+    test.cmd(1)
+        .must_have("-fdeps-format=p1689r5")
+        .must_have(&math);
+    let mapper = test.td.path().join("modules").join("gcc-module-mapper.txt");
+    let mapper_flag = format!("-fmodule-mapper={}", mapper.display());
+    test.cmd(2)
+        .must_have(&math)
+        .must_have_in_order("-x", "c++")
+        .must_have("-fmodules-ts")
+        .must_have(&mapper_flag);
+    test.cmd(3)
+        .must_have(&main)
+        .must_have("-fmodules-ts")
+        .must_have(&mapper_flag)
+        .must_not_have("-x");
+    assert_eq!(
+        std::fs::read_to_string(&mapper).unwrap(),
+        format!(
+            "math {}\n",
+            test.td.path().join("modules").join("math.gcm").display()
+        )
+    );
+}
+
+#[test]
+fn gnu_nasm() {
+    let test = Test::gnu();
+    test.shim("nasm")
+        .gcc()
+        .debug(true)
+        .include("inc")
+        .define("ARCH_X86_64", "1")
+        .define("PIC", None)
+        .asm_flag("-Xgnu")
+        .file("x86.nasm")
+        .compile("foo");
+
+    let format = if cfg!(target_os = "macos") {
+        "macho64"
+    } else {
+        "elf64"
+    };
+    test.cmd(0)
+        .must_have_in_order("-f", format)
+        .must_have_in_order("-I", "inc")
+        .must_have("-DARCH_X86_64=1")
+        .must_have("-DPIC")
+        .must_have("-g")
+        .must_have("-Xgnu")
+        .must_have("x86.nasm")
+        .must_not_have("-c");
+}
+
+#[test]
+fn gnu_nasm_file_with_options() {
+    let test = Test::gnu();
+    let mut options = cc::FileOptions::new();
+    options
+        .include("avx2")
+        .define("USE_AVX2", None)
+        .flag("-Xfile")
+        .remove_flag("-Xgnu");
+    test.shim("nasm")
+        .gcc()
+        .asm_flag("-Xgnu")
+        .asm_flag("-Xboth")
+        .file_with_options("avx2.nasm", &options)
+        .compile("foo");
+
+    test.cmd(0)
+        .must_have_in_order("-Xboth", "-Xfile")
+        .must_have_in_order("-I", "avx2")
+        .must_have("-DUSE_AVX2")
+        .must_have("-Xfile")
+        .must_not_have("-Xgnu");
+}
+
+#[test]
+fn msvc_nasm_with_yasm() {
+    let mut test = Test::msvc();
+    test.shim("yasm");
+    test.env.set("NASM", "yasm");
+    test.gcc()
+        .nasm(true)
+        .debug(true)
+        .file("x86.asm")
+        .compile("foo");
+
+    test.cmd(0)
+        .must_have_in_order("-f", "win64")
+        .must_have_in_order("-g", "cv8")
+        .must_have("x86.asm")
+        .must_not_have("-nologo");
+}
+
+#[test]
+fn gnu_objc() {
+    let test = Test::gnu();
+    test.gcc()
+        .file("foo.c")
+        .file("bar.m")
+        .file("baz.mm")
+        .std("c11")
+        .language_std(cc::Language::ObjCpp, "c++17")
+        .objc_exceptions(true)
+        .objc_runtime(cc::ObjcRuntime::GnuStep)
+        .compile("foo");
+
+    test.cmd(0)
+        .must_have("foo.c")
+        .must_not_have("-fobjc-exceptions")
+        .must_not_have("-fgnu-runtime");
+    test.cmd(1)
+        .must_have("bar.m")
+        .must_have("-std=c11")
+        .must_have("-fobjc-exceptions")
+        .must_have("-fgnu-runtime");
+    test.cmd(2)
+        .must_have("baz.mm")
+        .must_have("-std=c++17")
+        .must_not_have("-std=c11")
+        .must_have("-fgnu-runtime");
+}
+
+#[test]
+fn gnu_objc_without_runtime() {
+    let test = Test::gnu();
+    test.gcc()
+        .cpp(true)
+        .file("foo.cpp")
+        .file("bar.m")
+        .std("c++17")
+        .compile("foo");
+
+    // Without opting in, Objective-C files are compiled like the others.
+    test.cmd(1).must_have("bar.m").must_have("-std=c++17");
+}
+
+#[test]
+fn clang_objc_arc() {
+    let test = Test::clang();
+    test.gcc()
+        .compiler("clang")
+        .file("foo.m")
+        .objc_arc(true)
+        .objc_runtime(cc::ObjcRuntime::GnuStep)
+        .compile("foo");
+
+    test.cmd(0)
+        .must_have("-fobjc-arc")
+        .must_have("-fobjc-runtime=gnustep-2.0");
+}
+
+#[test]
+fn gnu_fortran() {
+    let mut test = Test::gnu();
+    test.shim("gfortran");
+    test.env.set("FFLAGS", "-fflag");
+    test.gcc()
+        .debug(true)
+        .include("inc")
+        .define("USE_BLAS", None)
+        .flag("-cflag")
+        .language_flag(cc::Language::Fortran, "-ffree-form")
+        .file("foo.c")
+        .file("dgemm.f")
+        .file("solver.F90")
+        .compile("foo");
+
+    test.cmd(0)
+        .must_have("foo.c")
+        .must_have("-cflag")
+        .must_not_have("-fflag")
+        .must_not_have("-ffree-form");
+    test.cmd(1)
+        .must_have("dgemm.f")
+        .must_have("-fflag")
+        .must_have("-ffree-form")
+        .must_not_have("-cflag")
+        .must_have("-g")
+        .must_have("-fPIC")
+        .must_not_have("-cpp");
+    test.cmd(2)
+        .must_have("solver.F90")
+        .must_have("-cpp")
+        .must_have_in_order("-I", "inc")
+        .must_have("-DUSE_BLAS");
+}
+
+#[test]
+fn clang_flang() {
+    let mut test = Test::clang();
+    test.shim("flang-new");
+    test.env.set("FC", "flang-new");
+    test.gcc()
+        .target("x86_64-unknown-linux-gnu")
+        .host("x86_64-unknown-linux-gnu")
+        .file("solver.f90")
+        .compile("foo");
+
+    test.cmd(0)
+        .must_have("solver.f90")
+        .must_have("--target=x86_64-unknown-linux-gnu");
+}
@@ -0,0 +1,147 @@
+use std::fs;
+use std::path::Path;
+
+mod support;
+use crate::support::Test;
+
+const TOOLCHAIN_FILE: &str = "\
+[constants]
+common_args = ['-DFROM_FILE', '-mcpu=cortex-a72']
+
+[binaries]
+c = 'my-gcc'
+cpp = 'my-g++'
+ar = 'my-ar'
+
+[built-in options]
+c_args = common_args + ['-DSPACED=\"a b\"']
+
+[properties]
+sys_root = '/opt/sysroot'
+needs_exe_wrapper = true
+";
+
+fn setup(test: &Test) -> std::path::PathBuf {
+    test.shim("my-gcc").shim("my-g++").shim("my-ar");
+    let path = test.td.path().join("cross.ini");
+    fs::write(&path, TOOLCHAIN_FILE).unwrap();
+    path
+}
+
+#[test]
+fn toolchain_file_builder() {
+    let test = Test::new();
+    let file = setup(&test);
+
+    let compiler = test.gcc().toolchain_file(&file).get_compiler();
+    assert_eq!(compiler.path(), Path::new("my-gcc"));
+
+    test.gcc()
+        .toolchain_file(&file)
+        .file("foo.c")
+        .compile("foo");
+    test.cmd(0)
+        .must_have("-DFROM_FILE")
+        .must_have("-mcpu=cortex-a72")
+        .must_have("-DSPACED=\"a b\"")
+        .must_have("--sysroot=/opt/sysroot")
+        .must_have_in_order("-DFROM_FILE", "-DSPACED=\"a b\"");
+}
+
+#[test]
+fn toolchain_file_env() {
+    let mut test = Test::new();
+    let file = setup(&test);
+    test.env.set("CC_TOOLCHAIN_FILE", &file);
+
+    let compiler = test.gcc().cpp(true).get_compiler();
+    assert_eq!(compiler.path(), Path::new("my-g++"));
+}
+
+#[test]
+fn toolchain_file_precedence() {
+    let mut test = Test::new();
+    let file = setup(&test);
+    test.shim("target-cc");
+
+    // Plain `CC` is overridden by the toolchain file...
+    test.env.set("CC", "cc");
+    let compiler = test.gcc().toolchain_file(&file).get_compiler();
+    assert_eq!(compiler.path(), Path::new("my-gcc"));
+
+    // ...but target-specific variables override the toolchain file.
+    test.env.set("CC_x86_64-unknown-linux-gnu", "target-cc");
+    test.env.set("CC_x86_64-apple-darwin", "target-cc");
+    let compiler = test.gcc().toolchain_file(&file).get_compiler();
+    assert_eq!(compiler.path(), Path::new("target-cc"));
+
+    // Flags from the toolchain file come after plain `CFLAGS`, and before
+    // target-specific ones.
+    test.env.set("CFLAGS", "-DPLAIN");
+    test.env.set("CFLAGS_x86_64-unknown-linux-gnu", "-DTARGET");
+    test.env.set("CFLAGS_x86_64-apple-darwin", "-DTARGET");
+    test.gcc()
+        .toolchain_file(&file)
+        .file("foo.c")
+        .compile("foo");
+    test.cmd(0)
+        .must_have_in_order("-DPLAIN", "-DFROM_FILE")
+        .must_have_in_order("-DFROM_FILE", "-DTARGET");
+}
+
+#[test]
+fn toolchain_file_errors() {
+    let test = Test::new();
+    let file = test.td.path().join("broken.ini");
+    fs::write(&file, "[binaries]\nc = missing + 'gcc'\n").unwrap();
+
+    let err = test
+        .gcc()
+        .toolchain_file(&file)
+        .try_get_compiler()
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("unknown constant `missing`"),
+        "{err}"
+    );
+
+    let err = test
+        .gcc()
+        .toolchain_file(test.td.path().join("does-not-exist.ini"))
+        .try_get_compiler()
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("failed to read toolchain file"),
+        "{err}"
+    );
+}
+
+#[test]
+fn toolchain_file_array_with_spaces() {
+    let test = Test::new();
+    let file = test.td.path().join("spaces.ini");
+    fs::write(
+        &file,
+        "[binaries]\nc = ['ccache', 'C:/Program Files/LLVM/bin/clang.exe']\n",
+    )
+    .unwrap();
+
+    let compiler = test.gcc().toolchain_file(&file).get_compiler();
+    assert_eq!(
+        compiler.path(),
+        Path::new("C:/Program Files/LLVM/bin/clang.exe")
+    );
+}
+
+#[test]
+fn toolchain_file_string_with_spaces() {
+    let test = Test::new();
+    fs::create_dir(test.td.path().join("My Tools")).unwrap();
+    test.shim("My Tools/my-gcc");
+    let gcc = test.td.path().join("My Tools").join("my-gcc");
+    let file = test.td.path().join("spaces.ini");
+    fs::write(&file, format!("[binaries]\nc = '{}'\n", gcc.display())).unwrap();
+
+    let compiler = test.gcc().toolchain_file(&file).get_compiler();
+    assert_eq!(compiler.path(), gcc);
+}
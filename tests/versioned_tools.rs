@@ -0,0 +1,65 @@
+#![allow(clippy::disallowed_methods)]
+
+use std::env;
+use std::path::Path;
+
+mod support;
+use crate::support::Test;
+
+/// Put the test's shims first in the process' `PATH`, which is where
+/// versioned tools are searched for.
+fn prepend_path(test: &mut Test) {
+    let mut path = env::split_paths(&env::var_os("PATH").unwrap_or_default()).collect::<Vec<_>>();
+    path.insert(0, test.td.path().to_owned());
+    let path = env::join_paths(path).unwrap();
+    test.env.set("PATH", path);
+}
+
+#[test]
+fn versioned_cross_gcc() {
+    let mut test = Test::new();
+    test.shim("aarch64-linux-gnu-gcc-10")
+        .shim("aarch64-linux-gnu-gcc-12")
+        .shim("aarch64-linux-gnu-gcc-ar-12");
+    prepend_path(&mut test);
+    test.env.remove("CROSS_COMPILE");
+
+    let compiler = test
+        .gcc()
+        .target("aarch64-unknown-linux-gnu")
+        .get_compiler();
+    assert_eq!(compiler.path(), Path::new("aarch64-linux-gnu-gcc-12"));
+}
+
+#[test]
+fn unversioned_cross_gcc_preferred() {
+    let mut test = Test::new();
+    test.shim("aarch64-linux-gnu-gcc")
+        .shim("aarch64-linux-gnu-gcc-12");
+    prepend_path(&mut test);
+    test.env.remove("CROSS_COMPILE");
+
+    let compiler = test
+        .gcc()
+        .target("aarch64-unknown-linux-gnu")
+        .get_compiler();
+    assert_eq!(compiler.path(), Path::new("aarch64-linux-gnu-gcc"));
+}
+
+#[test]
+fn versioned_clang_with_llvm_ar() {
+    let mut test = Test::new();
+    test.shim("clang-9")
+        .shim("clang-17")
+        .shim("clang-16")
+        .shim("llvm-ar-17")
+        .shim("llvm-ranlib-17");
+    // Make sure that an unversioned `clang` on the system isn't found.
+    test.env.set("PATH", test.td.path());
+
+    let mut build = test.gcc();
+    build.target("wasm32-unknown-unknown");
+    assert_eq!(build.get_compiler().path(), Path::new("clang-17"));
+    assert_eq!(build.get_archiver().get_program(), "llvm-ar-17");
+    assert_eq!(build.get_ranlib().get_program(), "llvm-ranlib-17");
+}
@@ -0,0 +1,89 @@
+use std::env::consts::EXE_SUFFIX;
+use std::fs;
+use std::path::PathBuf;
+
+mod support;
+use crate::support::Test;
+
+/// Create a minimal fake WASI SDK with the shim as its clang and llvm-ar.
+fn fake_wasi_sdk(test: &mut Test) -> PathBuf {
+    let root = test.fake_sdk(
+        "wasi-sdk",
+        "bin",
+        &["wasm32-wasip1-clang", "clang", "llvm-ar"],
+    );
+    fs::create_dir_all(root.join("share/wasi-sysroot")).unwrap();
+
+    test.env.set("WASI_SDK_PATH", &root);
+    root
+}
+
+#[test]
+fn wasi_sdk_path() {
+    let mut test = Test::new();
+    let root = fake_wasi_sdk(&mut test);
+
+    let mut build = test.gcc();
+    build.target("wasm32-wasip1").file("foo.c");
+    assert_eq!(
+        build.get_compiler().path(),
+        root.join(format!("bin/wasm32-wasip1-clang{EXE_SUFFIX}"))
+    );
+    assert_eq!(
+        build.get_archiver().get_program(),
+        root.join(format!("bin/llvm-ar{EXE_SUFFIX}"))
+    );
+
+    // Without a target-specific wrapper, the SDK's plain clang is used.
+    assert_eq!(
+        test.gcc().target("wasm32-wasip2").get_compiler().path(),
+        root.join(format!("bin/clang{EXE_SUFFIX}"))
+    );
+
+    build.compile("foo");
+    let sysroot = root.join("share/wasi-sysroot");
+    test.cmd(0)
+        .must_have(format!("--sysroot={}", sysroot.display()))
+        .must_not_have("-pthread")
+        .must_not_have("-fwasm-exceptions");
+}
+
+#[test]
+fn wasi_target_features() {
+    let mut test = Test::new();
+    fake_wasi_sdk(&mut test);
+    test.env.set(
+        "CARGO_CFG_TARGET_FEATURE",
+        "atomics,bulk-memory,exception-handling",
+    );
+
+    test.gcc()
+        .target("wasm32-wasip1")
+        .file("foo.c")
+        .compile("foo");
+    test.cmd(0)
+        .must_have("-pthread")
+        .must_have("-fwasm-exceptions");
+}
+
+#[test]
+#[cfg(not(windows))]
+fn emsdk() {
+    let mut test = Test::new();
+    let root = test.fake_sdk("emsdk", "upstream/emscripten", &["emcc", "em++", "emar"]);
+    let emscripten = root.join("upstream/emscripten");
+    test.env.set("EMSDK", &root);
+
+    let mut build = test.gcc();
+    build.target("wasm32-unknown-emscripten").file("foo.c");
+    assert_eq!(build.get_compiler().path(), emscripten.join("emcc"));
+    assert_eq!(build.get_archiver().get_program(), emscripten.join("emar"));
+    assert_eq!(
+        test.gcc()
+            .target("wasm32-unknown-emscripten")
+            .cpp(true)
+            .get_compiler()
+            .path(),
+        emscripten.join("em++")
+    );
+}
@@ -0,0 +1,55 @@
+mod support;
+use crate::support::Test;
+
+// `Test::gcc` uses an Apple target on macOS.
+#[cfg(not(target_vendor = "apple"))]
+#[test]
+fn zig_cc() {
+    let test = Test::new();
+    test.shim("zig");
+    test.gcc().zig(true).file("foo.c").compile("foo");
+
+    test.cmd(0)
+        .must_have_in_order("cc", "-target")
+        .must_have_in_order("-target", "x86_64-linux-gnu.2.17")
+        .must_have("foo.c")
+        .must_not_have("--target=x86_64-unknown-linux-gnu");
+    test.cmd(1).must_have_in_order("ar", "cqD");
+}
+
+#[cfg(not(target_vendor = "apple"))]
+#[test]
+fn zig_cxx() {
+    let test = Test::new();
+    test.shim("zig");
+    test.gcc()
+        .zig(true)
+        .cpp(true)
+        .cpp_link_stdlib(None)
+        .file("foo.cpp")
+        .compile("foo");
+
+    test.cmd(0)
+        .must_have("c++")
+        .must_not_have("cc")
+        .must_have("x86_64-linux-gnu.2.17");
+}
+
+#[cfg(not(target_vendor = "apple"))]
+#[test]
+fn zig_env() {
+    let mut test = Test::new();
+    test.shim("zig");
+    test.env.set("CC_USE_ZIG", "1");
+    test.env.set("CC_ZIG_GLIBC_VERSION", "2.31");
+    // `CC` is ignored in favour of Zig.
+    test.env.set("CC", "gcc");
+
+    let compiler = test.gcc().get_compiler();
+    assert_eq!(compiler.path(), std::path::Path::new("zig"));
+    test.gcc().file("foo.c").compile("foo");
+    test.cmd(0).must_have("x86_64-linux-gnu.2.31");
+
+    let compiler = test.gcc().zig(false).get_compiler();
+    assert_eq!(compiler.path(), std::path::Path::new("gcc"));
+}